@@ -0,0 +1,134 @@
+//! `--bell`, the terminal bell at chosen moments of a run.
+//!
+//! The tui reports each generation as `BellEvents`, `BellPolicy` decides whether
+//! it's worth a bell and keeps them at least a second apart.
+
+use std::time::{Duration, Instant};
+
+/// bells closer together than this are dropped
+const MIN_GAP: Duration = Duration::from_secs(1);
+
+// BellOn {{{
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BellOn {
+    #[default]
+    Off,
+    /// the last cells dying
+    OnExtinction,
+    /// the cells starting to repeat, still lifes included
+    OnPeriod,
+    /// every 100th generation
+    #[clap(name = "every-100")]
+    Every100,
+}
+// BellOn }}}
+
+// BellPolicy {{{
+/// What a generation did, as far as bells care
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BellEvents {
+    pub generation: u64,
+    pub population: usize,
+    /// cells that died this generation
+    pub deaths: usize,
+    /// only looked for with `BellOn::OnPeriod`
+    pub period: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BellPolicy {
+    pub on: BellOn,
+    last: Option<Instant>,
+    /// the previous generation, so only the start of a repeat rings
+    previous: BellEvents,
+}
+
+impl BellPolicy {
+    pub fn new(on: BellOn) -> Self {
+        Self {
+            on,
+            last: None,
+            previous: BellEvents::default(),
+        }
+    }
+
+    /// Whether to ring for `events`. A bell under a second after the last is dropped
+    pub fn ring(&mut self, events: BellEvents, now: Instant) -> bool {
+        let wanted = match self.on {
+            BellOn::Off => false,
+            BellOn::OnExtinction => events.population == 0 && events.deaths > 0,
+            BellOn::OnPeriod => events.period.is_some() && self.previous.period.is_none(),
+            BellOn::Every100 => events.generation > 0 && events.generation.is_multiple_of(100),
+        };
+        self.previous = events;
+        if !wanted || self.last.is_some_and(|l| now.duration_since(l) < MIN_GAP) {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+}
+// BellPolicy }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(generation: u64, population: usize, period: Option<usize>) -> BellEvents {
+        BellEvents {
+            generation,
+            population,
+            deaths: 1,
+            period,
+        }
+    }
+
+    #[test]
+    fn matching() {
+        let now = Instant::now();
+        let later = |n: u64| now + Duration::from_secs(n * 2);
+
+        let mut off = BellPolicy::new(BellOn::Off);
+        assert!(!off.ring(events(100, 0, Some(1)), now));
+
+        let mut extinction = BellPolicy::new(BellOn::OnExtinction);
+        let rang: Vec<bool> = [(5, 1), (0, 5), (0, 0), (3, 0), (0, 3)]
+            .iter()
+            .enumerate()
+            .map(|(n, (population, deaths))| {
+                let events = BellEvents {
+                    deaths: *deaths,
+                    ..events(n as u64, *population, None)
+                };
+                extinction.ring(events, later(n as u64))
+            })
+            .collect();
+        assert_eq!(rang, [false, true, false, false, true]);
+
+        let mut period = BellPolicy::new(BellOn::OnPeriod);
+        let rang: Vec<bool> = [None, Some(2), Some(2), None, Some(1)]
+            .iter()
+            .enumerate()
+            .map(|(n, p)| period.ring(events(n as u64, 9, *p), later(n as u64)))
+            .collect();
+        assert_eq!(rang, [false, true, false, false, true]);
+
+        let mut every = BellPolicy::new(BellOn::Every100);
+        let rang: Vec<u64> = (0..=300)
+            .filter(|n| every.ring(events(*n, 9, None), later(*n)))
+            .collect();
+        assert_eq!(rang, [100, 200, 300]);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let now = Instant::now();
+        let ms = Duration::from_millis;
+        let mut every = BellPolicy::new(BellOn::Every100);
+        // 100 generations every 300ms
+        let rang: Vec<u64> = (1..=10)
+            .filter(|n| every.ring(events(n * 100, 9, None), now + ms(n * 300)))
+            .collect();
+        assert_eq!(rang, [1, 5, 9]);
+    }
+}
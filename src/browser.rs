@@ -0,0 +1,222 @@
+//! The directory listing behind `:load` and `:save` without a path.
+//!
+//! Only reads directories, anything it can't read becomes a message instead of an error.
+
+use super::formats;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// a directory past this many entries only lists the first ones read
+pub const MAX_ENTRIES: usize = 500;
+
+// Entry {{{
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// lossy for names that aren't UTF-8, `path` keeps the real one
+    pub name: String,
+    pub path: PathBuf,
+    pub dir: bool,
+    /// has one of `formats::EXTENSIONS`
+    pub pattern: bool,
+}
+// Entry }}}
+
+// Browser {{{
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Purpose {
+    Load,
+    Save,
+}
+
+/// What Enter did
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Choice {
+    /// moved into a directory, still browsing
+    Descended,
+    File(PathBuf),
+    Nothing,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Browser {
+    pub purpose: Purpose,
+    pub dir: PathBuf,
+    /// directories first, then by name
+    pub entries: Vec<Entry>,
+    /// more entries than `MAX_ENTRIES`
+    pub truncated: bool,
+    /// why the directory couldn't be listed
+    pub error: Option<String>,
+    /// typed text, narrowing the list. The new file's name when saving
+    pub filter: String,
+    /// index into `visible`
+    pub selected: usize,
+}
+
+impl Browser {
+    pub fn open(dir: &Path, purpose: Purpose) -> Self {
+        let mut result = Self {
+            purpose,
+            dir: dir.to_path_buf(),
+            entries: Vec::new(),
+            truncated: false,
+            error: None,
+            filter: String::new(),
+            selected: 0,
+        };
+        result.read();
+        result
+    }
+
+    fn read(&mut self) {
+        self.entries.clear();
+        self.truncated = false;
+        self.error = None;
+        self.filter.clear();
+        self.selected = 0;
+        let listing = match fs::read_dir(&self.dir) {
+            Ok(listing) => listing,
+            Err(e) => {
+                self.error = Some(format!("could not read {}: {}", self.dir.display(), e));
+                return;
+            }
+        };
+        for entry in listing.filter_map(|e| e.ok()) {
+            if self.entries.len() == MAX_ENTRIES {
+                self.truncated = true;
+                break;
+            }
+            let path = entry.path();
+            // follows links, so a link to a directory can be entered
+            let dir = path.is_dir();
+            self.entries.push(Entry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                pattern: !dir && formats::extension(&path).is_some(),
+                path,
+                dir,
+            })
+        }
+        self.entries
+            .sort_by(|a, b| b.dir.cmp(&a.dir).then_with(|| a.name.cmp(&b.name)));
+    }
+
+    /// Entries whose names hold the filter, ignoring case
+    pub fn visible(&self) -> Vec<&Entry> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|e| e.name.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last)
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        self.filter.push(c);
+        self.selected = 0
+    }
+
+    /// Removes a typed char, or goes up a directory once there are none
+    pub fn backspace(&mut self) {
+        if self.filter.pop().is_some() {
+            self.selected = 0
+        } else if let Some(parent) = self.dir.parent() {
+            self.dir = parent.to_path_buf();
+            self.read()
+        }
+    }
+
+    /// Enters the highlighted directory or picks a file. Saving picks the typed name
+    /// over the highlighted file
+    pub fn enter(&mut self) -> Choice {
+        let highlighted = self.visible().get(self.selected).map(|e| (*e).clone());
+        match highlighted {
+            Some(entry) if entry.dir => {
+                self.dir = entry.path;
+                self.read();
+                Choice::Descended
+            }
+            _ if self.purpose == Purpose::Save && !self.filter.is_empty() => {
+                Choice::File(self.dir.join(&self.filter))
+            }
+            Some(entry) => Choice::File(entry.path),
+            None => Choice::Nothing,
+        }
+    }
+}
+// Browser }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browse() {
+        let dir = std::env::temp_dir().join(format!("ti_browser_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("b.rle"), "").unwrap();
+        fs::write(dir.join("a.txt"), "").unwrap();
+        fs::write(dir.join("sub/glider.cells"), "").unwrap();
+
+        let mut browser = Browser::open(&dir, Purpose::Load);
+        let names: Vec<&str> = browser.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["sub", "a.txt", "b.rle"]);
+        assert!(browser.entries[2].pattern && !browser.entries[1].pattern);
+
+        browser.move_by(5);
+        assert_eq!(browser.selected, 2);
+        "RL".chars().for_each(|c| browser.type_char(c));
+        assert_eq!(browser.visible().len(), 1);
+        assert_eq!(browser.enter(), Choice::File(dir.join("b.rle")));
+
+        browser.backspace();
+        browser.backspace();
+        assert_eq!(browser.visible().len(), 3);
+        assert_eq!(browser.enter(), Choice::Descended);
+        assert_eq!(browser.dir, dir.join("sub"));
+        browser.backspace();
+        assert_eq!(browser.dir, dir);
+
+        let mut save = Browser::open(&dir, Purpose::Save);
+        "b.rl".chars().for_each(|c| save.type_char(c));
+        assert_eq!(save.enter(), Choice::File(dir.join("b.rl")));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let missing = Browser::open(&dir, Purpose::Load);
+        assert!(missing.error.is_some());
+        assert_eq!(missing.clone().enter(), Choice::Nothing);
+    }
+
+    #[test]
+    fn huge() {
+        let dir = std::env::temp_dir().join(format!("ti_browser_huge_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for n in 0..MAX_ENTRIES + 3 {
+            fs::write(dir.join(format!("{}.lif", n)), "").unwrap();
+        }
+        let browser = Browser::open(&dir, Purpose::Load);
+        assert_eq!(browser.entries.len(), MAX_ENTRIES);
+        assert!(browser.truncated);
+        fs::remove_dir_all(&dir).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let dir = std::env::temp_dir().join(format!("ti_browser_utf_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let name = std::ffi::OsStr::from_bytes(b"bad\xff.rle");
+            fs::write(dir.join(name), "").unwrap();
+            let mut browser = Browser::open(&dir, Purpose::Load);
+            assert_eq!(browser.entries[0].name, "bad\u{fffd}.rle");
+            assert_eq!(browser.enter(), Choice::File(dir.join(name)));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
@@ -1,147 +1,3439 @@
-pub type Row = Vec<u8>;
-pub type Grid = Vec<Row>;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+use super::color::ColorPalette;
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "generic-cells")]
+mod generic;
+pub mod reference;
+
+/// How far `CellOpts::radius` may reach. Past 1 needs the `large-neighborhood` feature,
+/// which grows every rule to cover the counts a 5×5 square can give
+#[cfg(not(feature = "large-neighborhood"))]
+pub const MAX_RADIUS: u8 = 1;
+#[cfg(feature = "large-neighborhood")]
+pub const MAX_RADIUS: u8 = 2;
+
+/// Neighbor counts a rule has an entry for, 0 through everything within `MAX_RADIUS`
+pub const COUNTS: usize = (2 * MAX_RADIUS as usize + 1).pow(2);
+
+// Cell {{{
+/// What a grid can hold. The default is dead, anything above it alive.
+/// `PartialOrd` rather than `Ord` so `f32` gradients fit
+pub trait Cell: Copy + PartialOrd + Default {
+    /// One life more, capped at `life`
+    fn grow(self, life: Self) -> Self;
+    /// One life less, never below dead
+    fn decay(self) -> Self;
+
+    fn alive(self) -> bool {
+        self > Self::default()
+    }
+}
+
+impl Cell for u8 {
+    fn grow(self, life: Self) -> Self {
+        self.saturating_add(1).min(life)
+    }
+
+    fn decay(self) -> Self {
+        self.saturating_sub(1)
+    }
+}
+// Cell }}}
+
+pub type Row<C = u8> = Vec<C>;
+
+/// Rows of cells. Derefs to the rows underneath
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Grid<C = u8>(pub Vec<Row<C>>);
+
+impl<C> std::ops::Deref for Grid<C> {
+    type Target = Vec<Row<C>>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C> std::ops::DerefMut for Grid<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<C> From<Vec<Row<C>>> for Grid<C> {
+    fn from(rows: Vec<Row<C>>) -> Self {
+        Self(rows)
+    }
+}
+
+impl<C> FromIterator<Row<C>> for Grid<C> {
+    fn from_iter<I: IntoIterator<Item = Row<C>>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<C: PartialEq> PartialEq<Vec<Row<C>>> for Grid<C> {
+    fn eq(&self, other: &Vec<Row<C>>) -> bool {
+        &self.0 == other
+    }
+}
+
+impl<C: Cell> Grid<C> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<C> {
+        self.get(y).and_then(|r| r.get(x)).cloned()
+    }
+
+    /// Crops or pads with dead cells to `x`×`y`
+    pub fn resize(&mut self, x: usize, y: usize) {
+        if self.len() < y {
+            let missing = y - self.len();
+            self.extend((0..missing).map(|_| vec![C::default(); x]))
+        } else if self.len() > y {
+            self.truncate(y)
+        }
+
+        assert_eq!(self.len(), y);
+
+        for row in self.iter_mut() {
+            row.resize(x, C::default());
+            assert_eq!(row.len(), x);
+        }
+    }
+
+    /// Writes the next generation of these cells into `to`, which must be the same size.
+    /// `life` stands in for `opts.life` so it can go past a `u8`
+    /// Returns how many cells are alive after
+    pub fn advance_with(&self, to: &mut Grid<C>, opts: CellOpts, life: C) -> usize {
+        self.advance_masked(to, opts, life, None, false).population
+    }
+
+    /// `advance_with`, except cells flagged in the row major `frozen` are copied through.
+    /// With `wrap` the edges join, see `neighbors_wrapped`.
+    /// Returns the cells counted as they were written, leaving the generation and timing
+    pub fn advance_masked(
+        &self,
+        to: &mut Grid<C>,
+        opts: CellOpts,
+        life: C,
+        frozen: Option<&[bool]>,
+        wrap: bool,
+    ) -> StepInfo {
+        // sanity checks. too many?
+        assert_eq!(self.len(), to.len());
+        assert_eq!(self.first().map(|r| r.len()), to.first().map(|r| r.len()));
+        assert_eq!(self.last().map(|r| r.len()), to.last().map(|r| r.len()));
+        let w = self.first().map(|r| r.len()).unwrap_or(0);
+        if let Some(frozen) = frozen {
+            assert_eq!(frozen.len(), w * self.len());
+        }
+
+        let counts =
+            |(x, y): (usize, usize)| self.get_cell(x, y).is_some_and(|c| opts.counts(c, life));
+
+        let mut info = StepInfo::default();
+        for (y, row) in to.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let current = if self[y][x] > life { life } else { self[y][x] };
+                *cell = if frozen.is_some_and(|f| f[y * w + x]) {
+                    current
+                } else {
+                    let neighbors = if wrap {
+                        neighborhood_wrapped(
+                            x,
+                            y,
+                            self[y].len(),
+                            self.len(),
+                            opts.shape(),
+                            opts.radius,
+                        )
+                        .filter(|p| counts(*p))
+                        .count()
+                    } else {
+                        neighborhood(x, y, opts.shape(), opts.radius)
+                            .filter(|p| counts(*p))
+                            .count()
+                    };
+                    opts.next(current, neighbors, life)
+                };
+                info.tally(self[y][x], *cell)
+            }
+        }
+        info
+    }
+}
+
+impl Grid {
+    /// Writes the next generation of these cells into `to`, which must be the same size
+    pub fn advance(&self, to: &mut Grid, opts: CellOpts) {
+        self.advance_with(to, opts, opts.life);
+    }
+
+    /// `advance_masked` for a life of 1, where cells are only ever dead or alive.
+    /// Same results, without the clamping or the per neighbor bounds checks
+    pub fn advance_binary(
+        &self,
+        to: &mut Grid,
+        opts: CellOpts,
+        frozen: Option<&[bool]>,
+        wrap: bool,
+    ) -> StepInfo {
+        assert_eq!(self.len(), to.len());
+        // only looks one cell out
+        if opts.radius != 1 {
+            return self.advance_masked(to, opts, 1, frozen, wrap);
+        }
+        let w = self.first().map(|r| r.len()).unwrap_or(0);
+        if let Some(frozen) = frozen {
+            assert_eq!(frozen.len(), w * self.len());
+        }
+        let at = |row: &Row, x: usize| row.get(x).is_some_and(|c| *c != 0) as usize;
+
+        let mut info = StepInfo::default();
+        for (y, out) in to.iter_mut().enumerate() {
+            let row = &self[y];
+            assert_eq!(row.len(), out.len());
+            let h = self.len();
+            let (above, below) = if wrap {
+                (Some(&self[(y + h - 1) % h]), Some(&self[(y + 1) % h]))
+            } else {
+                (y.checked_sub(1).map(|y| &self[y]), self.get(y + 1))
+            };
+            for (x, cell) in out.iter_mut().enumerate() {
+                let alive = row[x] != 0;
+                *cell = if frozen.is_some_and(|f| f[y * w + x]) {
+                    alive as u8
+                } else {
+                    let (left, right) = if wrap {
+                        ((x + w - 1) % w, (x + 1) % w)
+                    } else {
+                        (x.wrapping_sub(1), x + 1)
+                    };
+                    let mut n = 0;
+                    if opts.polars {
+                        n += at(row, left) + at(row, right)
+                    }
+                    for r in [above, below].into_iter().flatten() {
+                        if opts.polars {
+                            n += at(r, x)
+                        }
+                        if opts.corners {
+                            n += at(r, left) + at(r, right)
+                        }
+                    }
+                    debug_assert!(n <= 8, "{} neighbors", n);
+                    let n = n.min(8);
+                    (opts.grow[n] || (alive && opts.survive[n])) as u8
+                };
+                info.tally(row[x], *cell)
+            }
+        }
+        info
+    }
+
+    /// Row major RGBA pixels, each cell a `cell_size` square, as `image::ImageBuffer::from_raw`
+    /// takes them. Cells past `opts.life` are colored as full life
+    pub fn to_image_data(
+        &self,
+        opts: &CellOpts,
+        cell_size: u32,
+        palette: &ColorPalette,
+    ) -> Vec<u8> {
+        let size = cell_size as usize;
+        let w = self.first().map(|r| r.len()).unwrap_or(0);
+        let mut result = Vec::with_capacity(w * size * self.len() * size * 4);
+        for row in self.iter() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|c| {
+                    let (r, g, b, a) = palette.get((*c).min(opts.life));
+                    [r, g, b, a].repeat(size)
+                })
+                .collect();
+            for _ in 0..size {
+                result.extend_from_slice(&line)
+            }
+        }
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct CellOpts {
+    /// the 4 diagonal neighbors count
     pub corners: bool,
+    /// the 4 neighbors sharing a side count. With `corners` that's the Moore neighborhood,
+    /// without it von Neumann's. Corners alone are diagonal only, and neither counts nothing
+    pub polars: bool,
+    /// how many cells out the neighborhood reaches, 1 for the 3×3 square around a cell.
+    /// Polars are the cells in line with it, corners the rest. Up to `MAX_RADIUS`
+    pub radius: u8,
     pub life: u8,
-    pub grow: [bool; 9],
-    pub die: [bool; 9],
+    /// neighbor counts a cell gains life at, B in rulestrings
+    pub grow: [bool; COUNTS],
+    /// neighbor counts a cell keeps its life at, S in rulestrings. It loses 1 at any other
+    pub survive: [bool; COUNTS],
+    /// only neighbors at full life count, so decaying cells show but don't take part,
+    /// as in Generations rules like Brian's Brain. /M in rulestrings
+    pub mature_neighbors_only: bool,
 }
 
-#[derive(Clone, Debug)]
-pub struct Game {
-    g1: Grid,
-    g2: Grid,
-    switch: bool,
-    opts: CellOpts,
+/// The rule with no `-g` or `-d`: nothing born, everything surviving at a life of 1
+impl Default for CellOpts {
+    fn default() -> Self {
+        Self {
+            corners: true,
+            polars: true,
+            radius: 1,
+            life: 1,
+            grow: [false; COUNTS],
+            survive: [true; COUNTS],
+            mature_neighbors_only: false,
+        }
+    }
 }
 
-impl Game {
-    pub fn new(opts: CellOpts) -> Self {
-        Self {
-            g1: Grid::new(),
-            g2: Grid::new(),
-            switch: false,
-            opts,
+/// B3/S23 on the Moore neighborhood, what most tests run
+#[cfg(test)]
+pub(crate) fn conway() -> CellOpts {
+    let mut opts = CellOpts {
+        survive: [false; COUNTS],
+        ..Default::default()
+    };
+    opts.grow[3] = true;
+    opts.survive[2] = true;
+    opts.survive[3] = true;
+    opts
+}
+
+impl CellOpts {
+    /// What a cell at `current` becomes with `neighbors` live around it, `life` standing
+    /// in for `self.life`. Every advance but the binary fast path goes through here
+    pub fn next<C: Cell>(&self, current: C, neighbors: usize, life: C) -> C {
+        // a bad count shouldn't index past the rule
+        debug_assert!(neighbors < COUNTS, "{} neighbors", neighbors);
+        let neighbors = neighbors.min(COUNTS - 1);
+        if self.grow[neighbors] {
+            current.grow(life)
+        } else if !self.survive[neighbors] {
+            current.decay()
+        } else {
+            current
         }
     }
 
-    pub fn advance(&mut self) {
-        if !self.switch {
-            advance(&self.g1, &mut self.g2, self.opts)
+    /// Whether a neighbor at `cell` counts toward the rule, `life` standing in for
+    /// `self.life`. Any live one does, or with /M only those at full life. Cells past life
+    /// count as at it, like they're clamped to it
+    pub fn counts<C: Cell>(&self, cell: C, life: C) -> bool {
+        if self.mature_neighbors_only {
+            cell >= life
         } else {
-            advance(&self.g2, &mut self.g1, self.opts)
+            cell.alive()
         }
-        self.switch = !self.switch
     }
 
-    pub fn resize(&mut self, x: usize, y: usize) {
-        resize(&mut self.g1, x, y);
-        resize(&mut self.g2, x, y);
+    /// The old inverted `survive`, counts a cell loses life at
+    #[deprecated(note = "use survive, die[n] is !survive[n]")]
+    pub fn die(&self) -> [bool; COUNTS] {
+        self.survive.map(|s| !s)
     }
 
-    pub fn grid<'a>(&'a self) -> &'a Grid {
-        if !self.switch {
-            &self.g1
-        } else {
-            &self.g2
+    #[deprecated(note = "use survive, die[n] is !survive[n]")]
+    pub fn set_die(&mut self, die: [bool; COUNTS]) {
+        self.survive = die.map(|d| !d)
+    }
+
+    /// `corners` and `polars`, for the neighborhood functions
+    pub fn shape(&self) -> (bool, bool) {
+        (self.corners, self.polars)
+    }
+
+    /// The most neighbors a cell can have. At a radius of 1 that's 8 with corners and polars,
+    /// 4 with one, 0 with neither
+    pub fn reachable(&self) -> usize {
+        let r = self.radius as usize;
+        let line = 4 * r;
+        let square = (2 * r + 1).pow(2) - 1;
+        (square - line) * self.corners as usize + line * self.polars as usize
+    }
+
+    /// Nothing is ever born and nothing ever decays, so no grid changes. Only counts up to
+    /// `reachable` can happen, so the rest don't matter
+    pub fn is_trivial(&self) -> bool {
+        (0..=self.reachable()).all(|n| !self.grow[n] && self.survive[n])
+    }
+
+    /// Rules a soup probably does nothing interesting under, which `random` skips:
+    ///  - trivial ones
+    ///  - B1, where any lone cell gives birth and everything turns to noise
+    ///  - births on more than half the counts, which fill the grid
+    ///  - no births at half the neighborhood or less, which soups rarely reach
+    ///
+    /// Only counts up to `reachable` matter
+    pub fn is_boring(&self) -> bool {
+        let reachable = self.reachable();
+        let births = (0..=reachable).filter(|n| self.grow[*n]).count();
+        self.is_trivial()
+            || self.grow[1]
+            || births * 2 > reachable + 1
+            || !(0..=reachable / 2).any(|n| self.grow[n])
+    }
+
+    /// Random births and survivals with the neighborhood and life of `like`, redrawn until
+    /// the rule isn't `is_boring`. B0 only with `allow_b0`
+    pub fn random(like: &CellOpts, allow_b0: bool, rng: &mut impl Rng) -> Self {
+        let reachable = like.reachable();
+        loop {
+            let mut result = CellOpts {
+                corners: like.corners,
+                polars: like.polars,
+                radius: like.radius,
+                life: like.life,
+                grow: [false; COUNTS],
+                survive: [false; COUNTS],
+                mature_neighbors_only: false,
+            };
+            for n in 0..=reachable {
+                result.grow[n] = (n > 0 || allow_b0) && rng.gen_bool(0.25);
+                result.survive[n] = rng.gen_bool(0.5);
+            }
+            if !result.is_boring() {
+                return result;
+            }
         }
     }
 
-    pub fn grid_mut<'a>(&'a mut self) -> &'a mut Grid {
-        if !self.switch {
-            &mut self.g1
+    /// Canonical B/S rulestring, ex "B3/S23".
+    /// Suffixes "/C4" without corners, "/D4" without polars, "/C0" without either,
+    /// "/R{radius}" past 1, "/G{life}" when life isn't 1, and "/M" for `mature_neighbors_only`.
+    /// Counts are comma separated past a radius of 1, ex "B3,10/S2,3/R2"
+    pub fn rulestring(&self) -> String {
+        // a 3×3 can't count past 8, and 9 on would read back as separate digits
+        let (separator, written) = if self.radius > 1 {
+            (",", COUNTS)
         } else {
-            &mut self.g2
+            ("", 9)
+        };
+        let counts = |set: &[bool; COUNTS]| -> String {
+            let counts: Vec<String> = (0..written)
+                .filter(|n| set[*n])
+                .map(|n| n.to_string())
+                .collect();
+            counts.join(separator)
+        };
+        let mut result = format!("B{}/S{}", counts(&self.grow), counts(&self.survive));
+        match self.shape() {
+            (true, true) => (),
+            (false, true) => result += "/C4",
+            (true, false) => result += "/D4",
+            (false, false) => result += "/C0",
+        }
+        if self.radius != 1 {
+            result += &format!("/R{}", self.radius)
         }
+        if self.life != 1 {
+            result += &format!("/G{}", self.life)
+        }
+        if self.mature_neighbors_only {
+            result += "/M"
+        }
+        result
     }
+}
 
-    pub fn opts<'a>(&'a self) -> &'a CellOpts {
-        &self.opts
+impl std::fmt::Display for CellOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.rulestring())
     }
+}
 
-    pub fn set_cell(&mut self, x: usize, y: usize, val: u8) {
-        if let Some(ptr) = self.grid_mut().get_mut(y).map(|r| r.get_mut(x)).flatten() {
-            *ptr = val
+/// A rulestring that isn't B/S notation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleParseError {
+    pub rule: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bad rule '{}': {}", self.rule, self.message)
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+impl std::str::FromStr for CellOpts {
+    type Err = RuleParseError;
+
+    /// Parses what `rulestring` writes, `B{counts}/S{counts}` then optional `/C4`, `/D4` or
+    /// `/C0`, `/R{radius}`, `/G{life}` and `/M`. Letters may be lowercase.
+    /// Counts are single digits, or comma separated past a radius of 1
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |message: String| RuleParseError {
+            rule: s.to_string(),
+            message,
+        };
+        let counts = |part: &str, wide: bool| -> Result<[bool; COUNTS], RuleParseError> {
+            let mut result = [false; COUNTS];
+            let limit = if wide { COUNTS } else { 9 };
+            let numbers: Vec<&str> = match part {
+                "" => Vec::new(),
+                _ if wide => part.split(',').collect(),
+                _ => part.matches(|_| true).collect(),
+            };
+            for number in numbers {
+                match number.parse::<usize>() {
+                    Ok(n) if n < limit => result[n] = true,
+                    _ => return Err(err(format!("'{}' isn't a neighbor count", number))),
+                }
+            }
+            Ok(result)
+        };
+
+        let mut parts = s.trim().split('/');
+        let Some(grow) = parts.next().and_then(|p| p.strip_prefix(['B', 'b'])) else {
+            return Err(err("expected 'B' first".to_string()));
+        };
+        let Some(survive) = parts.next().and_then(|p| p.strip_prefix(['S', 's'])) else {
+            return Err(err("expected '/S' after the births".to_string()));
+        };
+
+        // the counts are read last, how they're written depends on the radius
+        let mut result = CellOpts::default();
+        for part in parts {
+            match part {
+                "C8" | "c8" => (result.corners, result.polars) = (true, true),
+                "C4" | "c4" => (result.corners, result.polars) = (false, true),
+                "D4" | "d4" => (result.corners, result.polars) = (true, false),
+                "C0" | "c0" => (result.corners, result.polars) = (false, false),
+                "M" | "m" => result.mature_neighbors_only = true,
+                _ => match (part.strip_prefix(['G', 'g']), part.strip_prefix(['R', 'r'])) {
+                    (Some(life), _) => {
+                        result.life = life
+                            .parse()
+                            .map_err(|_| err(format!("'{}' isn't a life from 0 to 255", life)))?
+                    }
+                    (_, Some(radius)) => {
+                        result.radius = radius
+                            .parse()
+                            .ok()
+                            .filter(|r| (1..=MAX_RADIUS).contains(r))
+                            .ok_or_else(|| {
+                                let hint = if MAX_RADIUS < 2 {
+                                    ", more needs the large-neighborhood feature"
+                                } else {
+                                    ""
+                                };
+                                err(format!(
+                                    "'{}' isn't a radius from 1 to {}{}",
+                                    radius, MAX_RADIUS, hint
+                                ))
+                            })?
+                    }
+                    _ => return Err(err(format!("unknown suffix '/{}'", part))),
+                },
+            }
         }
+        result.grow = counts(grow, result.radius > 1)?;
+        result.survive = counts(survive, result.radius > 1)?;
+        Ok(result)
     }
+}
 
-    pub fn get_cell(&self, x: usize, y: usize) -> Option<u8> {
-        get_cell(self.grid(), x, y)
+/// A coordinate fell outside of a grid of size `w`×`h`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBoundsError {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+impl std::fmt::Display for OutOfBoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cell ({}, {}) is outside of the {}x{} grid",
+            self.x, self.y, self.w, self.h
+        )
     }
 }
 
-pub fn get_cell(grid: &Grid, x: usize, y: usize) -> Option<u8> {
-    grid.get(y).map(|r| r.get(x)).flatten().cloned()
+impl std::error::Error for OutOfBoundsError {}
+
+// Pressure {{{
+/// How `advance` thins a crowded grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum PressurePolicy {
+    /// no cell is born that step
+    SuppressBirths,
+    /// the cells with the most life die first, ties in reading order
+    CullOldest,
+    CullRandom,
 }
 
-pub fn advance(from: &Grid, to: &mut Grid, opts: CellOpts) {
-    // sanity checks. too many?
-    assert_eq!(from.len(), to.len());
-    assert_eq!(from.get(0).map(|r| r.len()), to.get(0).map(|r| r.len()));
-    assert_eq!(from.last().map(|r| r.len()), to.last().map(|r| r.len()));
+/// A cap on the population. Once more than `max_density` of the grid is alive, each `advance`
+/// also kills cells by `policy` until it isn't. No real cellular automaton does this, it only
+/// keeps exploding rules from filling the screen, so it's off unless asked for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pressure {
+    pub max_density: f32,
+    pub policy: PressurePolicy,
+}
 
-    for (y, row) in to.iter_mut().enumerate() {
-        for (x, cell) in row.iter_mut().enumerate() {
-            let (x, y) = (x as isize, y as isize);
-
-            let polars = [
-                (x, y - 1),     // up
-                (x + 1, y),     // right
-                (x, y + 1),     // down
-                (x - 1, y),     // left
-            ];
-
-            let corners = [
-                (x + 1, y - 1), // up right
-                (x + 1, y + 1), // right down
-                (x - 1, y + 1), // down left
-                (x - 1, y - 1), // left up
-            ];
-
-            let iter = if opts.corners {
-                polars.iter().chain(corners.iter())
-            } else {
-                polars.iter().chain([].iter())
-            };
+impl Pressure {
+    /// Most live cells allowed in `area`
+    pub fn limit(&self, area: usize) -> usize {
+        (self.max_density.clamp(0.0, 1.0) as f64 * area as f64) as usize
+    }
+}
+// Pressure }}}
 
-            let neighbors: usize = iter.filter_map(|(x, y)| {
-                if x >= &0 && y >= &0 {
-                    get_cell(from, *x as usize, *y as usize)
-                } else {
-                    None
-                }
-            })
-            .filter(|c| *c != 0)
-            .count();
+// Nondeterminism {{{
+/// Something that means the cells since the last fresh start can't be replayed exactly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nondeterminism {
+    /// `PressurePolicy::CullRandom` drawing from a seed nobody kept
+    UnseededPressure,
+    /// cells changed from the OS's randomness, named by what did it
+    Entropy(&'static str),
+    /// loaded from a snapshot that was already nondeterministic
+    Inherited,
+}
 
-            *cell = if opts.grow[neighbors] {
-                (from[y as usize][x as usize] + 1).min(opts.life)
-            } else if opts.die[neighbors] {
-                from[y as usize][x as usize].saturating_sub(1)
-            } else {
-                from[y as usize][x as usize]
+impl std::fmt::Display for Nondeterminism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnseededPressure => f.write_str("random culls without a seed"),
+            Self::Entropy(what) => write!(f, "{} from OS randomness", what),
+            Self::Inherited => f.write_str("a snapshot that wasn't reproducible"),
+        }
+    }
+}
+
+impl std::error::Error for Nondeterminism {}
+// Nondeterminism }}}
+
+// Rect {{{
+/// Cells `w`×`h` with their top left at x, y. Inclusive-exclusive like ranges, so it covers
+/// columns `x..x + w` and rows `y..y + h`, and a zero `w` or `h` covers nothing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+#[deprecated(note = "use Rect")]
+pub type Region = Rect;
+
+impl Rect {
+    /// The area spanned by two corner cells, both included, in any order. For selections
+    pub fn between(a: (usize, usize), b: (usize, usize)) -> Self {
+        Self {
+            x: a.0.min(b.0),
+            y: a.1.min(b.1),
+            w: a.0.abs_diff(b.0) + 1,
+            h: a.1.abs_diff(b.1) + 1,
+        }
+    }
+
+    /// From the top left cell up to but not including `end`, empty if `end` isn't past it
+    pub fn from_corners(start: (usize, usize), end: (usize, usize)) -> Self {
+        Self {
+            x: start.0,
+            y: start.1,
+            w: end.0.saturating_sub(start.0),
+            h: end.1.saturating_sub(start.1),
+        }
+    }
+
+    /// The column and row just past the bottom right, saturating
+    pub fn end(&self) -> (usize, usize) {
+        (self.x.saturating_add(self.w), self.y.saturating_add(self.h))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.w && y - self.y < self.h
+    }
+
+    /// The cells in both, `None` if there aren't any
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let ((x1, y1), (x2, y2)) = (self.end(), other.end());
+        let result = Rect::from_corners(
+            (self.x.max(other.x), self.y.max(other.y)),
+            (x1.min(x2), y1.min(y2)),
+        );
+        (!result.is_empty()).then_some(result)
+    }
+
+    /// The smallest rect covering both. Empty ones add nothing
+    pub fn union(&self, other: &Rect) -> Rect {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => *other,
+            (_, true) => *self,
+            _ => {
+                let ((x1, y1), (x2, y2)) = (self.end(), other.end());
+                Rect::from_corners(
+                    (self.x.min(other.x), self.y.min(other.y)),
+                    (x1.max(x2), y1.max(y2)),
+                )
             }
         }
     }
+
+    /// Whatever of it is on a `width`×`height` grid, which may be nothing
+    pub fn clamp_to(&self, width: usize, height: usize) -> Rect {
+        let (x, y) = (self.x.min(width), self.y.min(height));
+        let (x1, y1) = self.end();
+        Rect::from_corners((x, y), (x1.min(width), y1.min(height)))
+    }
+
+    /// Every x, y inside, row by row
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let Rect { x, y, .. } = *self;
+        let (x1, y1) = self.end();
+        (y..y1).flat_map(move |cy| (x..x1).map(move |cx| (cx, cy)))
+    }
 }
+// Rect }}}
 
-pub fn resize(grid: &mut Grid, x: usize, y: usize) {
-    if grid.len() < y {
-        grid.extend((0..(y - grid.len())).map(|_| vec![0; x]))
-    } else if grid.len() > y {
-        *grid = grid[0..y].to_vec()
+/// Sides of a grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Edges {
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+    pub left: bool,
+}
+
+impl Edges {
+    pub fn any(&self) -> bool {
+        self.top || self.right || self.bottom || self.left
+    }
+}
+
+/// What one `Game::advance` did, and how long the cells took
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepInfo {
+    /// the generation just reached, 1 after the first advance
+    pub generation: u64,
+    /// dead cells that came alive
+    pub births: usize,
+    /// live cells that died
+    pub deaths: usize,
+    /// live cells still alive, aged or not
+    pub survivors: usize,
+    /// cells with a different value, including ones that only aged
+    pub changed: usize,
+    /// cells with the same value, `changed` for the rest of the grid
+    pub unchanged: usize,
+    pub population: usize,
+    /// just the advance of the cells, without the pressure or the counting here
+    pub elapsed: Duration,
+}
+
+impl StepInfo {
+    /// Counts one cell the advance took from `before` to `now`
+    fn tally<C: Cell>(&mut self, before: C, now: C) {
+        if now != before {
+            self.changed += 1
+        } else {
+            self.unchanged += 1
+        }
+        match (before.alive(), now.alive()) {
+            (false, true) => self.births += 1,
+            (true, false) => self.deaths += 1,
+            (true, true) => self.survivors += 1,
+            (false, false) => (),
+        }
+        self.population += now.alive() as usize
+    }
+}
+
+/// The side of a newborn cell most of its parents were on, see `parent_side`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Called after every advance with the `StepInfo` and the game it left, borrowed so it can
+/// look at the cells but not touch them
+type HookFn = dyn FnMut(&StepInfo, &Game) + Send;
+
+struct StepHook(Option<Box<HookFn>>);
+
+/// A boxed closure can't be copied, so clones start without one
+impl Clone for StepHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+#[derive(Clone)]
+pub struct Game {
+    g1: Grid,
+    g2: Grid,
+    switch: bool,
+    opts: CellOpts,
+    /// advances so far
+    generation: u64,
+    /// areas `advance` leaves alone
+    regions: Vec<Rect>,
+    /// `regions` flattened row major, empty when there are none
+    frozen: Vec<bool>,
+    /// live cells, counted by `advance` and forgotten by anything else that edits cells
+    population: Option<usize>,
+    pressure: Option<(Pressure, StdRng)>,
+    /// the pressure rng came from a known seed
+    pressure_seeded: bool,
+    /// the first thing since the last fresh start that can't be replayed
+    nondeterminism: Option<Nondeterminism>,
+    /// refuse anything that would set `nondeterminism` instead
+    strict: bool,
+    /// the edges join, making the grid a torus
+    wrap: bool,
+    hook: StepHook,
+    /// `parent_side` of each cell the last advance gave birth to, row major. Only kept
+    /// while something asked for it, it's another pass over the grid
+    births: Option<Vec<Option<Side>>>,
+    /// what a pattern file said about itself, `N` name, `O` author, and `C` comments
+    metadata: HashMap<String, String>,
+}
+
+/// Largest area of the grid `Debug` prints
+const DEBUG_SIZE: (usize, usize) = (40, 20);
+
+/// The grid as `.` and `#`, cut to `DEBUG_SIZE` with `…` marking what's left out
+impl std::fmt::Debug for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (w, h) = self.size();
+        writeln!(
+            f,
+            "Game {}×{}, generation {}, population {}, {}",
+            w,
+            h,
+            self.generation,
+            self.population(),
+            self.opts
+        )?;
+        for row in self.grid().iter().take(DEBUG_SIZE.1) {
+            let line: String = row
+                .iter()
+                .take(DEBUG_SIZE.0)
+                .map(|c| if *c == 0 { '.' } else { '#' })
+                .collect();
+            writeln!(f, "{}{}", line, if w > DEBUG_SIZE.0 { "…" } else { "" })?;
+        }
+        if h > DEBUG_SIZE.1 {
+            writeln!(f, "…")?;
+        }
+        Ok(())
+    }
+}
+
+/// Games are equal when their visible cells and rules are, however they got there
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.grid() == other.grid() && self.opts == other.opts
+    }
+}
+
+impl Eq for Game {}
+
+impl Game {
+    pub fn new(opts: CellOpts) -> Self {
+        Self {
+            g1: Grid::new(),
+            g2: Grid::new(),
+            switch: false,
+            opts,
+            generation: 0,
+            regions: Vec::new(),
+            frozen: Vec::new(),
+            population: None,
+            pressure: None,
+            pressure_seeded: true,
+            nondeterminism: None,
+            strict: false,
+            wrap: false,
+            hook: StepHook(None),
+            births: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// An empty game under a rulestring, as `CellOpts::from_str` reads it
+    ///
+    /// ```ignore
+    /// let mut game = Game::with_rule("B3/S23")?;
+    /// game.resize(40, 20);
+    /// game.randomize(0.3, &mut rand::thread_rng());
+    /// ```
+    pub fn with_rule(rule: &str) -> Result<Self, RuleParseError> {
+        rule.parse().map(Self::new)
+    }
+
+    /// Creates a `w`×`h` game with every listed (x, y) set to `opts.life`
+    pub fn from_coords(
+        coords: &[(usize, usize)],
+        w: usize,
+        h: usize,
+        opts: CellOpts,
+    ) -> Result<Self, OutOfBoundsError> {
+        let mut game = Self::new(opts);
+        game.resize(w, h);
+        for (x, y) in coords.iter().copied() {
+            if x >= w || y >= h {
+                return Err(OutOfBoundsError { x, y, w, h });
+            }
+            game.set_cell(x, y, opts.life)
+        }
+        Ok(game)
+    }
+
+    /// Sorted (x, y) of every live cell, the inverse of `from_coords`
+    pub fn to_coords(&self) -> Vec<(usize, usize)> {
+        let mut result: Vec<(usize, usize)> = self
+            .grid()
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c != 0)
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect();
+        result.sort_unstable();
+        result
+    }
+
+    pub fn advance(&mut self) -> StepInfo {
+        // the deprecated `grid_mut` can leave ragged rows, pad them so both buffers match again
+        let grid = self.grid();
+        let (w, h) = (grid.iter().map(|r| r.len()).max().unwrap_or(0), grid.len());
+        let other = if self.switch { &self.g1 } else { &self.g2 };
+        if (self.g1.iter().chain(self.g2.iter())).any(|r| r.len() != w) || other.len() != h {
+            self.resize(w, h);
+        }
+
+        let limit = self.pressure.as_ref().map(|(p, _)| p.limit(w * h));
+        let crowded = limit.is_some_and(|l| self.population() > l);
+
+        let frozen = (!self.regions.is_empty()).then_some(&self.frozen[..]);
+        let (life, opts) = (self.opts.life, self.opts);
+        let (from, to) = if !self.switch {
+            (&self.g1, &mut self.g2)
+        } else {
+            (&self.g2, &mut self.g1)
+        };
+        let start = Instant::now();
+        let mut info = if life == 1 {
+            from.advance_binary(to, opts, frozen, self.wrap)
+        } else {
+            from.advance_masked(to, opts, life, frozen, self.wrap)
+        };
+        info.elapsed = start.elapsed();
+        self.switch = !self.switch;
+        self.generation = self.generation.wrapping_add(1);
+        info.generation = self.generation;
+
+        if let Some(limit) = limit {
+            self.relieve_pressure(limit, crowded, &mut info)
+        }
+        self.population = Some(info.population);
+
+        let (grid, previous) = if self.switch {
+            (&self.g2, &self.g1)
+        } else {
+            (&self.g1, &self.g2)
+        };
+        if let Some(births) = &mut self.births {
+            births.clear();
+            for (y, (row, before)) in grid.iter().zip(previous.iter()).enumerate() {
+                births.extend(row.iter().zip(before).enumerate().map(|(x, (now, was))| {
+                    (now.alive() && !was.alive())
+                        .then(|| parent_side(previous, x, y, &self.opts, self.wrap))
+                        .flatten()
+                }))
+            }
+        }
+        // out of `self` while it runs, so it can borrow the rest
+        if let Some(mut hook) = self.hook.0.take() {
+            hook(&info, self);
+            self.hook.0 = Some(hook)
+        }
+        info
+    }
+
+    /// Advances `n` times, returning the last step's info, `None` for 0. The generation counter
+    /// wraps instead of overflowing, so the step after `u64::MAX` is generation 0, then 1. Both
+    /// `generation` and the `StepInfo` show the wrapped count. `advance_n(u64::MAX / 2)` from 0
+    /// ends at `u64::MAX / 2` without wrapping, about 5 billion years at 60 generations a second
+    ///
+    /// ```ignore
+    /// let mut game = Game::new("B3/S23".parse().unwrap());
+    /// game.advance_n(3);
+    /// assert_eq!(game.generation(), 3);
+    /// ```
+    pub fn advance_n(&mut self, n: u64) -> Option<StepInfo> {
+        (0..n).map(|_| self.advance()).last()
     }
 
-    assert_eq!(grid.len(), y);
+    /// Advances until `budget` has passed, returning how many steps ran. A step that
+    /// starts in time always finishes, so the budget can be overrun by one
+    pub fn advance_for(&mut self, budget: Duration) -> usize {
+        self.advance_up_to(usize::MAX, budget)
+    }
 
-    for row in grid.iter_mut() {
-        if row.len() < x {
-            row.extend((0..(x - row.len())).map(|_| 0))
-        } else if row.len() > x {
-            *row = row[0..x].to_vec()
+    /// `advance_for`, stopping early after `max` steps
+    pub fn advance_up_to(&mut self, max: usize, budget: Duration) -> usize {
+        let start = Instant::now();
+        let mut steps = 0;
+        while steps < max && start.elapsed() < budget {
+            self.advance();
+            steps += 1
         }
-        assert_eq!(row.len(), x);
+        steps
+    }
+
+    /// Joins the edges, so cells off one side count as neighbors of the other
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    /// Runs `hook` after every advance, replacing any previous one
+    pub fn set_step_hook(&mut self, hook: impl FnMut(&StepInfo, &Game) + Send + 'static) {
+        self.hook = StepHook(Some(Box::new(hook)))
+    }
+
+    /// Starts or stops keeping `birth_sides`
+    pub fn set_birth_tracking(&mut self, on: bool) {
+        self.births = on.then(Vec::new)
+    }
+
+    /// Where the last advance's births came from, row major, `None` for every other cell.
+    /// Empty until an advance after `set_birth_tracking`, `None` without it
+    pub fn birth_sides(&self) -> Option<&[Option<Side>]> {
+        self.births.as_deref()
+    }
+
+    /// The loaded pattern's name, author, and comments, see `formats::rle_metadata`
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// Replaces the metadata. `reset` forgets it, along with the pattern
+    pub fn set_metadata(&mut self, metadata: HashMap<String, String>) {
+        self.metadata = metadata
+    }
+
+    pub fn clear_step_hook(&mut self) {
+        self.hook = StepHook(None)
+    }
+
+    /// Thins the cells `advance` just wrote, see `Pressure`.
+    /// Births are judged by the crowding before the step, culls by the population after.
+    /// `info` is corrected for the cells taken back
+    fn relieve_pressure(&mut self, limit: usize, crowded: bool, info: &mut StepInfo) {
+        let Some((pressure, rng)) = &mut self.pressure else {
+            return;
+        };
+        let (grid, previous) = if self.switch {
+            (&mut self.g2, &self.g1)
+        } else {
+            (&mut self.g1, &self.g2)
+        };
+        match pressure.policy {
+            PressurePolicy::SuppressBirths if crowded => {
+                for (cell, before) in grid.iter_mut().flatten().zip(previous.iter().flatten()) {
+                    if *before == 0 {
+                        *cell = 0
+                    }
+                }
+                // every birth is undone, leaving those cells as they were
+                info.population -= info.births;
+                info.changed -= info.births;
+                info.unchanged += info.births;
+                info.births = 0
+            }
+            PressurePolicy::SuppressBirths => (),
+            PressurePolicy::CullOldest | PressurePolicy::CullRandom => {
+                // nothing to cull, so no list of candidates either
+                if info.population <= limit {
+                    return;
+                }
+                // static regions are settled, culling them would undo `advance` skipping them
+                let w = grid.first().map(|r| r.len()).unwrap_or(0);
+                let frozen = |x: usize, y: usize| self.frozen.get(y * w + x).is_some_and(|f| *f);
+                let mut live: Vec<(usize, usize)> = (0..grid.len())
+                    .flat_map(|y| (0..grid[y].len()).map(move |x| (x, y)))
+                    .filter(|(x, y)| grid[*y][*x] != 0 && !frozen(*x, *y))
+                    .collect();
+                let excess = (info.population - limit).min(live.len());
+                if pressure.policy == PressurePolicy::CullRandom {
+                    if !self.pressure_seeded {
+                        // set_pressure_from_entropy already refused this in strict mode
+                        self.nondeterminism
+                            .get_or_insert(Nondeterminism::UnseededPressure);
+                    }
+                    live.shuffle(rng)
+                } else {
+                    live.sort_by_key(|(x, y)| std::cmp::Reverse(grid[*y][*x]))
+                }
+                for (x, y) in live.iter().take(excess) {
+                    let before = previous[*y][*x];
+                    if before == 0 {
+                        info.births -= 1;
+                        info.changed -= 1;
+                        info.unchanged += 1
+                    } else {
+                        info.survivors -= 1;
+                        info.deaths += 1;
+                        if before == grid[*y][*x] {
+                            info.changed += 1;
+                            info.unchanged -= 1
+                        }
+                    }
+                    grid[*y][*x] = 0
+                }
+                info.population -= excess
+            }
+        }
+    }
+
+    /// Turns density pressure on or off. `seed` drives `PressurePolicy::CullRandom`
+    pub fn set_pressure(&mut self, pressure: Option<Pressure>, seed: u64) {
+        self.pressure = pressure.map(|p| (p, StdRng::seed_from_u64(seed)));
+        self.pressure_seeded = true
+    }
+
+    /// `set_pressure` seeded by the OS. Random culls then can't be replayed, so this fails
+    /// for `PressurePolicy::CullRandom` with strict determinism
+    pub fn set_pressure_from_entropy(
+        &mut self,
+        pressure: Option<Pressure>,
+    ) -> Result<(), Nondeterminism> {
+        if self.strict && pressure.is_some_and(|p| p.policy == PressurePolicy::CullRandom) {
+            return Err(Nondeterminism::UnseededPressure);
+        }
+        self.pressure = pressure.map(|p| (p, StdRng::from_entropy()));
+        self.pressure_seeded = false;
+        Ok(())
+    }
+
+    pub fn pressure(&self) -> Option<Pressure> {
+        self.pressure.as_ref().map(|(p, _)| *p)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// With strict determinism `note_nondeterminism` fails instead of recording
+    pub fn set_strict_determinism(&mut self, strict: bool) {
+        self.strict = strict
+    }
+
+    pub fn strict_determinism(&self) -> bool {
+        self.strict
+    }
+
+    /// What first made the cells since the last fresh start unreproducible, if anything
+    pub fn nondeterminism(&self) -> Option<Nondeterminism> {
+        self.nondeterminism
+    }
+
+    /// Every caller about to change cells in a way that can't be replayed goes through
+    /// here first, and doesn't go ahead on an error
+    pub fn note_nondeterminism(&mut self, what: Nondeterminism) -> Result<(), Nondeterminism> {
+        if self.strict {
+            return Err(what);
+        }
+        self.nondeterminism.get_or_insert(what);
+        Ok(())
+    }
+
+    /// Starts a new reproducible timeline, as after loading cells from a file
+    pub fn reset_nondeterminism(&mut self) {
+        self.nondeterminism = None
+    }
+
+    /// Resizes both buffers, so the inactive one never carries stale cells into new space.
+    /// Cells inside the old bounds are kept and anything added is dead
+    pub fn resize(&mut self, x: usize, y: usize) {
+        self.g1.resize(x, y);
+        self.g2.resize(x, y);
+        self.population = None;
+        self.rebuild_frozen()
+    }
+
+    /// Stops `advance` from changing the cells in `region`. They still count as neighbors
+    pub fn add_static_region(&mut self, region: Rect) {
+        self.regions.push(region);
+        self.rebuild_frozen()
+    }
+
+    pub fn clear_static_regions(&mut self) {
+        self.regions.clear();
+        self.rebuild_frozen()
+    }
+
+    pub fn static_regions(&self) -> &[Rect] {
+        &self.regions
+    }
+
+    /// A copy of the cells in `rect`, clipped to the grid
+    pub fn extract(&self, rect: Rect) -> Grid {
+        let (w, h) = self.size();
+        let rect = rect.clamp_to(w, h);
+        let (x1, y1) = rect.end();
+        self.grid()[rect.y..y1]
+            .iter()
+            .map(|row| row[rect.x..x1].to_vec())
+            .collect()
+    }
+
+    /// Kills every cell in `rect`
+    pub fn clear_inside(&mut self, rect: Rect) {
+        self.edit(|view| view.map(|x, y, c| if rect.contains(x, y) { 0 } else { c }))
+    }
+
+    /// Kills every cell outside `rect`
+    pub fn clear_outside(&mut self, rect: Rect) {
+        self.edit(|view| view.map(|x, y, c| if rect.contains(x, y) { c } else { 0 }))
+    }
+
+    /// Whether x, y is in a static region
+    pub fn is_static(&self, x: usize, y: usize) -> bool {
+        let (w, _) = self.size();
+        x < w && self.frozen.get(y * w + x).is_some_and(|f| *f)
+    }
+
+    fn rebuild_frozen(&mut self) {
+        let (w, h) = self.size();
+        self.frozen.clear();
+        if self.regions.is_empty() {
+            return;
+        }
+        self.frozen.resize(w * h, false);
+        for r in self.regions.iter().map(|r| r.clamp_to(w, h)) {
+            let (x1, y1) = r.end();
+            for y in r.y..y1 {
+                self.frozen[y * w + r.x..y * w + x1].fill(true)
+            }
+        }
+    }
+
+    /// (width, height)
+    pub fn size(&self) -> (usize, usize) {
+        let grid = self.grid();
+        (grid.first().map(|r| r.len()).unwrap_or(0), grid.len())
+    }
+
+    /// Which sides have a live cell at most `distance` cells away from them
+    pub fn near_edges(&self, distance: usize) -> Edges {
+        let (w, h) = self.size();
+        let mut result = Edges::default();
+        for (x, y) in self.to_coords() {
+            result.top |= y <= distance;
+            result.left |= x <= distance;
+            result.bottom |= y + distance + 1 >= h;
+            result.right |= x + distance + 1 >= w;
+        }
+        result
+    }
+
+    /// Adds up to `margin` dead cells past each of `edges` without growing beyond `max` (w, h).
+    /// Returns how far right and down the existing cells moved
+    pub fn expand(&mut self, edges: Edges, margin: usize, max: (usize, usize)) -> (usize, usize) {
+        let (w, h) = self.size();
+        let mut room = (max.0.saturating_sub(w), max.1.saturating_sub(h));
+        let take = |flag: bool, room: &mut usize| {
+            let n = if flag { margin.min(*room) } else { 0 };
+            *room -= n;
+            n
+        };
+        let left = take(edges.left, &mut room.0);
+        let right = take(edges.right, &mut room.0);
+        let top = take(edges.top, &mut room.1);
+        let bottom = take(edges.bottom, &mut room.1);
+
+        let (nw, nh) = (w + left + right, h + top + bottom);
+        for grid in [&mut self.g1, &mut self.g2] {
+            let mut result = vec![vec![0; nw]; nh];
+            for (y, row) in grid.iter().enumerate() {
+                result[y + top][left..left + row.len()].copy_from_slice(row)
+            }
+            *grid = Grid(result)
+        }
+        for region in self.regions.iter_mut() {
+            region.x = region.x.saturating_add(left);
+            region.y = region.y.saturating_add(top);
+        }
+        self.rebuild_frozen();
+
+        (left, top)
+    }
+
+    pub fn grid(&self) -> &Grid {
+        if !self.switch {
+            &self.g1
+        } else {
+            &self.g2
+        }
+    }
+
+    /// The buffer that isn't showing, what `advance` will overwrite
+    pub fn back_grid(&self) -> &Grid {
+        if !self.switch {
+            &self.g2
+        } else {
+            &self.g1
+        }
+    }
+
+    /// Shows the back buffer instead, so the previous generation comes back. Both buffers are
+    /// always the same size, so everything sized by the grid stays valid
+    pub fn swap_buffers(&mut self) {
+        self.switch = !self.switch;
+        self.population = None;
+    }
+
+    /// Edits the visible cells through a view that can't resize them or skip the clamping
+    pub fn edit<R>(&mut self, f: impl FnOnce(&mut GridView) -> R) -> R {
+        self.population = None;
+        let life = self.opts.life;
+        let grid = if !self.switch {
+            &mut self.g1
+        } else {
+            &mut self.g2
+        };
+        f(&mut GridView { grid, life })
+    }
+
+    #[deprecated(note = "use Game::edit, which can't leave the buffers different sizes")]
+    pub fn grid_mut(&mut self) -> &mut Grid {
+        self.population = None;
+        if !self.switch {
+            &mut self.g1
+        } else {
+            &mut self.g2
+        }
+    }
+
+    pub fn opts(&self) -> &CellOpts {
+        &self.opts
+    }
+
+    /// Switches rules, clamping existing cells to the new life
+    pub fn set_opts(&mut self, opts: CellOpts) {
+        self.opts = opts;
+        self.population = None;
+        for cell in self.g1.iter_mut().chain(self.g2.iter_mut()).flatten() {
+            *cell = (*cell).min(opts.life)
+        }
+    }
+
+    /// Kills every cell and starts counting generations from 0 again, as for a new pattern.
+    /// The size, rule, and settings stay, the metadata goes with the cells
+    pub fn reset(&mut self) {
+        self.clear();
+        self.generation = 0;
+        self.reset_nondeterminism();
+        self.metadata.clear();
+    }
+
+    /// Kills every cell
+    pub fn clear(&mut self) {
+        self.population = Some(0);
+        for cell in self.g1.iter_mut().chain(self.g2.iter_mut()).flatten() {
+            *cell = 0
+        }
+    }
+
+    /// Replaces the grid with cells at `opts.life` with a chance of `density`
+    pub fn randomize(&mut self, density: f32, rng: &mut impl Rng) {
+        let (w, h) = self.size();
+        let random = random_grid(w, h, self.opts.life, density, rng);
+        self.edit(|view| view.map(|x, y, _| random[y][x]))
+    }
+
+    /// Toggles cells with a chance of `density`, dead ones to `opts.life` and live ones
+    /// to dead, leaving the rest of the pattern as it was. Returns how many changed
+    pub fn apply_noise(&mut self, density: f32, rng: &mut impl Rng) -> usize {
+        let life = self.opts.life;
+        let mut changed = 0;
+        self.edit(|view| {
+            view.map(|_, _, cell| {
+                if rng.gen::<f32>() < density {
+                    changed += 1;
+                    if cell == 0 {
+                        life
+                    } else {
+                        0
+                    }
+                } else {
+                    cell
+                }
+            })
+        });
+        changed
+    }
+
+    /// Sets a cell, clamped to the rule's life. Out of bounds is ignored
+    pub fn set_cell(&mut self, x: usize, y: usize, val: u8) {
+        self.edit(|view| view.set(x, y, val))
+    }
+
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<u8> {
+        self.grid().get_cell(x, y)
+    }
+
+    /// Number of live cells, free right after `advance`
+    pub fn population(&self) -> usize {
+        self.population
+            .unwrap_or_else(|| self.grid().iter().flatten().filter(|c| **c != 0).count())
+    }
+
+    /// Whether both buffers hold the same cells, meaning the last advance changed nothing
+    pub fn stable(&self) -> bool {
+        self.g1 == self.g2
+    }
+
+    /// Cells around `x`, `y` that count under the rule, /M included, with the edges as
+    /// they are. What `advance` counts, `None` off the grid
+    pub fn neighbors_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.get_cell(x, y)?;
+        let (grid, opts) = (self.grid(), &self.opts);
+        let counts = |(x, y): (usize, usize)| {
+            grid.get_cell(x, y)
+                .is_some_and(|c| opts.counts(c, opts.life))
+        };
+        let (shape, radius) = (opts.shape(), opts.radius);
+        Some(if self.wrap {
+            neighborhood_wrapped(x, y, grid[y].len(), grid.len(), shape, radius)
+                .filter(|p| counts(*p))
+                .count()
+        } else {
+            neighborhood(x, y, shape, radius)
+                .filter(|p| counts(*p))
+                .count()
+        })
+    }
+
+    /// What the cell at `x`, `y` becomes next generation, static regions included
+    pub fn next_cell(&self, x: usize, y: usize) -> Option<u8> {
+        let current = self.get_cell(x, y)?.min(self.opts.life);
+        if self.is_static(x, y) {
+            return Some(current);
+        }
+        let neighbors = self.neighbors_at(x, y)?;
+        Some(self.opts.next(current, neighbors, self.opts.life))
+    }
+
+    /// How many live cells have each value, 1 through `opts.life` at index 0 through
+    /// `life - 1`. Cells past life count as at it
+    pub fn value_histogram(&self) -> Vec<usize> {
+        let life = self.opts.life.max(1);
+        let mut result = vec![0; life as usize];
+        for cell in self.grid().iter().flatten().filter(|c| **c != 0) {
+            result[(*cell).min(life) as usize - 1] += 1
+        }
+        result
+    }
+
+    /// How many cells have each count of live neighbors. A full scan, so call it sparingly
+    pub fn neighbor_histogram(&self) -> [usize; COUNTS] {
+        let mut result = [0; COUNTS];
+        for (y, row) in self.grid().iter().enumerate() {
+            for x in 0..row.len() {
+                result[self.neighbors_at(x, y).unwrap_or(0)] += 1
+            }
+        }
+        result
+    }
+
+    /// Copies the live cells of `pattern` onto the grid with its top-left at x, y.
+    /// Anything outside the grid is clipped.
+    pub fn stamp(&mut self, pattern: &Grid, x: usize, y: usize) {
+        self.edit(|view| view.stamp(pattern, x, y))
+    }
+
+    /// `Grid::combined` in place
+    pub fn combine(&mut self, pattern: &Grid, x: usize, y: usize, op: Combine) {
+        self.edit(|view| view.combine(pattern, x, y, op))
+    }
+}
+
+// Combine {{{
+/// How a stamped pattern's cells join the ones under it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Combine {
+    /// live pattern cells replace what's under them
+    #[default]
+    Overwrite,
+    /// the higher of the two
+    Union,
+    /// the lower of the two
+    Intersect,
+    /// live pattern cells clear what's under them
+    Subtract,
+    /// whichever is alive if only one is, otherwise dead
+    Xor,
+}
+
+const COMBINES: [(&str, Combine); 5] = [
+    ("overwrite", Combine::Overwrite),
+    ("union", Combine::Union),
+    ("intersect", Combine::Intersect),
+    ("subtract", Combine::Subtract),
+    ("xor", Combine::Xor),
+];
+
+impl Combine {
+    pub fn apply(self, under: u8, over: u8) -> u8 {
+        match self {
+            Self::Overwrite if over != 0 => over,
+            Self::Overwrite => under,
+            Self::Union => under.max(over),
+            Self::Intersect => under.min(over),
+            Self::Subtract if over != 0 => 0,
+            Self::Subtract => under,
+            Self::Xor if under != 0 && over != 0 => 0,
+            Self::Xor => under.max(over),
+        }
+    }
+}
+
+impl std::fmt::Display for Combine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = COMBINES.iter().find(|(_, op)| op == self).map(|(n, _)| *n);
+        f.write_str(name.unwrap_or_default())
+    }
+}
+
+impl std::str::FromStr for Combine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COMBINES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, op)| *op)
+            .ok_or_else(|| {
+                let names: Vec<&str> = COMBINES.iter().map(|(n, _)| *n).collect();
+                format!("unknown operation '{}', expected {}", s, names.join(", "))
+            })
+    }
+}
+
+impl Grid {
+    /// A copy with `pattern` combined in at x, y. Only cells under the pattern change, so
+    /// intersecting leaves the rest alone. Clipped at the edges, and clamped to `life`
+    pub fn combined(&self, pattern: &Grid, x: usize, y: usize, op: Combine, life: u8) -> Grid {
+        let mut result = self.clone();
+        GridView {
+            grid: &mut result,
+            life,
+        }
+        .combine(pattern, x, y, op);
+        result
+    }
+}
+// Combine }}}
+
+// DeltaEncoding {{{
+/// The cells that differ between two grids, as `(x, y, value)`, for sending a grid as
+/// it changes instead of whole
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DeltaEncoding {
+    /// row major
+    pub changed: Vec<(u16, u16, u8)>,
+}
+
+impl DeltaEncoding {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+impl Game {
+    /// The current cells that differ from `prev`. Cells `prev` doesn't have count as dead,
+    /// and the ones only it has are left out. Panics past 65536 cells in either direction
+    pub fn encode_delta(&self, prev: &Grid) -> DeltaEncoding {
+        let coord = |n: usize| u16::try_from(n).expect("grid too big for a delta");
+        let mut changed = Vec::new();
+        for (y, row) in self.grid().iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if prev.get_cell(x, y).unwrap_or(0) != *cell {
+                    changed.push((coord(x), coord(y), *cell))
+                }
+            }
+        }
+        DeltaEncoding { changed }
+    }
+
+    /// Writes `delta` over the current cells, ignoring anything outside the grid.
+    /// Values are clamped to life like any other edit
+    pub fn apply_delta(&mut self, delta: &DeltaEncoding) {
+        self.edit(|view| {
+            for (x, y, value) in &delta.changed {
+                view.set(*x as usize, *y as usize, *value)
+            }
+        })
+    }
+}
+// DeltaEncoding }}}
+
+// GridView {{{
+/// The visible buffer as `Game::edit` lends it out. Cells can change but the size can't,
+/// and every write is clamped to the rule's life
+pub struct GridView<'a> {
+    grid: &'a mut Grid,
+    life: u8,
+}
+
+impl GridView<'_> {
+    pub fn size(&self) -> (usize, usize) {
+        (
+            self.grid.first().map(|r| r.len()).unwrap_or(0),
+            self.grid.len(),
+        )
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        self.grid.get_cell(x, y)
+    }
+
+    /// Out of bounds is ignored
+    pub fn set(&mut self, x: usize, y: usize, val: u8) {
+        if let Some(ptr) = self.grid.get_mut(y).and_then(|r| r.get_mut(x)) {
+            *ptr = val.min(self.life)
+        }
+    }
+
+    pub fn fill(&mut self, val: u8) {
+        let val = val.min(self.life);
+        self.grid.iter_mut().flatten().for_each(|c| *c = val)
+    }
+
+    /// Copies the live cells of `pattern` with its top-left at x, y, clipping what's outside
+    pub fn stamp(&mut self, pattern: &Grid, x: usize, y: usize) {
+        self.combine(pattern, x, y, Combine::Overwrite)
+    }
+
+    /// Combines every cell of `pattern` with the one under it, top-left at x, y
+    pub fn combine(&mut self, pattern: &Grid, x: usize, y: usize, op: Combine) {
+        for (py, row) in pattern.iter().enumerate() {
+            for (px, cell) in row.iter().enumerate() {
+                let (Some(x), Some(y)) = (x.checked_add(px), y.checked_add(py)) else {
+                    continue;
+                };
+                if let Some(under) = self.get(x, y) {
+                    self.set(x, y, op.apply(under, (*cell).min(self.life)))
+                }
+            }
+        }
+    }
+
+    /// Replaces each cell with `f(x, y, cell)`, row by row
+    pub fn map(&mut self, mut f: impl FnMut(usize, usize, u8) -> u8) {
+        let life = self.life;
+        for (y, row) in self.grid.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = f(x, y, *cell).min(life)
+            }
+        }
+    }
+}
+// GridView }}}
+
+#[deprecated(note = "use Grid::get_cell")]
+pub fn get_cell(grid: &Grid, x: usize, y: usize) -> Option<u8> {
+    grid.get_cell(x, y)
+}
+
+/// Positions within `radius` of `x`, `y` in the `(corners, polars)` shape `CellOpts::shape`
+/// gives, corners last. Ones off the top or left wrap to huge values, so like the ones off
+/// the bottom or right they read back `None`
+pub fn neighborhood(
+    x: usize,
+    y: usize,
+    (corners, polars): (bool, bool),
+    radius: u8,
+) -> impl Iterator<Item = (usize, usize)> {
+    // the usual 3×3 is spelled out, it's what nearly every advance counts
+    let near = radius == 1;
+    let sides = [
+        (x, y.wrapping_sub(1)),
+        (x + 1, y),
+        (x, y + 1),
+        (x.wrapping_sub(1), y),
+    ];
+    let diagonals = [
+        (x + 1, y.wrapping_sub(1)),
+        (x + 1, y + 1),
+        (x.wrapping_sub(1), y + 1),
+        (x.wrapping_sub(1), y.wrapping_sub(1)),
+    ];
+    let r = if near { 0 } else { radius as isize };
+    let square = move || (-r..=r).flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)));
+    let far = square()
+        .filter(move |(dx, dy)| polars && (*dx == 0) != (*dy == 0))
+        .chain(square().filter(move |(dx, dy)| corners && *dx != 0 && *dy != 0))
+        .map(move |(dx, dy)| (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy)));
+    sides
+        .into_iter()
+        .take(if polars && near { 4 } else { 0 })
+        .chain(
+            diagonals
+                .into_iter()
+                .take(if corners && near { 4 } else { 0 }),
+        )
+        .chain(far)
+}
+
+/// `neighborhood` on a `w`×`h` torus, positions past an edge moved to the other side.
+/// Small grids can list the same cell more than once
+pub fn neighborhood_wrapped(
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    shape: (bool, bool),
+    radius: u8,
+) -> impl Iterator<Item = (usize, usize)> {
+    let wrap = |n: usize, size: usize| match n {
+        usize::MAX => size - 1,
+        n if n < size => n,
+        n if n < 2 * size => n - size,
+        // further out than the grid is wide, or off the top or left by more than 1
+        n => (n as isize).rem_euclid(size as isize) as usize,
+    };
+    neighborhood(x, y, shape, radius).map(move |(nx, ny)| (wrap(nx, w), wrap(ny, h)))
+}
+
+/// Spots a game repeating itself by remembering hashes of recent generations
+#[derive(Clone, Debug, Default)]
+pub struct PeriodDetector {
+    history: VecDeque<u64>,
+    max_period: usize,
+}
+
+impl PeriodDetector {
+    pub fn new(max_period: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(max_period),
+            max_period,
+        }
+    }
+
+    /// Records the current cells, returning the period if they match one of the last
+    /// `max_period` observed. A still life has period 1
+    pub fn observe(&mut self, game: &Game) -> Option<usize> {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        game.grid().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let period = self
+            .history
+            .iter()
+            .rev()
+            .position(|h| *h == hash)
+            .map(|n| n + 1);
+        if self.history.len() == self.max_period {
+            self.history.pop_front();
+        }
+        self.history.push_back(hash);
+        period
+    }
+
+    /// Forgets everything, for after the cells were changed by hand
+    pub fn clear(&mut self) {
+        self.history.clear()
+    }
+}
+
+/// Which side of `x`, `y` most of its neighbors that count under `opts` are on in `grid`,
+/// from the sum of their offsets. `None` when they balance out, and a tie between the axes
+/// goes to left or right
+pub fn parent_side(grid: &Grid, x: usize, y: usize, opts: &CellOpts, wrap: bool) -> Option<Side> {
+    let (w, h) = (grid[y].len() as isize, grid.len() as isize);
+    let (mut sx, mut sy) = (0, 0);
+    for (nx, ny) in neighborhood(x, y, opts.shape(), opts.radius) {
+        // off the top or left is past usize::MAX / 2, so these come out negative
+        let (dx, dy) = (nx.wrapping_sub(x) as isize, ny.wrapping_sub(y) as isize);
+        let cell = if wrap {
+            let (nx, ny) = (
+                (x as isize + dx).rem_euclid(w),
+                (y as isize + dy).rem_euclid(h),
+            );
+            grid.get_cell(nx as usize, ny as usize)
+        } else {
+            grid.get_cell(nx, ny)
+        };
+        if cell.is_some_and(|c| opts.counts(c, opts.life)) {
+            (sx, sy) = (sx + dx, sy + dy)
+        }
+    }
+    match (sx, sy) {
+        (0, 0) => None,
+        _ if sx.abs() >= sy.abs() && sx < 0 => Some(Side::Left),
+        _ if sx.abs() >= sy.abs() => Some(Side::Right),
+        _ if sy < 0 => Some(Side::Up),
+        _ => Some(Side::Down),
+    }
+}
+
+/// Live cells around `x`, `y`. Everything past the edges is dead
+pub fn neighbors<C: Cell>(
+    grid: &Grid<C>,
+    x: usize,
+    y: usize,
+    shape: (bool, bool),
+    radius: u8,
+) -> usize {
+    neighborhood(x, y, shape, radius)
+        .filter_map(|(x, y)| grid.get_cell(x, y))
+        .filter(|c| c.alive())
+        .count()
+}
+
+/// `neighbors` with the edges joined. `x`, `y` must be on the grid
+pub fn neighbors_wrapped<C: Cell>(
+    grid: &Grid<C>,
+    x: usize,
+    y: usize,
+    shape: (bool, bool),
+    radius: u8,
+) -> usize {
+    let (w, h) = (grid[y].len(), grid.len());
+    neighborhood_wrapped(x, y, w, h, shape, radius)
+        .filter_map(|(x, y)| grid.get_cell(x, y))
+        .filter(|c| c.alive())
+        .count()
+}
+
+// SumTable {{{
+/// Live cells in every rectangle from the top left corner, for counting any box of
+/// cells in constant time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumTable {
+    width: usize,
+    height: usize,
+    /// `(width + 1)`×`(height + 1)`, row major, with a zero row and column leading
+    sums: Vec<u32>,
+}
+
+impl SumTable {
+    pub fn new<C: Cell>(grid: &Grid<C>) -> Self {
+        let width = grid.first().map(|r| r.len()).unwrap_or(0);
+        let height = grid.len();
+        let stride = width + 1;
+        let mut sums = vec![0; stride * (height + 1)];
+        for (y, row) in grid.iter().enumerate() {
+            let mut run = 0;
+            for (x, cell) in row.iter().enumerate() {
+                run += cell.alive() as u32;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + run
+            }
+        }
+        Self {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    /// Live cells in columns `x0..x1` of rows `y0..y1`, clipped to the grid
+    pub fn sum(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> usize {
+        let (x1, y1) = (x1.min(self.width), y1.min(self.height));
+        if x0 >= x1 || y0 >= y1 {
+            return 0;
+        }
+        let stride = self.width + 1;
+        let at = |x: usize, y: usize| self.sums[y * stride + x];
+        (at(x1, y1) + at(x0, y0) - at(x0, y1) - at(x1, y0)) as usize
+    }
+
+    /// Live cells within `radius` of `x`, `y` in both directions, including it
+    pub fn around(&self, x: usize, y: usize, radius: usize) -> usize {
+        self.sum(
+            x.saturating_sub(radius),
+            y.saturating_sub(radius),
+            x + radius + 1,
+            y + radius + 1,
+        )
+    }
+}
+
+/// Experimental `Grid::advance` counting neighbors from a `SumTable` instead of
+/// looking at each one. Returns how many cells are alive after
+pub fn advance_sumtable(from: &Grid, to: &mut Grid, opts: CellOpts) -> usize {
+    // the table only counts live cells
+    if opts.mature_neighbors_only {
+        return from.advance_with(to, opts, opts.life);
+    }
+    assert_eq!(from.len(), to.len());
+    let table = SumTable::new(from);
+    let (life, r) = (opts.life, opts.radius as usize);
+
+    let mut population = 0;
+    for (y, row) in to.iter_mut().enumerate() {
+        assert_eq!(from[y].len(), row.len());
+        for (x, cell) in row.iter_mut().enumerate() {
+            let current = from[y][x].min(life);
+            let alive = current.alive() as usize;
+            // the row and column through the cell, each holding it once
+            let (x0, y0) = (x.saturating_sub(r), y.saturating_sub(r));
+            let polars = || {
+                table.sum(x0, y, x + r + 1, y + 1) + table.sum(x, y0, x + 1, y + r + 1) - 2 * alive
+            };
+            let neighbors = match opts.shape() {
+                (true, true) => table.around(x, y, r) - alive,
+                (false, true) => polars(),
+                (true, false) => table.around(x, y, r) - alive - polars(),
+                (false, false) => 0,
+            };
+            *cell = opts.next(current, neighbors, life);
+            population += cell.alive() as usize
+        }
+    }
+    population
+}
+// SumTable }}}
+
+/// `Game::advance` as a free function, for treating `Game` as plain data
+pub fn advance_game(game: &mut Game) -> StepInfo {
+    game.advance()
+}
+
+#[deprecated(note = "use Grid::advance")]
+pub fn advance(from: &Grid, to: &mut Grid, opts: CellOpts) {
+    from.advance(to, opts)
+}
+
+/// Shrinks by `scale` in both directions, each new cell the max of the block it covers.
+/// Zoomed out views use this so lone cells don't vanish
+pub fn downsample(grid: &Grid, scale: usize) -> Grid {
+    let scale = scale.max(1);
+    let w = grid.first().map(|r| r.len()).unwrap_or(0);
+    grid.chunks(scale)
+        .map(|rows| {
+            (0..w.div_ceil(scale))
+                .map(|x| {
+                    rows.iter()
+                        .flat_map(|r| r.iter().skip(x * scale).take(scale))
+                        .copied()
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// `w`×`h` cells, each at `life` with a chance of `density` and dead otherwise
+pub fn new_random_grid(w: usize, h: usize, life: u8, density: f32) -> Grid {
+    random_grid(w, h, life, density, &mut rand::thread_rng())
+}
+
+/// `new_random_grid` from `rng`, so a seed gives the same grid
+pub fn random_grid(w: usize, h: usize, life: u8, density: f32, rng: &mut impl Rng) -> Grid {
+    (0..h)
+        .map(|_| {
+            (0..w)
+                .map(|_| if rng.gen::<f32>() < density { life } else { 0 })
+                .collect()
+        })
+        .collect()
+}
+
+#[deprecated(note = "use Grid::resize")]
+pub fn resize(grid: &mut Grid, x: usize, y: usize) {
+    grid.resize(x, y)
+}
+
+#[cfg(test)]
+mod soak;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(w: usize, h: usize, cells: &[(usize, usize)]) -> Game {
+        Game::from_coords(cells, w, h, conway()).unwrap()
+    }
+
+    fn assert_period_2(w: usize, h: usize, phase_a: &[(usize, usize)], phase_b: &[(usize, usize)]) {
+        let mut game = build(w, h, phase_a);
+        let (a, b) = (game.clone(), build(w, h, phase_b));
+
+        game.advance();
+        assert_eq!(game, b);
+        game.advance();
+        assert_eq!(game, a);
+    }
+
+    #[test]
+    fn from_coords() {
+        let game = build(4, 3, &[(0, 0), (3, 2)]);
+        assert_eq!(
+            game.grid(),
+            &vec![vec![1, 0, 0, 0], vec![0; 4], vec![0, 0, 0, 1]]
+        );
+
+        assert_eq!(
+            Game::from_coords(&[(1, 1), (4, 1)], 4, 3, conway()).unwrap_err(),
+            OutOfBoundsError {
+                x: 4,
+                y: 1,
+                w: 4,
+                h: 3
+            }
+        );
+    }
+
+    #[test]
+    fn to_coords() {
+        let coords = [(0, 1), (2, 0), (3, 2), (1, 1)];
+        let small = build(4, 3, &coords);
+        let big = build(40, 30, &coords);
+        assert_eq!(small.to_coords(), [(0, 1), (1, 1), (2, 0), (3, 2)]);
+        assert_eq!(small.to_coords(), big.to_coords());
+    }
+
+    /// A glider heading towards the bottom right with its corner at x, y.
+    /// Mirrored when `flip` is set for that axis
+    fn glider(x: usize, y: usize, flip: (bool, bool)) -> Vec<(usize, usize)> {
+        [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+            .into_iter()
+            .map(|(gx, gy)| {
+                (
+                    x + if flip.0 { 2 - gx } else { gx },
+                    y + if flip.1 { 2 - gy } else { gy },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn glider_reaches_edges() {
+        let none = Edges::default();
+        for (coords, edge) in [
+            (glider(1, 3, (false, true)), Edges { top: true, ..none }),
+            (
+                glider(6, 1, (false, false)),
+                Edges {
+                    right: true,
+                    ..none
+                },
+            ),
+            (
+                glider(1, 6, (false, false)),
+                Edges {
+                    bottom: true,
+                    ..none
+                },
+            ),
+            (glider(3, 1, (true, false)), Edges { left: true, ..none }),
+        ] {
+            let mut game = build(12, 12, &coords);
+            let mut steps = 0;
+            while !game.near_edges(0).any() {
+                assert!(steps < 100, "glider never reached {:?}", edge);
+                game.advance();
+                steps += 1;
+            }
+            assert_eq!(game.near_edges(0), edge);
+
+            let before = game.to_coords();
+            let (dx, dy) = game.expand(game.near_edges(2), 5, (100, 100));
+            assert_eq!(
+                game.size(),
+                (
+                    12 + edge.left as usize * 5 + edge.right as usize * 5,
+                    12 + edge.top as usize * 5 + edge.bottom as usize * 5
+                )
+            );
+            assert_eq!(
+                game.to_coords(),
+                before
+                    .iter()
+                    .map(|(x, y)| (x + dx, y + dy))
+                    .collect::<Vec<_>>()
+            );
+            assert!(!game.near_edges(2).any());
+
+            // both buffers grew so it keeps running
+            game.advance();
+            game.advance();
+        }
+    }
+
+    #[test]
+    fn expand_cap() {
+        let mut game = build(10, 10, &[(0, 0)]);
+        let all = Edges {
+            top: true,
+            right: true,
+            bottom: true,
+            left: true,
+        };
+        assert_eq!(game.expand(all, 8, (16, 12)), (6, 2));
+        assert_eq!(game.size(), (16, 12));
+        assert_eq!(game.to_coords(), [(6, 2)]);
+        assert_eq!(game.expand(all, 8, (16, 12)), (0, 0));
+    }
+
+    #[test]
+    fn resize_zeroes_both_buffers() {
+        let mut game = Game::new(conway());
+        game.resize(10, 10);
+        game.g1 = Grid::from(vec![vec![1; 10]; 10]);
+        game.g2 = Grid::from(vec![vec![1; 10]; 10]);
+        assert!(!game.switch);
+
+        game.resize(20, 20);
+        assert_eq!(game.g2[15][15], 0);
+        assert_eq!(game.g1[15][15], 0);
+        assert_eq!(game.g2[5][15], 0);
+        assert_eq!(game.g2[15][5], 0);
+        // old region is untouched
+        assert_eq!(game.g2[9][9], 1);
+        assert_eq!(game.g1[9][9], 1);
+    }
+
+    #[test]
+    fn torus() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut game = Game::from_coords(&glider, 8, 6, conway()).unwrap();
+        game.set_wrap(true);
+        // a glider moves 1 diagonally every 4 generations, so back home after 8 across and 6 down
+        game.advance_n(4 * 24);
+        assert_eq!(game.to_coords().len(), 5);
+        let mut coords = game.to_coords();
+        coords.sort();
+        let mut start = glider.to_vec();
+        start.sort();
+        assert_eq!(coords, start);
+
+        // across the corner
+        assert_eq!(
+            neighborhood_wrapped(0, 0, 8, 6, (true, true), 1).collect::<Vec<_>>(),
+            [
+                (0, 5),
+                (1, 0),
+                (0, 1),
+                (7, 0),
+                (1, 5),
+                (1, 1),
+                (7, 1),
+                (7, 5)
+            ]
+        );
+        let grid: Grid = Grid::from(vec![vec![0, 0, 1], vec![0, 0, 0], vec![1, 0, 0]]);
+        assert_eq!(neighbors(&grid, 0, 0, (true, true), 1), 0);
+        assert_eq!(neighbors_wrapped(&grid, 0, 0, (true, true), 1), 2);
+
+        game.set_wrap(false);
+        game.advance_n(4 * 24);
+        assert_ne!(game.to_coords().len(), 5);
+    }
+
+    #[test]
+    fn step_info() {
+        use std::sync::{Arc, Mutex};
+
+        // blinker, 2 born and 2 dead each step
+        let mut game = Game::from_coords(&[(1, 2), (2, 2), (3, 2)], 5, 5, conway()).unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        game.set_step_hook(move |info, game| {
+            assert_eq!(game.generation(), info.generation);
+            log.lock().unwrap().push(*info)
+        });
+
+        let info = game.advance();
+        assert_eq!(
+            info,
+            StepInfo {
+                generation: 1,
+                births: 2,
+                deaths: 2,
+                survivors: 1,
+                changed: 4,
+                unchanged: 21,
+                population: 3,
+                elapsed: info.elapsed,
+            }
+        );
+        assert!(info.elapsed > Duration::ZERO);
+        assert_eq!(game.advance_n(3).map(|i| i.generation), Some(4));
+        assert_eq!(game.advance_n(0), None);
+        let generations = |seen: &Mutex<Vec<StepInfo>>| -> Vec<u64> {
+            seen.lock().unwrap().iter().map(|i| i.generation).collect()
+        };
+        assert_eq!(generations(&seen), [1, 2, 3, 4]);
+
+        // clones don't share the hook
+        let mut clone = game.clone();
+        clone.advance();
+        game.clear_step_hook();
+        game.advance();
+        assert_eq!(generations(&seen), [1, 2, 3, 4]);
+
+        // aging isn't a birth or a death
+        let mut game = Game::from_coords(
+            &[(1, 1)],
+            3,
+            3,
+            CellOpts {
+                life: 3,
+                ..conway()
+            },
+        )
+        .unwrap();
+        let info = game.advance();
+        assert_eq!((info.births, info.deaths, info.changed), (0, 0, 1));
+        assert_eq!((info.survivors, info.unchanged), (1, 8));
+    }
+
+    #[test]
+    fn empty_grids() {
+        for (w, h) in [(0, 0), (0, 5), (5, 0), (1, 1)] {
+            let mut game = Game::new(conway());
+            game.resize(w, h);
+            game.stamp(&Grid::from(vec![vec![1; 3]; 3]), 0, 0);
+            game.set_cell(0, 0, 1);
+            game.advance();
+            game.randomize(1.0, &mut rand::thread_rng());
+            game.expand(game.near_edges(2), 4, (0, 0));
+            assert_eq!(game.population(), (w * h).min(1));
+            assert_eq!(game.neighbor_histogram().iter().sum::<usize>(), w * h);
+            assert_eq!(game.generation(), 1);
+            assert!(format!("{:?}", game).contains(&format!(
+                "{}×{}",
+                game.size().0,
+                game.size().1
+            )));
+        }
+        assert_eq!(super::downsample(&Grid::new(), 4), Grid::new());
+    }
+
+    #[test]
+    fn static_regions() {
+        // a blinker half frozen keeps its frozen cells, which still feed births
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        game.add_static_region(Rect::between((3, 3), (1, 2)));
+        assert!(game.is_static(2, 3) && !game.is_static(2, 1) && !game.is_static(9, 0));
+        game.advance();
+        assert_eq!(game.to_coords(), [(1, 2), (2, 1), (2, 2), (3, 2)]);
+
+        // regions follow the cells when the world grows
+        game.expand(
+            Edges {
+                left: true,
+                top: true,
+                ..Edges::default()
+            },
+            2,
+            (100, 100),
+        );
+        assert_eq!(game.static_regions()[0], Rect::between((3, 4), (5, 5)));
+        assert!(game.is_static(3, 4));
+        game.resize(2, 2);
+        assert!(!game.is_static(1, 1));
+
+        game.clear_static_regions();
+        assert!(!game.is_static(3, 4));
+    }
+
+    #[test]
+    fn image_data() {
+        let grid = Grid::from(vec![vec![0, 1], vec![2, 0]]);
+        let palette = ColorPalette {
+            dead: (0, 0, 0, 0),
+            alive: vec![(1, 1, 1, 1), (2, 2, 2, 2)],
+        };
+        let data = grid.to_image_data(&conway(), 2, &palette);
+        // 4×4 pixels
+        assert_eq!(data.len(), 4 * 4 * 4);
+        let pixel = |x: usize, y: usize| data[(y * 4 + x) * 4];
+        assert_eq!([pixel(0, 0), pixel(2, 0), pixel(3, 1)], [0, 1, 1]);
+        // clamped to conway's life of 1
+        assert_eq!([pixel(0, 2), pixel(1, 3), pixel(2, 3)], [1, 1, 0]);
+        assert!(grid.to_image_data(&conway(), 0, &palette).is_empty());
+    }
+
+    #[test]
+    fn pressure() {
+        // B13/S012345678 only ever grows
+        let mut opts = conway();
+        opts.grow[1] = true;
+        opts.survive = [true; COUNTS];
+        let game = |policy| {
+            let mut game = Game::new(opts);
+            game.resize(10, 10);
+            game.set_cell(5, 5, 1);
+            game.set_pressure(
+                Some(Pressure {
+                    max_density: 0.3,
+                    policy,
+                }),
+                7,
+            );
+            game
+        };
+
+        let mut births = game(PressurePolicy::SuppressBirths);
+        let mut sizes = Vec::new();
+        for _ in 0..4 {
+            let info = births.advance();
+            sizes.push(births.population());
+            assert_eq!(info.population, births.to_coords().len());
+        }
+        // one step over the cap, then nothing more is born
+        assert_eq!(sizes, [9, 17, 33, 33]);
+        let info = births.advance();
+        assert_eq!((info.births, info.deaths, info.changed), (0, 0, 0));
+
+        for policy in [PressurePolicy::CullOldest, PressurePolicy::CullRandom] {
+            let mut cull = game(policy);
+            let before = cull.advance_n(2).unwrap().population;
+            let info = cull.advance();
+            assert_eq!(cull.population(), 30);
+            assert_eq!(cull.to_coords().len(), 30);
+            // the culls show up as deaths or as births that never happened
+            assert_eq!(info.population, 30);
+            assert_eq!(info.births + info.survivors, 30);
+            assert_eq!(info.survivors + info.deaths, before);
+            assert_eq!(info.changed + info.unchanged, 100);
+        }
+
+        // a still life in a static region is never culled, even where the oldest cells are
+        for policy in [PressurePolicy::CullOldest, PressurePolicy::CullRandom] {
+            let mut cull = game(policy);
+            let block = [(0, 0), (1, 0), (0, 1), (1, 1)];
+            for (x, y) in block {
+                cull.set_cell(x, y, 1)
+            }
+            cull.add_static_region(Rect::between((0, 0), (1, 1)));
+            for _ in 0..4 {
+                let info = cull.advance();
+                assert_eq!(info.population, cull.to_coords().len());
+                assert!(block.iter().all(|(x, y)| cull.get_cell(*x, *y) == Some(1)));
+            }
+            assert_eq!(cull.population(), 30);
+        }
+
+        let mut off = game(PressurePolicy::CullOldest);
+        off.set_pressure(None, 0);
+        off.advance_n(3);
+        assert_eq!(off.population(), 33);
+    }
+
+    #[test]
+    fn determinism() {
+        let mut opts = conway();
+        opts.grow[1] = true;
+        opts.survive = [true; COUNTS];
+        let cull = Pressure {
+            max_density: 0.3,
+            policy: PressurePolicy::CullRandom,
+        };
+        let random = Some(cull);
+        let mut game = Game::new(opts);
+        game.resize(10, 10);
+        game.set_cell(5, 5, 1);
+
+        // seeded culls replay
+        game.set_pressure(random, 7);
+        game.advance_n(3);
+        assert_eq!(game.nondeterminism(), None);
+
+        // unseeded ones are only noticed once they happen
+        game.reset();
+        game.set_cell(5, 5, 1);
+        game.set_pressure_from_entropy(random).unwrap();
+        assert_eq!(game.nondeterminism(), None);
+        game.advance_n(2);
+        assert_eq!(game.nondeterminism(), None);
+        game.advance();
+        assert_eq!(
+            game.nondeterminism(),
+            Some(Nondeterminism::UnseededPressure)
+        );
+        // the first cause is kept
+        game.note_nondeterminism(Nondeterminism::Entropy("noise"))
+            .unwrap();
+        assert_eq!(
+            game.nondeterminism(),
+            Some(Nondeterminism::UnseededPressure)
+        );
+        game.reset_nondeterminism();
+        assert_eq!(game.nondeterminism(), None);
+
+        game.set_strict_determinism(true);
+        assert_eq!(
+            game.set_pressure_from_entropy(random),
+            Err(Nondeterminism::UnseededPressure)
+        );
+        let births = Some(Pressure {
+            policy: PressurePolicy::SuppressBirths,
+            ..cull
+        });
+        assert_eq!(game.set_pressure_from_entropy(births), Ok(()));
+        assert!(game
+            .note_nondeterminism(Nondeterminism::Entropy("noise"))
+            .is_err());
+        game.advance_n(3);
+        assert_eq!(game.nondeterminism(), None);
+    }
+
+    #[test]
+    fn binary_matches_multistate() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let (w, h) = (rng.gen_range(0..12), rng.gen_range(0..12));
+            let opts = CellOpts {
+                corners: rng.gen(),
+                polars: rng.gen(),
+                radius: 1,
+                life: 1,
+                grow: rng.gen(),
+                survive: rng.gen(),
+                mature_neighbors_only: false,
+            };
+            // a few raw values past life too
+            let from: Grid = (0..h)
+                .map(|_| (0..w).map(|_| [0, 0, 1, 7][rng.gen_range(0..4)]).collect())
+                .collect();
+            let frozen: Vec<bool> = (0..w * h).map(|_| rng.gen_bool(0.2)).collect();
+            let frozen = rng.gen_bool(0.5).then_some(&frozen[..]);
+
+            let (mut binary, mut multi) = (from.clone(), from.clone());
+            let wrap = rng.gen();
+            let count = from.advance_binary(&mut binary, opts, frozen, wrap);
+            assert_eq!(
+                count,
+                from.advance_masked(&mut multi, opts, 1, frozen, wrap)
+            );
+            assert_eq!(binary, multi, "{} from {:?}", opts, from);
+        }
+    }
+
+    #[test]
+    fn sumtable_matches_advance() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..200 {
+            let (w, h) = (rng.gen_range(0..12), rng.gen_range(0..12));
+            let opts = CellOpts {
+                corners: rng.gen(),
+                polars: rng.gen(),
+                radius: rng.gen_range(1..=MAX_RADIUS),
+                life: rng.gen_range(1..5),
+                grow: rng.gen(),
+                survive: rng.gen(),
+                mature_neighbors_only: false,
+            };
+            let from: Grid = (0..h)
+                .map(|_| {
+                    (0..w)
+                        .map(|_| [0, 0, 1, 3, 9][rng.gen_range(0..5)])
+                        .collect()
+                })
+                .collect();
+
+            let (mut table, mut scan) = (from.clone(), from.clone());
+            let count = advance_sumtable(&from, &mut table, opts);
+            assert_eq!(count, from.advance_with(&mut scan, opts, opts.life));
+            assert_eq!(table, scan, "{} from {:?}", opts, from);
+        }
+
+        // wider boxes against counting by hand
+        let from: Grid = (0..9)
+            .map(|_| (0..7).map(|_| rng.gen_range(0..2)).collect())
+            .collect();
+        let table = SumTable::new(&from);
+        for (x, y) in [(0usize, 0usize), (3, 4), (6, 8), (5, 1)] {
+            let count = (y.saturating_sub(2)..(y + 3).min(9))
+                .flat_map(|y| (x.saturating_sub(2)..(x + 3).min(7)).map(move |x| (x, y)))
+                .filter(|(x, y)| from[*y][*x] != 0)
+                .count();
+            assert_eq!(table.around(x, y, 2), count, "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn sumtable_cost() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let from: Grid = (0..500)
+            .map(|_| (0..500).map(|_| rng.gen_bool(0.3) as u8 * 5).collect())
+            .collect();
+        let mut to = from.clone();
+        let opts = CellOpts {
+            life: 5,
+            ..conway()
+        };
+        const STEPS: u32 = 50;
+
+        let start = std::time::Instant::now();
+        for _ in 0..STEPS {
+            from.advance(&mut to, opts)
+        }
+        eprintln!("scan: {:?} per step", start.elapsed() / STEPS);
+        let start = std::time::Instant::now();
+        for _ in 0..STEPS {
+            advance_sumtable(&from, &mut to, opts);
+        }
+        eprintln!("sumtable: {:?} per step", start.elapsed() / STEPS);
+    }
+
+    #[test]
+    fn noise() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut game = Game::new(CellOpts {
+            life: 4,
+            ..conway()
+        });
+        game.resize(100, 100);
+        game.stamp(&Grid::from(vec![vec![2; 100]; 50]), 0, 0);
+        let before = game.clone();
+
+        let changed = game.apply_noise(0.1, &mut rng);
+        assert!((800..1200).contains(&changed), "{}", changed);
+        let (mut born, mut killed) = (0, 0);
+        for y in 0..100 {
+            for x in 0..100 {
+                match (before.get_cell(x, y), game.get_cell(x, y)) {
+                    (Some(0), Some(4)) => born += 1,
+                    (Some(2), Some(0)) => killed += 1,
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+        }
+        assert_eq!(born + killed, changed);
+        assert_eq!(game.population(), 5000 + born - killed);
+
+        assert_eq!(game.apply_noise(0.0, &mut rng), 0);
+    }
+
+    #[test]
+    fn next_cell() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let generations = CellOpts {
+            life: 3,
+            ..conway()
+        };
+        for opts in [generations, "B3/S23/G3/M".parse().unwrap()] {
+            let mut game = Game::new(opts);
+            game.resize(12, 9);
+            game.randomize(0.4, &mut rng);
+            // some cells decaying, which /M leaves out
+            game.advance();
+            game.add_static_region(Rect::between((1, 1), (3, 2)));
+            for wrap in [false, true] {
+                game.set_wrap(wrap);
+                let expected: Vec<Option<u8>> = (0..9)
+                    .flat_map(|y| (0..12).map(move |x| (x, y)))
+                    .map(|(x, y)| game.next_cell(x, y))
+                    .collect();
+                let mut next = game.clone();
+                next.advance();
+                let actual: Vec<Option<u8>> = (0..9)
+                    .flat_map(|y| (0..12).map(move |x| (x, y)))
+                    .map(|(x, y)| next.get_cell(x, y))
+                    .collect();
+                assert_eq!(actual, expected, "{}", opts);
+            }
+        }
+        let game = build(12, 9, &[]);
+        assert_eq!(game.next_cell(12, 0), None);
+        assert_eq!(game.neighbors_at(0, 9), None);
+    }
+
+    #[test]
+    fn random_grids() {
+        let grid = new_random_grid(200, 100, 3, 0.3);
+        assert_eq!(grid.len(), 100);
+        assert!(grid.iter().all(|r| r.len() == 200));
+        assert!(grid.iter().flatten().all(|c| *c == 0 || *c == 3));
+        let alive = grid.iter().flatten().filter(|c| **c != 0).count();
+        let density = alive as f32 / 20000.0;
+        assert!((density - 0.3).abs() < 0.03, "{}", density);
+
+        assert_eq!(new_random_grid(0, 5, 1, 0.5), vec![Vec::<u8>::new(); 5]);
+        assert!(new_random_grid(10, 10, 1, 0.0)
+            .iter()
+            .flatten()
+            .all(|c| *c == 0));
+        let seeded = |seed| random_grid(8, 8, 1, 0.5, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(seeded(4), seeded(4));
+    }
+
+    #[test]
+    fn advance_for() {
+        let mut game = Game::new(conway());
+        game.resize(20, 20);
+        game.randomize(0.3, &mut StdRng::seed_from_u64(2));
+        assert_eq!(game.advance_for(Duration::ZERO), 0);
+        let ran = game.advance_for(Duration::from_millis(5));
+        assert!(ran > 0);
+        assert_eq!(game.generation(), ran as u64);
+        assert_eq!(game.advance_up_to(3, Duration::from_secs(60)), 3);
+        assert_eq!(game.generation(), ran as u64 + 3);
+    }
+
+    #[test]
+    fn trivial_rules() {
+        assert!(CellOpts::default().is_trivial());
+        assert!("B/S012345678".parse::<CellOpts>().unwrap().is_trivial());
+        assert!(!"B3/S23".parse::<CellOpts>().unwrap().is_trivial());
+        assert!(!"B/S01234567".parse::<CellOpts>().unwrap().is_trivial());
+        assert!(!"B0/S012345678".parse::<CellOpts>().unwrap().is_trivial());
+        // 5 to 8 neighbors can't happen without corners
+        let four = CellOpts {
+            corners: false,
+            polars: true,
+            grow: std::array::from_fn(|n| n >= 5),
+            survive: std::array::from_fn(|n| n < 5),
+            radius: 1,
+            life: 1,
+            mature_neighbors_only: false,
+        };
+        assert!(four.is_trivial());
+
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        game.set_opts(four);
+        let before = game.to_coords();
+        game.advance_n(3);
+        assert_eq!(game.to_coords(), before);
+    }
+
+    #[test]
+    fn random_rules() {
+        let rule = |s: &str| s.parse::<CellOpts>().unwrap();
+        assert!(!rule("B3/S23").is_boring());
+        assert!(!rule("B36/S23").is_boring());
+        assert!(!rule("B2/S").is_boring());
+        assert!(rule("B/S012345678").is_boring());
+        assert!(rule("B13/S23").is_boring());
+        assert!(rule("B5678/S45678").is_boring());
+        assert!(rule("B23456/S").is_boring());
+        // without corners 4 is the most there can be
+        assert!(!rule("B2/S1/C4").is_boring());
+        assert!(rule("B34/S1/C4").is_boring());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for shape in ["", "/C4", "/D4"] {
+            let like = rule(&format!("B/S{}/G3", shape));
+            for _ in 0..200 {
+                let opts = CellOpts::random(&like, false, &mut rng);
+                assert!(!opts.is_boring() && !opts.grow[0], "{}", opts);
+                assert_eq!((opts.shape(), opts.life), (like.shape(), 3));
+            }
+        }
+        let conway = rule("B3/S23");
+        assert!((0..200).any(|_| CellOpts::random(&conway, true, &mut rng).grow[0]));
+        // seeded, so a seed names a rule
+        let again = |seed| CellOpts::random(&conway, false, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(again(42), again(42));
+    }
+
+    #[test]
+    fn default_opts() {
+        let opts = CellOpts::default();
+        assert_eq!(opts.life, 1);
+        let mut game = Game::new(CellOpts {
+            grow: std::array::from_fn(|n| n == 3),
+            ..Default::default()
+        });
+        game.resize(5, 5);
+        for x in 1..4 {
+            game.set_cell(x, 2, 1)
+        }
+        game.advance();
+        // births reach a life of 1 instead of staying at 0
+        assert_eq!(game.get_cell(2, 1), Some(1));
+    }
+
+    #[test]
+    fn with_rule() -> Result<(), RuleParseError> {
+        let mut game = Game::with_rule("B3/S23")?;
+        assert_eq!(game.opts(), &conway());
+        game.resize(40, 20);
+        game.randomize(0.3, &mut StdRng::seed_from_u64(1));
+        assert!(game.population() > 0);
+        assert_eq!(Game::with_rule("B3/S2/G5")?.opts().life, 5);
+        assert!(Game::with_rule("S23").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rulestring() {
+        assert_eq!(conway().rulestring(), "B3/S23");
+        let opts = CellOpts {
+            corners: false,
+            life: 5,
+            ..conway()
+        };
+        assert_eq!(opts.rulestring(), "B3/S23/C4/G5");
+        assert_eq!(format!("{}", conway()), "B3/S23");
+        assert_eq!(opts.to_string(), opts.rulestring());
+    }
+
+    #[test]
+    fn neighborhood_shapes() {
+        // a plus around the middle, and an x
+        let plus: Grid = Grid::from(vec![vec![0, 1, 0], vec![1, 0, 1], vec![0, 1, 0]]);
+        let x: Grid = Grid::from(vec![vec![1, 0, 1], vec![0, 0, 0], vec![1, 0, 1]]);
+        for (rule, on_plus, on_x) in [
+            ("B3/S23", 4, 4),
+            ("B3/S23/C4", 4, 0),
+            ("B3/S23/D4", 0, 4),
+            ("B3/S23/C0", 0, 0),
+        ] {
+            let opts: CellOpts = rule.parse().unwrap();
+            assert_eq!(opts.to_string(), rule);
+            assert_eq!(opts.reachable(), on_plus + on_x);
+            assert_eq!(neighbors(&plus, 1, 1, opts.shape(), 1), on_plus, "{}", rule);
+            assert_eq!(neighbors(&x, 1, 1, opts.shape(), 1), on_x, "{}", rule);
+            assert_eq!(
+                neighbors_wrapped(&x, 1, 1, opts.shape(), 1),
+                on_x,
+                "{}",
+                rule
+            );
+        }
+
+        // diagonal only, a cell with 2 corner neighbors is born
+        let mut game = Game::new("B2/S/D4".parse().unwrap());
+        game.resize(5, 5);
+        game.set_cell(1, 1, 1);
+        game.set_cell(3, 3, 1);
+        game.advance();
+        assert_eq!(game.to_coords(), [(2, 2)]);
+        assert_eq!(game.neighbors_at(2, 1), Some(0));
+
+        // nothing to count, so only B0 and S0 matter
+        let none = |rule: &str| rule.parse::<CellOpts>().unwrap();
+        assert!(none("B12345678/S0/C0").is_trivial());
+        assert!(!none("B1/S/C0").is_trivial());
+    }
+
+    #[test]
+    fn rect_corners() {
+        let rect = Rect {
+            x: 2,
+            y: 3,
+            w: 4,
+            h: 2,
+        };
+        assert_eq!(Rect::between((5, 4), (2, 3)), rect);
+        assert_eq!(Rect::from_corners((2, 3), (6, 5)), rect);
+        assert_eq!(rect.end(), (6, 5));
+        assert!(rect.contains(2, 3) && rect.contains(5, 4));
+        assert!(!rect.contains(6, 4) && !rect.contains(5, 5) && !rect.contains(1, 3));
+
+        // a single cell, then nothing
+        assert_eq!(Rect::between((7, 7), (7, 7)).iter_cells().count(), 1);
+        let empty = Rect::from_corners((6, 5), (2, 3));
+        assert!(empty.is_empty());
+        assert!(!empty.contains(6, 5));
+        assert_eq!(empty.iter_cells().count(), 0);
+        let flat = Rect { h: 0, ..rect };
+        assert!(flat.is_empty() && !flat.contains(2, 3));
+
+        assert_eq!(
+            rect.iter_cells().collect::<Vec<_>>(),
+            [
+                (2, 3),
+                (3, 3),
+                (4, 3),
+                (5, 3),
+                (2, 4),
+                (3, 4),
+                (4, 4),
+                (5, 4)
+            ]
+        );
+        let huge = Rect {
+            x: usize::MAX - 1,
+            y: 0,
+            w: 10,
+            h: 1,
+        };
+        assert_eq!(huge.end(), (usize::MAX, 1));
+    }
+
+    #[test]
+    fn rect_intersect_and_union() {
+        let a = Rect::from_corners((0, 0), (4, 4));
+        let b = Rect::from_corners((2, 1), (6, 3));
+        assert_eq!(a.intersect(&b), Some(Rect::from_corners((2, 1), (4, 3))));
+        assert_eq!(b.intersect(&a), a.intersect(&b));
+        assert_eq!(a.intersect(&a), Some(a));
+        assert_eq!(a.union(&b), Rect::from_corners((0, 0), (6, 4)));
+
+        // touching edges share no cells, and empty rects share nothing
+        let beside = Rect::from_corners((4, 0), (8, 4));
+        assert_eq!(a.intersect(&beside), None);
+        assert_eq!(a.union(&beside), Rect::from_corners((0, 0), (8, 4)));
+        let far = Rect::from_corners((10, 10), (12, 12));
+        assert_eq!(a.intersect(&far), None);
+        assert_eq!(a.union(&far), Rect::from_corners((0, 0), (12, 12)));
+        let nothing = Rect::from_corners((20, 20), (20, 25));
+        assert_eq!(a.intersect(&nothing), None);
+        assert_eq!(a.union(&nothing), a);
+        assert_eq!(nothing.union(&a), a);
+        assert_eq!(Rect::default().union(&Rect::default()), Rect::default());
+    }
+
+    #[test]
+    fn rect_clamping() {
+        let rect = Rect::from_corners((3, 3), (8, 6));
+        assert_eq!(rect.clamp_to(10, 10), rect);
+        assert_eq!(rect.clamp_to(5, 4), Rect::from_corners((3, 3), (5, 4)));
+        assert!(rect.clamp_to(3, 10).is_empty());
+        assert!(rect.clamp_to(0, 0).is_empty());
+        let past = Rect {
+            x: 2,
+            y: 0,
+            w: usize::MAX,
+            h: usize::MAX,
+        };
+        assert_eq!(past.clamp_to(5, 4), Rect::from_corners((2, 0), (5, 4)));
+    }
+
+    #[test]
+    fn extract_and_clear() {
+        let mut game = build(5, 5, &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        let middle = Rect::from_corners((1, 1), (4, 4));
+        assert_eq!(
+            game.extract(middle),
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]]
+        );
+        assert_eq!(
+            game.extract(Rect::from_corners((3, 3), (9, 9))),
+            vec![vec![1, 0], vec![0, 1]]
+        );
+        assert!(game.extract(Rect::from_corners((7, 7), (9, 9))).is_empty());
+
+        let mut inside = game.clone();
+        inside.clear_inside(middle);
+        assert_eq!(inside.to_coords(), [(0, 0), (4, 4)]);
+        game.clear_outside(middle);
+        assert_eq!(game.to_coords(), [(1, 1), (2, 2), (3, 3)]);
+        game.clear_outside(Rect::default());
+        assert_eq!(game.population(), 0);
+    }
+
+    #[test]
+    fn mature_neighbors() {
+        // a dead cell between one at full life and one decaying
+        let step = |rule: &str, wrap: bool| {
+            let mut game = Game::new(rule.parse().unwrap());
+            game.resize(5, 5);
+            game.set_wrap(wrap);
+            game.set_cell(1, 2, 2);
+            game.set_cell(3, 2, 1);
+            game.advance();
+            game.get_cell(2, 2)
+        };
+        assert_eq!(step("B2/S/G2", false), Some(1));
+        assert_eq!(step("B2/S/G2/M", false), Some(0));
+        assert_eq!(step("B1/S/G2/M", false), Some(1));
+        assert_eq!(step("B1/S/G2/M", true), Some(1));
+
+        let opts: CellOpts = "B2/S/G2/M".parse().unwrap();
+        assert!(opts.mature_neighbors_only);
+        assert_eq!(opts.rulestring(), "B2/S/G2/M");
+        let from = Grid::from(vec![vec![2, 0, 1]]);
+        let (mut to, mut table) = (Grid::from(vec![vec![0; 3]]), Grid::from(vec![vec![0; 3]]));
+        from.advance(&mut to, opts);
+        advance_sumtable(&from, &mut table, opts);
+        assert_eq!(to, table);
+        assert_eq!(to, vec![vec![1, 0, 0]]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn ragged_rows() {
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        // one row longer, one shorter, and a row missing from the other buffer
+        game.grid_mut()[0].push(0);
+        game.grid_mut()[4].pop();
+        game.grid_mut().push(vec![0; 2]);
+        let info = game.advance();
+        assert_eq!(game.size(), (6, 6));
+        assert!(game.grid().iter().all(|r| r.len() == 6));
+        assert_eq!(info.population, 3);
+        assert_eq!(game.to_coords(), [(2, 1), (2, 2), (2, 3)]);
+        game.advance();
+        assert_eq!(game.to_coords(), [(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn die_shim() {
+        let mut opts = conway();
+        assert_eq!(
+            opts.die()[..9],
+            [true, true, false, false, true, true, true, true, true]
+        );
+        let mut die = [true; COUNTS];
+        die[1] = false;
+        opts.set_die(die);
+        assert_eq!(opts.rulestring(), "B3/S1");
+        assert_eq!(opts.die(), die);
+    }
+
+    #[test]
+    fn parse_rule() {
+        assert_eq!("B3/S23".parse(), Ok(conway()));
+        assert_eq!("b3/s23".parse(), Ok(conway()));
+        let opts: CellOpts = "B36/S23/C4/G5".parse().unwrap();
+        assert!(opts.grow[6] && !opts.corners && opts.life == 5);
+
+        let mut rules = vec![
+            conway(),
+            CellOpts {
+                corners: false,
+                life: 5,
+                ..conway()
+            },
+            CellOpts {
+                life: 0,
+                grow: [false; COUNTS],
+                survive: [false; COUNTS],
+                ..conway()
+            },
+            "B2/S/G2/M".parse().unwrap(),
+        ];
+        rules.extend(crate::demo::steps().iter().map(|s| s.rule));
+        for opts in rules {
+            assert_eq!(opts.to_string().parse::<CellOpts>(), Ok(opts));
+        }
+
+        for bad in [
+            "",
+            "B3",
+            "S23/B3",
+            "B9/S23",
+            "B3/S2x",
+            "B3/S23/C6",
+            "B3/S23/G300",
+            "B3/S23/R0",
+            "B3/S23/R3",
+        ] {
+            assert!(bad.parse::<CellOpts>().is_err(), "{}", bad);
+        }
+    }
+
+    #[cfg(not(feature = "large-neighborhood"))]
+    #[test]
+    fn radius_needs_feature() {
+        assert_eq!("B3/S23/R1".parse(), Ok(conway()));
+        let err = "B3/S23/R2".parse::<CellOpts>().unwrap_err();
+        assert!(err.message.contains("large-neighborhood"), "{}", err);
+    }
+
+    #[cfg(feature = "large-neighborhood")]
+    #[test]
+    fn radius_two() {
+        let opts: CellOpts = "B3,10/S2,3,24/R2".parse().unwrap();
+        assert!(opts.grow[3] && opts.grow[10] && opts.survive[24] && opts.radius == 2);
+        assert_eq!(opts.to_string(), "B3,10/S2,3,24/R2");
+        // single digits only reach 8 at a radius of 1
+        assert!("B9/S".parse::<CellOpts>().is_err());
+        assert!("B3,10/S".parse::<CellOpts>().is_err());
+        assert!("B3/S2,25/R2".parse::<CellOpts>().is_err());
+        for (shape, reachable) in [("", 24), ("/C4", 8), ("/D4", 16), ("/C0", 0)] {
+            let opts: CellOpts = format!("B3/S23/R2{}", shape).parse().unwrap();
+            assert_eq!(opts.reachable(), reachable, "{}", shape);
+            assert_eq!(opts.to_string().parse(), Ok(opts));
+        }
+
+        // a full 5×5, every cell within 2 of the middle
+        let full: Grid = vec![vec![1; 5]; 5].into();
+        assert_eq!(neighbors(&full, 2, 2, (true, true), 2), 24);
+        assert_eq!(neighbors(&full, 2, 2, (false, true), 2), 8);
+        assert_eq!(neighbors(&full, 0, 0, (true, true), 2), 8);
+        assert_eq!(neighbors_wrapped(&full, 0, 0, (true, true), 2), 24);
+        // past a 3×3's edges the wrapped positions come back around more than once
+        let spots: Vec<_> = neighborhood_wrapped(0, 0, 3, 3, (true, true), 2).collect();
+        assert_eq!(spots.len(), 24);
+        assert!(spots.iter().all(|(x, y)| *x < 3 && *y < 3));
+
+        // a lone cell births at 1 everywhere within reach, and dies
+        let mut opts: CellOpts = "B1/S/R2".parse().unwrap();
+        let mut game = Game::new(opts);
+        game.resize(7, 7);
+        game.set_cell(3, 3, 1);
+        game.advance();
+        assert_eq!(game.population(), 24);
+        assert_eq!(game.get_cell(3, 3), Some(0));
+        assert_eq!(game.get_cell(1, 1), Some(1));
+        opts.life = 3;
+        game.set_opts(opts);
+        game.clear();
+        game.set_cell(3, 3, 3);
+        game.advance();
+        assert_eq!((game.population(), game.get_cell(5, 3)), (25, Some(1)));
+    }
+
+    #[test]
+    fn neighbor_histogram() {
+        let game = build(5, 5, &[(2, 2)]);
+        assert_eq!(game.neighbor_histogram()[..9], [17, 8, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut game = Game::new(conway());
+        game.resize(5, 4);
+        game.edit(|view| view.fill(1));
+        // 6 interior, 4 corners, 10 on the edges
+        assert_eq!(game.neighbor_histogram()[..9], [0, 0, 0, 4, 0, 10, 0, 0, 6]);
+
+        let opts = CellOpts {
+            corners: false,
+            ..conway()
+        };
+        game.set_opts(opts);
+        assert_eq!(game.neighbor_histogram()[..9], [0, 0, 4, 10, 6, 0, 0, 0, 0]);
+
+        // a decaying cell only counts without /M
+        for (rule, middle) in [("B2/S/G3", 2), ("B2/S/G3/M", 1)] {
+            let mut game = Game::new(rule.parse().unwrap());
+            game.resize(3, 1);
+            game.set_cell(0, 0, 2);
+            game.set_cell(2, 0, 3);
+            assert_eq!(game.neighbors_at(1, 0), Some(middle), "{}", rule);
+            let mut expected = [0; COUNTS];
+            expected[0] = 2;
+            expected[middle] = 1;
+            assert_eq!(game.neighbor_histogram(), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn value_histogram() {
+        let mut game = Game::new(CellOpts {
+            life: 3,
+            ..conway()
+        });
+        game.resize(4, 1);
+        for (x, value) in [(0, 1), (1, 3), (2, 9)] {
+            game.set_cell(x, 0, value)
+        }
+        assert_eq!(game.value_histogram(), [1, 0, 2]);
+        assert_eq!(build(3, 3, &[]).value_histogram(), [0]);
+    }
+
+    #[test]
+    fn game_eq() {
+        let blinker = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        let mut later = blinker.clone();
+        later.advance();
+        assert_ne!(blinker, later);
+        // same cells in the other buffer
+        later.advance();
+        assert_eq!(blinker, later);
+
+        let mut other_rule = blinker.clone();
+        other_rule.set_opts(CellOpts {
+            corners: false,
+            ..conway()
+        });
+        assert_ne!(blinker, other_rule);
+    }
+
+    #[test]
+    fn generation_counter() {
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        game.advance_n(3);
+        assert_eq!(game.generation(), 3);
+        game.reset();
+        assert_eq!((game.generation(), game.population()), (0, 0));
+
+        game.generation = u64::MAX;
+        assert_eq!(game.advance().generation, 0);
+
+        // across the wrap one step at a time, as advance_n takes them
+        game.generation = u64::MAX - 1;
+        let seen = (0..3)
+            .map(|_| game.advance().generation)
+            .collect::<Vec<_>>();
+        assert_eq!(seen, [u64::MAX, 0, 1]);
+        game.generation = u64::MAX - 1;
+        assert_eq!(game.advance_n(3).map(|i| i.generation), Some(1));
+        assert_eq!(game.generation(), 1);
+    }
+
+    #[test]
+    fn buffers_dont_matter() {
+        let cells = [
+            (1, 0),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (6, 6),
+            (7, 6),
+            (8, 6),
+        ];
+        let mut game = build(10, 10, &cells);
+        game.advance_n(2);
+        assert!(!game.switch);
+        let mut fresh = build(10, 10, &cells);
+        fresh.advance_n(2);
+        assert_eq!((&game.g1, &game.g2), (&fresh.g1, &fresh.g2));
+
+        // the same cells starting in the other buffer step to the same cells
+        let mut flipped = build(10, 10, &[]);
+        flipped.swap_buffers();
+        assert!(flipped.switch);
+        for (x, y) in cells {
+            flipped.set_cell(x, y, 1)
+        }
+        let mut straight = build(10, 10, &cells);
+        for _ in 0..4 {
+            let (a, b) = (flipped.advance(), straight.advance());
+            assert_eq!(flipped.grid(), straight.grid());
+            assert_eq!((a.population, a.changed), (b.population, b.changed));
+        }
+    }
+
+    #[test]
+    fn grid_views() {
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        game.edit(|view| {
+            assert_eq!(view.size(), (5, 5));
+            view.set(0, 0, 9);
+            view.set(5, 0, 1);
+            view.set(usize::MAX, 0, 1);
+        });
+        assert_eq!(game.get_cell(0, 0), Some(1));
+        assert_eq!(game.population(), 4);
+
+        game.edit(|view| view.fill(0));
+        assert_eq!(game.population(), 0);
+        game.edit(|view| view.stamp(&Grid::from(vec![vec![1, 1]]), 4, 4));
+        assert_eq!(game.to_coords(), [(4, 4)]);
+        game.edit(|view| view.map(|x, y, _| (x == y) as u8 * 7));
+        assert_eq!(game.population(), 5);
+
+        // the back buffer is the previous generation
+        let mut blinker = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        let before = blinker.grid().clone();
+        blinker.advance();
+        assert_eq!(blinker.back_grid(), &before);
+        blinker.swap_buffers();
+        assert_eq!(blinker.grid(), &before);
+        assert_eq!(blinker.population(), 3);
+    }
+
+    /// A 4x1 row of 1 2 2 0 under a life of 2, and a 2x1 pattern of 3 0 to combine onto it
+    fn combining(op: Combine, x: usize) -> Grid {
+        Grid::from(vec![vec![1, 2, 2, 0]]).combined(&Grid::from(vec![vec![3, 0]]), x, 0, op, 2)
+    }
+
+    #[test]
+    fn combine_overwrite() {
+        assert_eq!(combining(Combine::Overwrite, 0), vec![vec![2, 2, 2, 0]]);
+        assert_eq!(combining(Combine::Overwrite, 3), vec![vec![1, 2, 2, 2]]);
+        assert_eq!(combining(Combine::Overwrite, 9), vec![vec![1, 2, 2, 0]]);
+    }
+
+    #[test]
+    fn combine_union() {
+        assert_eq!(combining(Combine::Union, 0), vec![vec![2, 2, 2, 0]]);
+        assert_eq!(combining(Combine::Union, 3), vec![vec![1, 2, 2, 2]]);
+    }
+
+    #[test]
+    fn combine_intersect() {
+        // only what's under the pattern is touched
+        assert_eq!(combining(Combine::Intersect, 0), vec![vec![1, 0, 2, 0]]);
+        assert_eq!(combining(Combine::Intersect, 3), vec![vec![1, 2, 2, 0]]);
+        assert_eq!(combining(Combine::Intersect, 9), vec![vec![1, 2, 2, 0]]);
+    }
+
+    #[test]
+    fn combine_subtract() {
+        assert_eq!(combining(Combine::Subtract, 1), vec![vec![1, 0, 2, 0]]);
+        assert_eq!(combining(Combine::Subtract, 3), vec![vec![1, 2, 2, 0]]);
+    }
+
+    #[test]
+    fn combine_xor() {
+        assert_eq!(combining(Combine::Xor, 0), vec![vec![0, 2, 2, 0]]);
+        assert_eq!(combining(Combine::Xor, 3), vec![vec![1, 2, 2, 2]]);
+    }
+
+    #[test]
+    fn combine_in_place() {
+        let mut game = build(4, 4, &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let block = Grid::from(vec![vec![u8::MAX; 2]; 2]);
+        game.combine(&block, 1, 1, Combine::Subtract);
+        assert_eq!(game.to_coords(), [(0, 0), (0, 1), (1, 0)]);
+        game.combine(&block, 3, 3, Combine::Xor);
+        assert_eq!(game.to_coords(), [(0, 0), (0, 1), (1, 0), (3, 3)]);
+        assert_eq!(game.get_cell(3, 3), Some(1));
+
+        assert_eq!("XOR".parse(), Ok(Combine::Xor));
+        assert_eq!(Combine::Subtract.to_string(), "subtract");
+        assert!("minus"
+            .parse::<Combine>()
+            .unwrap_err()
+            .contains("intersect"));
+    }
+
+    #[test]
+    fn deltas() {
+        let mut game = Game::new(conway());
+        game.resize(6, 6);
+        for (x, y) in glider(1, 1, (false, false)) {
+            game.set_cell(x, y, 1)
+        }
+        let prev = game.grid().clone();
+        assert!(game.encode_delta(&prev).is_empty());
+
+        let mut copy = game.clone();
+        game.advance();
+        let delta = game.encode_delta(&prev);
+        assert!(delta
+            .changed
+            .windows(2)
+            .all(|w| (w[0].1, w[0].0) < (w[1].1, w[1].0)));
+        // a glider step swaps 2 births for 2 deaths
+        assert_eq!(delta.changed.len(), 4);
+        copy.apply_delta(&delta);
+        assert_eq!(copy.grid(), game.grid());
+        assert_eq!(copy.population(), game.population());
+
+        // a smaller grid is dead where it doesn't reach, and a patch past the edges is dropped
+        let small: Grid = vec![vec![0; 2]; 2].into();
+        assert_eq!(game.encode_delta(&small).changed.len(), game.population());
+        let mut little = Game::new(conway());
+        little.resize(2, 2);
+        little.apply_delta(&DeltaEncoding {
+            changed: vec![(1, 1, 9), (5, 5, 1)],
+        });
+        assert_eq!(little.to_coords(), [(1, 1)]);
+        assert_eq!(little.get_cell(1, 1), Some(1));
+    }
+
+    #[test]
+    fn birth_sides() {
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        game.advance();
+        assert_eq!(game.birth_sides(), None);
+
+        game.set_birth_tracking(true);
+        assert_eq!(game.birth_sides(), Some(&[][..]));
+        game.advance();
+        let sides = game.birth_sides().unwrap();
+        // the blinker's new ends are beside the old middle
+        assert_eq!(sides[2 * 5 + 1], Some(Side::Right));
+        assert_eq!(sides[2 * 5 + 3], Some(Side::Left));
+        assert_eq!(sides.iter().flatten().count(), 2);
+        game.advance();
+        let sides = game.birth_sides().unwrap();
+        assert_eq!(sides[5 + 2], Some(Side::Down));
+        assert_eq!(sides[3 * 5 + 2], Some(Side::Up));
+
+        // parents across a wrapped edge are still beside it
+        let grid: Grid = vec![vec![0, 0, 0, 1], vec![0, 0, 0, 1], vec![0, 0, 0, 1]].into();
+        let opts = conway();
+        assert_eq!(parent_side(&grid, 0, 1, &opts, true), Some(Side::Left));
+        assert_eq!(parent_side(&grid, 0, 1, &opts, false), None);
+        assert_eq!(parent_side(&grid, 2, 1, &opts, false), Some(Side::Right));
+        // a tie goes sideways, and a balanced one is nowhere
+        let corner: Grid = vec![vec![1, 0, 0], vec![0, 0, 0], vec![0, 0, 0]].into();
+        assert_eq!(parent_side(&corner, 1, 1, &opts, false), Some(Side::Left));
+        let sides: Grid = vec![vec![0, 0, 0], vec![1, 0, 1], vec![0, 0, 0]].into();
+        assert_eq!(parent_side(&sides, 1, 1, &opts, false), None);
+
+        game.set_birth_tracking(false);
+        game.advance();
+        assert_eq!(game.birth_sides(), None);
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let original = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        let before = original.to_coords();
+
+        let mut clone = original.clone();
+        clone.advance();
+        assert_ne!(clone.to_coords(), before);
+        assert_eq!(original.to_coords(), before);
+
+        let mut clone = original.clone();
+        clone.set_cell(0, 0, 1);
+        clone.edit(|view| view.set(4, 4, 1));
+        assert_eq!(original.to_coords(), before);
+        assert_eq!(original.get_cell(0, 0), Some(0));
+    }
+
+    #[test]
+    fn periods() {
+        let mut detector = PeriodDetector::new(4);
+        let mut game = build(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        assert_eq!(detector.observe(&game), None);
+        game.advance();
+        assert_eq!(detector.observe(&game), None);
+        game.advance();
+        assert_eq!(detector.observe(&game), Some(2));
+
+        detector.clear();
+        game.clear();
+        assert_eq!(detector.observe(&game), None);
+        game.advance();
+        assert_eq!(detector.observe(&game), Some(1));
+
+        // a glider never repeats exactly
+        let mut detector = PeriodDetector::new(8);
+        let mut game = build(20, 20, &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        for _ in 0..30 {
+            assert_eq!(detector.observe(&game), None);
+            game.advance();
+        }
+    }
+
+    #[test]
+    fn debug() {
+        let mut game = build(4, 3, &[(1, 0), (1, 1), (1, 2)]);
+        game.advance();
+        assert_eq!(
+            format!("{:?}", game),
+            "Game 4×3, generation 1, population 3, B3/S23\n....\n###.\n....\n"
+        );
+
+        let big = build(50, 30, &[(0, 0)]);
+        let text = format!("{:?}", big);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1 + 20 + 1);
+        assert_eq!(lines[1], format!("#{}…", ".".repeat(39)));
+        assert_eq!(lines[21], "…");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn grid_methods_match_shims() {
+        let mut a = build(5, 3, &[(1, 1), (2, 1), (3, 1)]).grid().clone();
+        let mut b = a.clone();
+        a.resize(6, 4);
+        resize(&mut b, 6, 4);
+        assert_eq!(a, b);
+        assert_eq!(a.get_cell(2, 1), get_cell(&b, 2, 1));
+        assert_eq!(a.get_cell(6, 0), None);
+
+        let (mut next_a, mut next_b) = (a.clone(), b.clone());
+        a.advance(&mut next_a, conway());
+        advance(&b, &mut next_b, conway());
+        assert_eq!(next_a, next_b);
+        assert_eq!(next_a.get_cell(2, 0), Some(1));
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |opts: CellOpts| {
+            let mut hasher = DefaultHasher::new();
+            opts.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(conway()), hash(conway()));
+        let mut highlife = conway();
+        highlife.grow[6] = true;
+        assert_ne!(hash(conway()), hash(highlife));
+
+        let mut seen = std::collections::HashMap::new();
+        seen.insert(conway(), "life");
+        assert_eq!(seen.get(&conway()), Some(&"life"));
+    }
+
+    #[test]
+    fn advance_game_matches_method() {
+        let mut a = build(5, 3, &[(1, 1), (2, 1), (3, 1)]);
+        let mut b = a.clone();
+        a.advance();
+        advance_game(&mut b);
+        assert_eq!(a.grid(), b.grid());
+    }
+
+    #[test]
+    fn downsample() {
+        let grid = Grid::from(vec![
+            vec![0, 0, 0, 0, 0],
+            vec![0, 3, 0, 0, 1],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        assert_eq!(
+            super::downsample(&grid, 2),
+            vec![vec![3, 0, 1], vec![0, 0, 0]]
+        );
+        assert_eq!(super::downsample(&grid, 1), grid);
+        assert_eq!(super::downsample(&grid, 5), vec![vec![3]]);
+    }
+
+    #[test]
+    fn blinker() {
+        assert_period_2(5, 3, &[(1, 1), (2, 1), (3, 1)], &[(2, 0), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn beacon() {
+        assert_period_2(
+            6,
+            6,
+            &[
+                (1, 1),
+                (2, 1),
+                (1, 2),
+                (2, 2),
+                (3, 3),
+                (4, 3),
+                (3, 4),
+                (4, 4),
+            ],
+            &[(1, 1), (2, 1), (1, 2), (4, 3), (3, 4), (4, 4)],
+        );
+    }
+
+    #[test]
+    fn toad() {
+        assert_period_2(
+            6,
+            6,
+            &[(2, 2), (3, 2), (4, 2), (1, 3), (2, 3), (3, 3)],
+            &[(3, 1), (1, 2), (4, 2), (1, 3), (4, 3), (2, 4)],
+        );
     }
 }
@@ -0,0 +1,54 @@
+//! Cells past `u8`, for lives over 255 or fractional gradients
+
+use super::Cell;
+
+impl Cell for u16 {
+    fn grow(self, life: Self) -> Self {
+        self.saturating_add(1).min(life)
+    }
+
+    fn decay(self) -> Self {
+        self.saturating_sub(1)
+    }
+}
+
+impl Cell for f32 {
+    fn grow(self, life: Self) -> Self {
+        (self + 1.0).min(life)
+    }
+
+    fn decay(self) -> Self {
+        (self - 1.0).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{conway, Grid};
+
+    /// A blinker's middle survives while its ends decay and new ends are born
+    fn blinker<C: super::Cell + std::fmt::Debug>(life: C, live: C) -> Vec<Vec<C>> {
+        let d = C::default();
+        let from = Grid::from(vec![vec![d; 3], vec![live; 3], vec![d; 3]]);
+        let mut to = Grid::from(vec![vec![d; 3]; 3]);
+        from.advance_with(&mut to, conway(), life);
+        to.0
+    }
+
+    #[test]
+    fn wide_cells() {
+        let grid = blinker(300u16, 300);
+        assert_eq!(grid[1], [299, 300, 299]);
+        assert_eq!(grid[0], [0, 1, 0]);
+        // clamped to life first
+        assert_eq!(blinker(300u16, 1000)[1], [299, 300, 299]);
+    }
+
+    #[test]
+    fn float_cells() {
+        let grid = blinker(2.5f32, 2.5);
+        assert_eq!(grid[1], [1.5, 2.5, 1.5]);
+        assert_eq!(grid[0], [0.0, 1.0, 0.0]);
+        assert_eq!(blinker(0.5f32, 0.5)[1], [0.0, 0.5, 0.0]);
+    }
+}
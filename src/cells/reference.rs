@@ -0,0 +1,65 @@
+//! `advance` written as plainly as it can be, for `selftest` to hold the real ones to.
+//!
+//! Nothing here is shared with the engines in `cells` but `CellOpts` and `Grid`, and it
+//! stays slow on purpose. Don't optimize it, don't make it call them.
+
+use super::{CellOpts, Grid};
+
+/// The next generation of `from` under `opts`. With `wrap` every offset is taken modulo the
+/// size, so a neighbor reached twice on a tiny grid counts twice
+pub fn advance(from: &Grid, opts: &CellOpts, wrap: bool) -> Grid {
+    let h = from.len() as isize;
+    let r = opts.radius as isize;
+    let mut to = from.clone();
+    for y in 0..h {
+        let w = from[y as usize].len() as isize;
+        for x in 0..w {
+            let mut neighbors = 0;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let polar = dx == 0 || dy == 0;
+                    if (polar && !opts.polars) || (!polar && !opts.corners) {
+                        continue;
+                    }
+                    let (mut nx, mut ny) = (x + dx, y + dy);
+                    if wrap {
+                        nx = nx.rem_euclid(w);
+                        ny = ny.rem_euclid(h);
+                    } else if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                        continue;
+                    }
+                    let cell = from[ny as usize][nx as usize];
+                    let counts = if opts.mature_neighbors_only {
+                        cell >= opts.life
+                    } else {
+                        cell > 0
+                    };
+                    if counts {
+                        neighbors += 1
+                    }
+                }
+            }
+
+            let current = from[y as usize][x as usize].min(opts.life);
+            to[y as usize][x as usize] = if opts.grow[neighbors] {
+                if current < opts.life {
+                    current + 1
+                } else {
+                    current
+                }
+            } else if !opts.survive[neighbors] {
+                if current > 0 {
+                    current - 1
+                } else {
+                    current
+                }
+            } else {
+                current
+            };
+        }
+    }
+    to
+}
@@ -0,0 +1,221 @@
+//! Random walks over the public `Game` API, checking its invariants after every step.
+//!
+//! `cargo test soak -- --ignored` runs the long version. Failures name their seed,
+//! `SOAK_SEED=<seed>` reruns just that one.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::panic::{self, AssertUnwindSafe};
+
+use super::{CellOpts, Edges, Game, Grid, Pressure, PressurePolicy, Rect, MAX_RADIUS};
+use crate::patterns;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Resize(usize, usize),
+    SetCell(usize, usize, u8),
+    Advance(usize),
+    Clear,
+    Randomize(f32),
+    Expand(Edges, usize, (usize, usize)),
+    Stamp(usize, usize, usize),
+    SetOpts(CellOpts),
+    Freeze(Rect),
+    Pressure(Option<Pressure>),
+    Thaw,
+    Wrap(bool),
+    /// goes through `Game::edit`, then advances
+    View(ViewEdit),
+    SwapBuffers,
+}
+
+#[derive(Clone, Debug)]
+enum ViewEdit {
+    Cell(usize, usize, u8),
+    Fill(u8),
+    Stamp(usize, usize, usize),
+}
+
+fn coord(rng: &mut StdRng, size: usize) -> usize {
+    match rng.gen_range(0..10) {
+        0 => usize::MAX,
+        1 => size + rng.gen_range(0..8),
+        _ => rng.gen_range(0..size.max(1)),
+    }
+}
+
+fn dimension(rng: &mut StdRng, max: usize) -> usize {
+    match rng.gen_range(0..6) {
+        0 => 0,
+        1 => 1,
+        2 => max,
+        _ => rng.gen_range(0..=max),
+    }
+}
+
+fn random_opts(rng: &mut StdRng) -> CellOpts {
+    CellOpts {
+        corners: rng.gen(),
+        polars: rng.gen(),
+        radius: rng.gen_range(1..=MAX_RADIUS),
+        life: rng.gen_range(0..12),
+        grow: rng.gen(),
+        survive: rng.gen(),
+        mature_neighbors_only: rng.gen(),
+    }
+}
+
+fn random_op(rng: &mut StdRng, game: &Game, max: usize) -> Op {
+    let (w, h) = game.size();
+    match rng.gen_range(0..25) {
+        0..=1 => Op::Resize(dimension(rng, max), dimension(rng, max)),
+        2..=6 => Op::SetCell(coord(rng, w), coord(rng, h), rng.gen()),
+        7..=10 => Op::Advance(rng.gen_range(1..4)),
+        11 => Op::Clear,
+        12 => Op::Randomize(rng.gen_range(-0.5..1.5)),
+        13 => Op::Expand(
+            Edges {
+                top: rng.gen(),
+                right: rng.gen(),
+                bottom: rng.gen(),
+                left: rng.gen(),
+            },
+            rng.gen_range(0..8),
+            (dimension(rng, max * 2), dimension(rng, max * 2)),
+        ),
+        14..=15 => Op::Stamp(rng.gen_range(0..64), coord(rng, w), coord(rng, h)),
+        16 => Op::SetOpts(random_opts(rng)),
+        17 => Op::Freeze(Rect {
+            x: coord(rng, w),
+            y: coord(rng, h),
+            w: coord(rng, w),
+            h: coord(rng, h),
+        }),
+        18 => Op::Thaw,
+        19 => Op::Wrap(rng.gen()),
+        20 => Op::Pressure(rng.gen_bool(0.7).then(|| Pressure {
+            max_density: rng.gen_range(-0.2..1.2),
+            policy: [
+                PressurePolicy::SuppressBirths,
+                PressurePolicy::CullOldest,
+                PressurePolicy::CullRandom,
+            ][rng.gen_range(0..3)],
+        })),
+        21 => Op::SwapBuffers,
+        _ => Op::View(match rng.gen_range(0..4) {
+            0 | 1 => ViewEdit::Cell(coord(rng, w), coord(rng, h), rng.gen()),
+            2 => ViewEdit::Fill(rng.gen()),
+            _ => ViewEdit::Stamp(rng.gen_range(0..64), coord(rng, w), coord(rng, h)),
+        }),
+    }
+}
+
+fn apply(game: &mut Game, op: &Op, library: &[Grid]) {
+    match op.clone() {
+        Op::Resize(w, h) => game.resize(w, h),
+        Op::SetCell(x, y, val) => game.set_cell(x, y, val),
+        Op::Advance(n) => {
+            for _ in 0..n {
+                let before = game.population();
+                let info = game.advance();
+                assert_eq!(before + info.births - info.deaths, info.population);
+                assert!(info.births + info.deaths <= info.changed);
+            }
+        }
+        Op::Clear => game.clear(),
+        Op::Randomize(density) => game.randomize(density, &mut StdRng::seed_from_u64(0)),
+        Op::Expand(edges, margin, max) => {
+            game.expand(edges, margin, max);
+        }
+        Op::Stamp(n, x, y) => game.stamp(&library[n % library.len()], x, y),
+        Op::SetOpts(opts) => game.set_opts(opts),
+        Op::Freeze(region) => game.add_static_region(region),
+        Op::Thaw => game.clear_static_regions(),
+        Op::Wrap(wrap) => game.set_wrap(wrap),
+        Op::Pressure(pressure) => game.set_pressure(pressure, 0),
+        Op::View(edit) => {
+            game.edit(|view| match edit {
+                ViewEdit::Cell(x, y, val) => view.set(x, y, val),
+                ViewEdit::Fill(val) => view.fill(val),
+                ViewEdit::Stamp(n, x, y) => view.stamp(&library[n % library.len()], x, y),
+            });
+            game.advance();
+        }
+        Op::SwapBuffers => game.swap_buffers(),
+    }
+}
+
+fn check(game: &Game) -> Result<(), String> {
+    let (w, h) = game.size();
+    for (name, grid) in [("g1", &game.g1), ("g2", &game.g2)] {
+        if grid.len() != h || grid.iter().any(|r| r.len() != w) {
+            return Err(format!("{} isn't {}×{}", name, w, h));
+        }
+    }
+    if !game.regions.is_empty() && game.frozen.len() != w * h {
+        return Err(format!(
+            "{} frozen flags for {}×{}",
+            game.frozen.len(),
+            w,
+            h
+        ));
+    }
+    let life = game.opts.life;
+    if let Some(cell) = game.grid().iter().flatten().find(|c| **c > life) {
+        return Err(format!("cell of {} past life {}", cell, life));
+    }
+    let count = game.grid().iter().flatten().filter(|c| **c != 0).count();
+    if game.population() != count || game.to_coords().len() != count {
+        return Err(format!(
+            "population {} but {} live",
+            game.population(),
+            count
+        ));
+    }
+    Ok(())
+}
+
+/// Runs `steps` random operations from `seed`, panicking with the seed and operation on failure
+fn walk(seed: u64, steps: usize, max: usize) {
+    let library: Vec<Grid> = patterns::builtins()
+        .iter()
+        .map(|p| p.load().unwrap())
+        .collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new(random_opts(&mut rng));
+
+    for step in 0..steps {
+        let op = random_op(&mut rng, &game, max);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            apply(&mut game, &op, &library);
+            check(&game)
+        }));
+        match result {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => panic!("seed {} step {} {:?}: {}\n{:?}", seed, step, op, e, game),
+            Err(_) => panic!("seed {} step {} {:?} panicked", seed, step, op),
+        }
+    }
+}
+
+fn seeds(default: std::ops::Range<u64>) -> Vec<u64> {
+    match std::env::var("SOAK_SEED") {
+        Ok(seed) => vec![seed.parse().expect("SOAK_SEED should be a number")],
+        Err(_) => default.collect(),
+    }
+}
+
+#[test]
+fn soak_short() {
+    for seed in seeds(0..16) {
+        walk(seed, 200, 24)
+    }
+}
+
+#[test]
+#[ignore]
+fn soak() {
+    for seed in seeds(0..500) {
+        walk(seed, 5000, 96)
+    }
+}
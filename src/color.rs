@@ -0,0 +1,356 @@
+//! Terminal color support.
+//!
+//! What the terminal can show is decided once at startup, then every color is
+//! given as RGB and brought down to that depth.
+
+use crossterm::style::Color;
+
+// ColorChoice {{{
+/// `--color`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// color even if NO_COLOR is set or TERM is dumb
+    Always,
+    /// detect from TERM, COLORTERM, and NO_COLOR
+    Auto,
+    Never,
+    #[clap(name = "16")]
+    Ansi16,
+    #[clap(name = "256")]
+    Ansi256,
+    Truecolor,
+}
+// ColorChoice }}}
+
+// ColorDepth {{{
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorDepth {
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Works out the depth from `choice` and environment variables read through `var`
+    pub fn detect(choice: ColorChoice, var: impl Fn(&str) -> Option<String>) -> Self {
+        let supported = || {
+            let term = var("TERM").unwrap_or_default();
+            match var("COLORTERM").as_deref() {
+                Some("truecolor" | "24bit") => Self::TrueColor,
+                _ if term.contains("truecolor") || term.contains("direct") => Self::TrueColor,
+                _ if term.contains("256") => Self::Ansi256,
+                _ => Self::Ansi16,
+            }
+        };
+        match choice {
+            ColorChoice::Never => Self::Monochrome,
+            ColorChoice::Ansi16 => Self::Ansi16,
+            ColorChoice::Ansi256 => Self::Ansi256,
+            ColorChoice::Truecolor => Self::TrueColor,
+            ColorChoice::Always => supported(),
+            ColorChoice::Auto => {
+                // https://no-color.org, any non-empty value
+                if var("NO_COLOR").is_some_and(|v| !v.is_empty())
+                    || var("TERM").is_none_or(|t| t == "dumb")
+                {
+                    Self::Monochrome
+                } else {
+                    supported()
+                }
+            }
+        }
+    }
+
+    /// `rgb` as close as this depth gets, `None` for monochrome
+    pub fn color(&self, rgb: (u8, u8, u8)) -> Option<Color> {
+        match self {
+            Self::Monochrome => None,
+            Self::Ansi16 => Some(ANSI_16[quantize_16(rgb)].0),
+            Self::Ansi256 => Some(Color::AnsiValue(nearest_ansi256(rgb.0, rgb.1, rgb.2))),
+            Self::TrueColor => Some(Color::Rgb {
+                r: rgb.0,
+                g: rgb.1,
+                b: rgb.2,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Monochrome => "monochrome",
+            Self::Ansi16 => "16 colors",
+            Self::Ansi256 => "256 colors",
+            Self::TrueColor => "truecolor",
+        })
+    }
+}
+// ColorDepth }}}
+
+// quantize {{{
+/// The basic colors with xterm's default RGB for each
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of each channel in the 6×6×6 cube at 16..=231
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Index into `ANSI_16` of the nearest basic color
+fn quantize_16(rgb: (u8, u8, u8)) -> usize {
+    (0..ANSI_16.len())
+        .min_by_key(|n| distance(rgb, ANSI_16[*n].1))
+        .unwrap_or(0)
+}
+
+/// Nearest of the 256 color palette's cube and gray ramp, skipping the
+/// first 16 since terminals theme those
+pub fn nearest_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let rgb = (r, g, b);
+    let level = |c: u8| {
+        (0..CUBE.len())
+            .min_by_key(|n| (CUBE[*n] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE[r], CUBE[g], CUBE[b]);
+
+    // grays run 8, 18, ..., 238 at 232..=255
+    let mean = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let step = ((mean.saturating_sub(3)) / 10).min(23) as u8;
+    let gray = 8 + step * 10;
+
+    if distance(rgb, (gray, gray, gray)) < distance(rgb, cube) {
+        232 + step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+// quantize }}}
+
+// heat {{{
+/// dark red through orange and yellow to near white
+const HEAT: [(u8, u8, u8); 4] = [(110, 0, 0), (230, 60, 0), (255, 190, 0), (255, 255, 210)];
+
+/// Heatmap color for a cell with `fraction` of its life left
+pub fn heat(fraction: f32) -> (u8, u8, u8) {
+    let f = fraction.clamp(0.0, 1.0) * (HEAT.len() - 1) as f32;
+    let n = (f as usize).min(HEAT.len() - 2);
+    let t = f - n as f32;
+    let (a, b) = (HEAT[n], HEAT[n + 1]);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+/// `a` at 0 to `b` at 1
+pub fn mix(a: (u8, u8, u8), b: (u8, u8, u8), t: f32) -> (u8, u8, u8) {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+/// How bright text has to be to read on a dark terminal, the dark end of `heat` isn't
+pub const DIGIT_LUMINANCE: f32 = 0.4;
+
+/// Rough relative luminance from 0 to 1, without linearizing
+pub fn luminance((r, g, b): (u8, u8, u8)) -> f32 {
+    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+}
+
+/// `rgb` mixed with white until it's at least `floor` bright. Brighter colors are left as they are
+pub fn lift(rgb: (u8, u8, u8), floor: f32) -> (u8, u8, u8) {
+    let lum = luminance(rgb);
+    if lum >= floor {
+        return rgb;
+    }
+    let t = (floor - lum) / (1.0 - lum);
+    let mix = |c: u8| (c as f32 + (255.0 - c as f32) * t).round() as u8;
+    (mix(rgb.0), mix(rgb.1), mix(rgb.2))
+}
+// heat }}}
+
+// ColorPalette {{{
+pub type Rgba = (u8, u8, u8, u8);
+
+/// Pixel colors by cell value, for images rather than the terminal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorPalette {
+    pub dead: Rgba,
+    /// entry n is for cells with n + 1 life, the last one also covers anything higher
+    pub alive: Vec<Rgba>,
+}
+
+impl ColorPalette {
+    /// The TUI's heatmap over `life` steps, on opaque black
+    pub fn heatmap(life: u8) -> Self {
+        let life = life.max(1);
+        Self {
+            dead: (0, 0, 0, 255),
+            alive: (1..=life)
+                .map(|n| {
+                    let (r, g, b) = heat(n as f32 / life as f32);
+                    (r, g, b, 255)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, cell: u8) -> Rgba {
+        match cell {
+            0 => self.dead,
+            n => self
+                .alive
+                .get(n as usize - 1)
+                .or(self.alive.last())
+                .copied()
+                .unwrap_or(self.dead),
+        }
+    }
+}
+// ColorPalette }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |k| {
+            vars.iter()
+                .find(|(n, _)| *n == k)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn detect() {
+        let auto = |vars| ColorDepth::detect(ColorChoice::Auto, env(vars));
+        assert_eq!(auto(&[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(auto(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(
+            auto(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(auto(&[("TERM", "dumb")]), ColorDepth::Monochrome);
+        assert_eq!(auto(&[]), ColorDepth::Monochrome);
+        assert_eq!(
+            auto(&[("TERM", "xterm"), ("NO_COLOR", "1")]),
+            ColorDepth::Monochrome
+        );
+        // empty doesn't count
+        assert_eq!(
+            auto(&[("TERM", "xterm"), ("NO_COLOR", "")]),
+            ColorDepth::Ansi16
+        );
+
+        let vars = [("TERM", "dumb"), ("NO_COLOR", "1")];
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Always, env(&vars)),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Ansi256, env(&vars)),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Never, env(&[("COLORTERM", "truecolor")])),
+            ColorDepth::Monochrome
+        );
+    }
+
+    #[test]
+    fn cube() {
+        // exact cube colors map to themselves
+        assert_eq!(nearest_ansi256(255, 0, 0), 196);
+        assert_eq!(nearest_ansi256(0, 0, 255), 21);
+        assert_eq!(nearest_ansi256(95, 135, 175), 16 + 36 + 12 + 3);
+        // and near ones round to them
+        assert_eq!(nearest_ansi256(250, 10, 5), 196);
+        assert_eq!(nearest_ansi256(100, 130, 180), 16 + 36 + 12 + 3);
+        // black and white stay in the cube, they're exact there
+        assert_eq!(nearest_ansi256(0, 0, 0), 16);
+        assert_eq!(nearest_ansi256(255, 255, 255), 231);
+    }
+
+    #[test]
+    fn grays() {
+        assert_eq!(nearest_ansi256(8, 8, 8), 232);
+        assert_eq!(nearest_ansi256(128, 128, 128), 244);
+        assert_eq!(nearest_ansi256(238, 238, 238), 255);
+        // slightly tinted is still gray
+        assert_eq!(nearest_ansi256(120, 124, 128), 244);
+    }
+
+    #[test]
+    fn basic() {
+        let color = |rgb| ColorDepth::Ansi16.color(rgb);
+        assert_eq!(color((250, 20, 20)), Some(Color::Red));
+        assert_eq!(color((0, 0, 200)), Some(Color::DarkBlue));
+        assert_eq!(color((130, 120, 125)), Some(Color::DarkGrey));
+        assert_eq!(ColorDepth::Monochrome.color((1, 2, 3)), None);
+        assert_eq!(
+            ColorDepth::TrueColor.color((1, 2, 3)),
+            Some(Color::Rgb { r: 1, g: 2, b: 3 })
+        );
+    }
+
+    #[test]
+    fn heatmap() {
+        assert_eq!(heat(0.0), HEAT[0]);
+        assert_eq!(heat(1.0), HEAT[3]);
+        assert_eq!(heat(2.0), HEAT[3]);
+        assert_eq!(heat(0.5), (243, 125, 0));
+        // every step of a long life is its own color on a 256 color terminal
+        let shades: std::collections::HashSet<u8> = (1..=5)
+            .map(|n| heat(n as f32 / 5.0))
+            .map(|(r, g, b)| nearest_ansi256(r, g, b))
+            .collect();
+        assert_eq!(shades.len(), 5);
+    }
+
+    #[test]
+    fn lifting() {
+        assert!(luminance(HEAT[0]) < DIGIT_LUMINANCE);
+        let lifted = lift(HEAT[0], DIGIT_LUMINANCE);
+        assert!(luminance(lifted) >= DIGIT_LUMINANCE - 0.01, "{:?}", lifted);
+        // still reddest
+        assert!(lifted.0 > lifted.1 && lifted.1 == lifted.2);
+        assert_eq!(lift(HEAT[3], DIGIT_LUMINANCE), HEAT[3]);
+        assert_eq!(lift((0, 0, 0), 1.0), (255, 255, 255));
+    }
+
+    #[test]
+    fn palette() {
+        let palette = ColorPalette::heatmap(3);
+        assert_eq!(palette.alive.len(), 3);
+        assert_eq!(palette.get(0), (0, 0, 0, 255));
+        assert_eq!(palette.get(3), (255, 255, 210, 255));
+        assert_eq!(palette.get(200), palette.get(3));
+        let empty = ColorPalette {
+            dead: (1, 2, 3, 4),
+            alive: Vec::new(),
+        };
+        assert_eq!(empty.get(5), (1, 2, 3, 4));
+    }
+}
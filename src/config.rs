@@ -0,0 +1,259 @@
+//! `--config`, a JSON file of settings that's watched while the TUI runs.
+//!
+//! Its keys override the matching flags. Once running, only what can change without
+//! invalidating the cells is applied when it's edited, the rest waits for a restart
+//! unless `--hot-reload-rules`.
+//!
+//! ```json
+//! { "color": "256", "numeric": false, "speed": 20, "bell": "on-period", "rule": "B36/S23" }
+//! ```
+
+use serde::{Deserialize, Deserializer};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::bell::BellOn;
+use super::cells::CellOpts;
+use super::color::ColorChoice;
+
+/// `config.json` in `$XDG_CONFIG_HOME/terminal_illness` or `~/.config/terminal_illness`
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .map(|p| p.join("terminal_illness/config.json"))
+}
+
+// FileConfig {{{
+/// What a config file says, `None` for keys it leaves out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// as `--color`
+    #[serde(deserialize_with = "value_enum")]
+    pub color: Option<ColorChoice>,
+    pub numeric: Option<bool>,
+    /// generations per second, as `--speed`
+    pub speed: Option<f32>,
+    #[serde(deserialize_with = "value_enum")]
+    pub bell: Option<BellOn>,
+    /// a rulestring, as `CellOpts::from_str` reads it
+    #[serde(deserialize_with = "from_str")]
+    pub rule: Option<CellOpts>,
+}
+
+/// Names as the flags spell them
+fn value_enum<'de, D: Deserializer<'de>, T: clap::ValueEnum>(d: D) -> Result<Option<T>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|s| T::from_str(&s, true).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn from_str<'de, D: Deserializer<'de>, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    Option::<String>::deserialize(d)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl FileConfig {
+    /// Only the keys that differ from `before`, so a reload doesn't undo changes made
+    /// while running to keys nobody edited
+    pub fn changed_from(&self, before: &FileConfig) -> FileConfig {
+        fn new<T: PartialEq + Copy>(now: Option<T>, before: Option<T>) -> Option<T> {
+            now.filter(|_| now != before)
+        }
+        FileConfig {
+            color: new(self.color, before.color),
+            numeric: new(self.numeric, before.numeric),
+            speed: new(self.speed, before.speed),
+            bell: new(self.bell, before.bell),
+            rule: new(self.rule, before.rule),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<FileConfig, String> {
+    let result: FileConfig = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if result.speed.is_some_and(|s| !(s > 0.0 && s.is_finite())) {
+        return Err("speed must be above 0".to_string());
+    }
+    Ok(result)
+}
+
+/// Reads and parses `path`. A missing file says nothing
+pub fn load(path: &Path) -> Result<FileConfig, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(FileConfig::default()),
+        Err(e) => Err(format!("could not read {}: {}", path.display(), e)),
+    }
+}
+// FileConfig }}}
+
+// Live {{{
+/// The settings a config file can change, as they are in effect. The rule is the only one that
+/// changes what the cells do, the rest are how they're shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Live {
+    pub color: ColorChoice,
+    pub numeric: bool,
+    pub speed: f32,
+    pub bell: BellOn,
+    pub rule: CellOpts,
+}
+
+/// `file` laid over `old`. Keys that would change the running simulation only apply with
+/// `rules`, the ones left out are returned. Keys missing from the file keep their old value
+pub fn merge(old: &Live, file: &FileConfig, rules: bool) -> (Live, Vec<&'static str>) {
+    let mut skipped = Vec::new();
+    let rule = match file.rule {
+        Some(rule) if rule != old.rule && !rules => {
+            skipped.push("rule");
+            old.rule
+        }
+        rule => rule.unwrap_or(old.rule),
+    };
+    let applied = Live {
+        color: file.color.unwrap_or(old.color),
+        numeric: file.numeric.unwrap_or(old.numeric),
+        speed: file.speed.unwrap_or(old.speed),
+        bell: file.bell.unwrap_or(old.bell),
+        rule,
+    };
+    (applied, skipped)
+}
+// Live }}}
+
+// Watcher {{{
+/// Polls a config file's modification time and length
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watcher {
+    pub path: PathBuf,
+    /// `None` while the file doesn't exist
+    seen: Option<(SystemTime, u64)>,
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf) -> Self {
+        let mut result = Self { path, seen: None };
+        result.seen = result.stamp();
+        result
+    }
+
+    fn stamp(&self) -> Option<(SystemTime, u64)> {
+        let meta = fs::metadata(&self.path).ok()?;
+        Some((meta.modified().ok()?, meta.len()))
+    }
+
+    /// Whether the file changed since the last call, a new or deleted file included
+    pub fn changed(&mut self) -> bool {
+        let stamp = self.stamp();
+        let changed = stamp != self.seen;
+        self.seen = stamp;
+        changed
+    }
+}
+// Watcher }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live() -> Live {
+        Live {
+            color: ColorChoice::Auto,
+            numeric: false,
+            speed: 10.0,
+            bell: BellOn::Off,
+            rule: "B3/S23".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        let file =
+            parse(r#"{"color": "256", "speed": 2.5, "bell": "every-100", "rule": "B36/S23"}"#)
+                .unwrap();
+        assert_eq!(file.color, Some(ColorChoice::Ansi256));
+        assert_eq!(file.speed, Some(2.5));
+        assert_eq!(file.bell, Some(BellOn::Every100));
+        assert_eq!(
+            file.rule.map(|r| r.rulestring()),
+            Some("B36/S23".to_string())
+        );
+        assert_eq!(file.numeric, None);
+        assert_eq!(parse("{}"), Ok(FileConfig::default()));
+
+        assert!(parse(r#"{"colour": "256"}"#)
+            .unwrap_err()
+            .contains("colour"));
+        assert!(parse(r#"{"bell": "sometimes"}"#).is_err());
+        assert!(parse(r#"{"rule": "S23"}"#).is_err());
+        assert!(parse(r#"{"speed": 0}"#).is_err());
+        assert!(parse("{").is_err());
+        assert_eq!(
+            load(Path::new("/nonexistent/config.json")),
+            Ok(FileConfig::default())
+        );
+    }
+
+    #[test]
+    fn merging() {
+        let old = live();
+        let file = parse(r#"{"numeric": true, "speed": 30, "rule": "B36/S23"}"#).unwrap();
+
+        let (applied, skipped) = merge(&old, &file, false);
+        assert_eq!(
+            applied,
+            Live {
+                numeric: true,
+                speed: 30.0,
+                ..old
+            }
+        );
+        assert_eq!(skipped, ["rule"]);
+
+        let (applied, skipped) = merge(&old, &file, true);
+        assert_eq!(applied.rule, file.rule.unwrap());
+        assert!(skipped.is_empty());
+
+        // the same rule isn't a change, and a missing key keeps what's running
+        let same = parse(r#"{"rule": "B3/S23"}"#).unwrap();
+        assert_eq!(
+            merge(&applied, &FileConfig::default(), false),
+            (applied, vec![])
+        );
+        assert_eq!(merge(&old, &same, false), (old, vec![]));
+
+        let edited = parse(r#"{"numeric": true, "speed": 5, "rule": "B36/S23"}"#).unwrap();
+        assert_eq!(
+            edited.changed_from(&file),
+            FileConfig {
+                speed: Some(5.0),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn watching() {
+        let path = std::env::temp_dir().join(format!("ti_config_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut watcher = Watcher::new(path.clone());
+        assert!(!watcher.changed());
+        fs::write(&path, "{}").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        fs::write(&path, r#"{"numeric": true}"#).unwrap();
+        assert!(watcher.changed());
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+    }
+}
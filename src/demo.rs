@@ -0,0 +1,203 @@
+//! Scripted tour of the program, run with `--demo`.
+//!
+//! Scenes are plain data, the interpreter only knows how to load one into a
+//! `Game` and count down its generations.
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use std::time::Duration;
+
+use super::cells::{CellOpts, Game, COUNTS};
+use super::formats;
+use super::patterns::{GLIDER, GOSPER_GUN};
+
+// DemoStep {{{
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DemoPattern {
+    /// plaintext `.cells` art, centered
+    Cells(&'static str),
+    /// seeded noise over the whole grid
+    Soup { density: f32, seed: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DemoStep {
+    pub pattern: DemoPattern,
+    pub rule: CellOpts,
+    pub caption: &'static str,
+    /// how long the scene runs before waiting for a key
+    pub generations: u64,
+    pub tick: Duration,
+}
+// DemoStep }}}
+
+// steps {{{
+/// Birth/survival rule from neighbor counts
+fn rule(birth: &[usize], survive: &[usize], life: u8) -> CellOpts {
+    let mut result = CellOpts {
+        corners: true,
+        polars: true,
+        radius: 1,
+        life,
+        grow: [false; COUNTS],
+        survive: [false; COUNTS],
+        mature_neighbors_only: false,
+    };
+    for n in birth {
+        result.grow[*n] = true
+    }
+    for n in survive {
+        result.survive[*n] = true
+    }
+    result
+}
+
+pub fn steps() -> Vec<DemoStep> {
+    vec![
+        DemoStep {
+            pattern: DemoPattern::Cells(GLIDER),
+            rule: rule(&[3], &[2, 3], 1),
+            caption: "This is a glider. Under Conway's Life, B3/S23, a dead cell with 3 live \
+                neighbors is born and a live one with 2 or 3 survives. \
+                These five cells repeat every 4 steps, one cell further along.",
+            generations: 40,
+            tick: Duration::from_millis(150),
+        },
+        DemoStep {
+            pattern: DemoPattern::Cells(GOSPER_GUN),
+            rule: rule(&[3], &[2, 3], 1),
+            caption: "The Gosper glider gun, the first pattern found that grows forever. \
+                It fires a new glider every 30 generations.",
+            generations: 240,
+            tick: Duration::from_millis(50),
+        },
+        DemoStep {
+            pattern: DemoPattern::Soup {
+                density: 0.5,
+                seed: 3678,
+            },
+            rule: rule(&[3, 6, 7, 8], &[3, 4, 6, 7, 8], 1),
+            caption: "Random soup under Day & Night, B3678/S34678. Live and dead cells follow \
+                mirrored rules, so it settles into blobs of both.",
+            generations: 200,
+            tick: Duration::from_millis(50),
+        },
+        DemoStep {
+            pattern: DemoPattern::Soup {
+                density: 0.3,
+                seed: 23,
+            },
+            rule: rule(&[3], &[2, 3], 9),
+            caption: "Cells may live longer than one step. With a life of 9, births and \
+                deaths only add or remove 1 life at a time, and the soup smolders instead. \
+                Try --numeric to see every cell's age.",
+            generations: 150,
+            tick: Duration::from_millis(60),
+        },
+    ]
+}
+// steps }}}
+
+// Demo {{{
+#[derive(Clone, Debug)]
+pub struct Demo {
+    steps: Vec<DemoStep>,
+    index: usize,
+    remaining: u64,
+}
+
+impl Demo {
+    pub fn new(steps: Vec<DemoStep>) -> Self {
+        let remaining = steps.first().map(|s| s.generations).unwrap_or(0);
+        Self {
+            steps,
+            index: 0,
+            remaining,
+        }
+    }
+
+    pub fn current(&self) -> Option<&DemoStep> {
+        self.steps.get(self.index)
+    }
+
+    /// True once the current scene has run all its generations
+    pub fn waiting(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Replaces the game's rule and cells with the current scene
+    pub fn load(&self, game: &mut Game) {
+        let Some(step) = self.current() else { return };
+        game.set_opts(step.rule);
+        game.clear();
+        match step.pattern {
+            DemoPattern::Cells(text) => {
+                let pattern =
+                    formats::parse_cells(text, step.rule.life).expect("demo patterns are valid");
+                let (w, h) = game.size();
+                let (pw, ph) = (pattern.first().map(|r| r.len()).unwrap_or(0), pattern.len());
+                game.stamp(&pattern, w.saturating_sub(pw) / 2, h.saturating_sub(ph) / 2)
+            }
+            DemoPattern::Soup { density, seed } => {
+                game.randomize(density, &mut StdRng::seed_from_u64(seed))
+            }
+        }
+    }
+
+    /// Counts down one generation, returning true when the scene just finished
+    pub fn tick(&mut self) -> bool {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.remaining == 0
+        } else {
+            false
+        }
+    }
+
+    /// Moves to the next scene. False when there are none left
+    pub fn next(&mut self) -> bool {
+        self.index += 1;
+        self.remaining = self.current().map(|s| s.generations).unwrap_or(0);
+        self.current().is_some()
+    }
+}
+// Demo }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_parse() {
+        for step in steps() {
+            if let DemoPattern::Cells(text) = step.pattern {
+                formats::parse_cells(text, 1).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn interpreter() {
+        let mut steps = steps();
+        steps.truncate(2);
+        steps[0].generations = 2;
+        let mut game = Game::new(steps[0].rule);
+        game.resize(40, 20);
+        let mut demo = Demo::new(steps);
+
+        demo.load(&mut game);
+        assert_eq!(game.population(), 5);
+        assert!(!demo.tick());
+        assert!(demo.tick());
+        assert!(demo.waiting());
+        assert!(!demo.tick());
+
+        assert!(demo.next());
+        assert!(!demo.waiting());
+        demo.load(&mut game);
+        assert_eq!(game.population(), 36);
+
+        assert!(!demo.next());
+        assert!(demo.current().is_none());
+    }
+}
@@ -0,0 +1,773 @@
+//! Pattern file formats, and a raw dump of whole grids for other tools.
+//!
+//! The raw dump is a 14 byte header then the cells, so it can be memory mapped as is:
+//!
+//! | offset | size | value                                |
+//! |--------|------|--------------------------------------|
+//! | 0      | 4    | magic `TIRW`                         |
+//! | 4      | 1    | version, 1                           |
+//! | 5      | 4    | width, u32 little endian             |
+//! | 9      | 4    | height, u32 little endian            |
+//! | 13     | 1    | bytes per cell, 1                    |
+//! | 14     | w×h  | cell values, rows top to bottom      |
+//!
+//! Any change to that layout gets a new version. Reading one with numpy:
+//!
+//! ```python
+//! import numpy as np
+//! head = np.fromfile("out.bin", dtype=np.uint8, count=14)
+//! w, h = (int(head[5:9].view("<u4")[0]), int(head[9:13].view("<u4")[0]))
+//! cells = np.fromfile("out.bin", dtype=np.uint8, offset=14).reshape(h, w)
+//! ```
+
+use super::cells::Grid;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Most cells a file may hold, so a bad header can't ask for more than anything could show
+pub const MAX_CELLS: u64 = 1 << 28;
+/// Widest or tallest pattern `parse_rle` and `parse_life` read, a short run or coordinate
+/// can ask for far more than the file holds
+pub const MAX_SIDE: u64 = 1 << 16;
+
+// ParseError {{{
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1 indexed
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Refuses a `w`×`h` pattern past `MAX_SIDE` or `MAX_CELLS`, blaming `line`
+fn check_size(w: u64, h: u64, line: usize) -> Result<(), ParseError> {
+    if w > MAX_SIDE || h > MAX_SIDE || w * h > MAX_CELLS {
+        return Err(ParseError {
+            line,
+            message: format!(
+                "{}×{} is too big, patterns go up to {} a side and {} cells",
+                w, h, MAX_SIDE, MAX_CELLS
+            ),
+        });
+    }
+    Ok(())
+}
+// ParseError }}}
+
+// plaintext {{{
+/// Parses the plaintext `.cells` format, `O` alive `.` dead, `!` comments.
+/// Live cells are set to `value`. Rows are padded to the widest one
+pub fn parse_cells(text: &str, value: u8) -> Result<Grid, ParseError> {
+    let mut grid = Grid::new();
+    for (n, line) in text.lines().enumerate() {
+        if line.starts_with('!') {
+            continue;
+        }
+        grid.push(
+            line.trim_end()
+                .chars()
+                .map(|c| match c {
+                    'O' | '*' => Ok(value),
+                    '.' => Ok(0),
+                    c => Err(ParseError {
+                        line: n + 1,
+                        message: format!("unexpected '{}'", c),
+                    }),
+                })
+                .collect::<Result<_, _>>()?,
+        );
+    }
+
+    let width = grid.iter().map(|r| r.len()).max().unwrap_or(0);
+    for row in grid.iter_mut() {
+        row.resize(width, 0)
+    }
+    Ok(grid)
+}
+// plaintext }}}
+
+// rle {{{
+/// Parses the body of a run length encoded pattern, `b` dead, any other letter alive.
+/// Live cells are set to `value`. Golly's multi-state cells are read too, `.` dead and
+/// `A` to `X` states 1 to 24, with a `p` to `y` prefix adding 24 for each letter past `o`.
+/// Those cells get their state, capped at `value`
+pub fn parse_rle(text: &str, value: u8) -> Result<Grid, ParseError> {
+    let mut grid = Grid::from(vec![Vec::new()]);
+    let mut run = String::new();
+    let mut header = false;
+    // widest row so far
+    let mut width = 0;
+
+    'lines: for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if !header && line.starts_with('x') {
+            header = true;
+            continue;
+        }
+        let mut chars = line.chars().peekable();
+        // a `p` to `y` waiting for the `A` to `X` it's the high part of
+        let mut high = None;
+        while let Some(c) = chars.next() {
+            let err = |message: String| ParseError {
+                line: n + 1,
+                message,
+            };
+            let count = if run.is_empty() {
+                1
+            } else {
+                run.parse::<usize>()
+                    .map_err(|e| err(format!("bad run '{}': {}", run, e)))?
+            };
+            // checked before anything is allocated for the run
+            let rows = grid.len();
+            let mut widen = |len: usize| {
+                let len = len.saturating_add(count);
+                check_size(width.max(len) as u64, rows as u64, n + 1)?;
+                width = width.max(len);
+                Ok::<_, ParseError>(len)
+            };
+            match c {
+                '0'..='9' => {
+                    run.push(c);
+                    continue;
+                }
+                'p'..='y' if chars.peek().is_some_and(|c| c.is_ascii_uppercase()) => {
+                    high = Some(c as usize - 'p' as usize + 1);
+                    continue;
+                }
+                'A'..='X' => {
+                    let state = high.take().unwrap_or(0) * 24 + (c as usize - 'A' as usize + 1);
+                    let state = u8::try_from(state)
+                        .map_err(|_| err(format!("state {} is past 255", state)))?;
+                    let row = grid.last_mut().unwrap();
+                    row.resize(widen(row.len())?, state.min(value))
+                }
+                c if c.is_ascii_uppercase() => return Err(err(format!("unexpected '{}'", c))),
+                '!' => break 'lines,
+                '$' => {
+                    check_size(width as u64, rows.saturating_add(count) as u64, n + 1)?;
+                    grid.extend((0..count).map(|_| Vec::new()))
+                }
+                'b' | '.' => {
+                    let row = grid.last_mut().unwrap();
+                    row.resize(widen(row.len())?, 0)
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let row = grid.last_mut().unwrap();
+                    row.resize(widen(row.len())?, value)
+                }
+                c if c.is_whitespace() => (),
+                c => return Err(err(format!("unexpected '{}'", c))),
+            }
+            run.clear()
+        }
+    }
+
+    // trailing `$`s don't make rows
+    while grid.len() > 1 && grid.last().is_some_and(|r| r.is_empty()) {
+        grid.pop();
+    }
+    let width = grid.iter().map(|r| r.len()).max().unwrap_or(0);
+    for row in grid.iter_mut() {
+        row.resize(width, 0)
+    }
+    Ok(grid)
+}
+// rle }}}
+
+// life 1.06 {{{
+/// Parses Life 1.06, a `#Life 1.06` line then one `x y` pair per live cell.
+/// Coordinates may be negative, the result is shifted to start at 0, 0
+pub fn parse_life(text: &str, value: u8) -> Result<Grid, ParseError> {
+    let mut cells = Vec::new();
+    let (mut low, mut high) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+    // far apart coordinates would overflow an i64
+    let span =
+        |low: i64, high: i64| u64::try_from(high as i128 - low as i128 + 1).unwrap_or(u64::MAX);
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if n == 0 && line.starts_with("#Life") && line != "#Life 1.06" {
+            return Err(ParseError {
+                line: 1,
+                message: format!("unsupported version '{}'", line),
+            });
+        }
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let mut nums = line.split_whitespace().map(|v| v.parse::<i64>());
+        match (nums.next(), nums.next(), nums.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => {
+                (low, high) = ((low.0.min(x), low.1.min(y)), (high.0.max(x), high.1.max(y)));
+                check_size(span(low.0, high.0), span(low.1, high.1), n + 1)?;
+                cells.push((x, y))
+            }
+            _ => {
+                return Err(ParseError {
+                    line: n + 1,
+                    message: format!("expected 'x y', got '{}'", line),
+                })
+            }
+        }
+    }
+
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let w = cells.iter().map(|c| c.0 - min_x + 1).max().unwrap_or(0);
+    let h = cells.iter().map(|c| c.1 - min_y + 1).max().unwrap_or(0);
+    let mut grid = Grid::from(vec![vec![0; w as usize]; h as usize]);
+    for (x, y) in cells {
+        grid[(y - min_y) as usize][(x - min_x) as usize] = value
+    }
+    Ok(grid)
+}
+// life 1.06 }}}
+
+// files {{{
+/// What can be learned about a pattern file without parsing its cells
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    pub name: Option<String>,
+    pub size: Option<(usize, usize)>,
+    pub rule: Option<String>,
+}
+
+/// Formats picked by file extension
+pub const EXTENSIONS: &[&str] = &["rle", "cells", "lif", "life"];
+
+/// Reads the name, size, and rule from the leading comments and header lines
+pub fn header(text: &str, ext: &str) -> Result<Header, ParseError> {
+    let mut result = Header::default();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        match ext {
+            "rle" => {
+                if let Some(name) = line.strip_prefix("#N") {
+                    result.name = Some(name.trim().to_string())
+                } else if line.starts_with('x') {
+                    let err = |message: &str| ParseError {
+                        line: n + 1,
+                        message: message.to_string(),
+                    };
+                    let mut size = (None, None);
+                    for field in line.split(',') {
+                        let (key, val) = field.split_once('=').ok_or_else(|| err("bad header"))?;
+                        let val = val.trim();
+                        match key.trim() {
+                            "x" => size.0 = Some(val.parse().map_err(|_| err("bad width"))?),
+                            "y" => size.1 = Some(val.parse().map_err(|_| err("bad height"))?),
+                            "rule" => result.rule = Some(val.to_string()),
+                            _ => (),
+                        }
+                    }
+                    result.size = size.0.zip(size.1);
+                    return Ok(result);
+                } else if !line.starts_with('#') && !line.is_empty() {
+                    return Err(ParseError {
+                        line: n + 1,
+                        message: "missing 'x = ..., y = ...' header".to_string(),
+                    });
+                }
+            }
+            "cells" => {
+                if let Some(name) = line.strip_prefix("!Name:") {
+                    result.name = Some(name.trim().to_string())
+                } else if !line.starts_with('!') {
+                    break;
+                }
+            }
+            _ => {
+                if n == 0 && !line.starts_with("#Life 1.06") {
+                    return Err(ParseError {
+                        line: 1,
+                        message: "missing '#Life 1.06' header".to_string(),
+                    });
+                }
+                break;
+            }
+        }
+    }
+    if ext == "rle" {
+        return Err(ParseError {
+            line: text.lines().count(),
+            message: "missing 'x = ..., y = ...' header".to_string(),
+        });
+    }
+    Ok(result)
+}
+
+/// The `#N` name, `#O` author, and `#C` comments of an `.rle` file, keyed by their letter.
+/// Comment lines are joined with newlines
+pub fn rle_metadata(text: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('x') {
+            break;
+        }
+        let Some(rest) = line.strip_prefix('#') else {
+            continue;
+        };
+        let Some(key) = rest.chars().next() else {
+            continue;
+        };
+        let value = rest[key.len_utf8()..].trim();
+        match key {
+            'N' | 'O' => {
+                result.insert(key.to_string(), value.to_string());
+            }
+            'C' | 'c' => {
+                let comments: &mut String = result.entry("C".to_string()).or_default();
+                if !comments.is_empty() {
+                    comments.push('\n')
+                }
+                *comments += value
+            }
+            _ => (),
+        }
+    }
+    result
+}
+
+/// Writes `grid` as a `.cells` file, any live cell as `O`
+pub fn to_cells(grid: &Grid) -> String {
+    grid.iter()
+        .map(|row| {
+            let line: String = row
+                .iter()
+                .map(|c| if *c != 0 { 'O' } else { '.' })
+                .collect();
+            line.trim_end_matches('.').to_string() + "\n"
+        })
+        .collect()
+}
+
+/// Golly's letters for a multi-state cell, `.` dead, then `A` to `X` with a `p` to `y`
+/// prefix past 24, see `parse_rle`
+fn rle_state(state: u8) -> String {
+    match state {
+        0 => ".".to_string(),
+        s @ 1..=24 => char::from(b'A' + s - 1).to_string(),
+        s => {
+            let (high, low) = ((s - 1) / 24, (s - 1) % 24);
+            format!("{}{}", char::from(b'p' + high - 1), char::from(b'A' + low))
+        }
+    }
+}
+
+/// Writes `grid` as an `.rle` file under `rule`, lines kept under 70 chars.
+/// `metadata` goes in front as `#N`, `#O`, then a `#C` per comment line.
+/// Any cell past 1 switches every cell to Golly's multi-state letters, so no state is lost
+pub fn to_rle(grid: &Grid, rule: &str, metadata: &HashMap<String, String>) -> String {
+    let w = grid.first().map(|r| r.len()).unwrap_or(0);
+    let multi = grid.iter().flatten().any(|c| *c > 1);
+    let tag = |state: u8| match (multi, state) {
+        (true, s) => rle_state(s),
+        (false, 0) => "b".to_string(),
+        (false, _) => "o".to_string(),
+    };
+    // runs stay whole when lines are split
+    let mut body: Vec<String> = Vec::new();
+    let mut push = |count: usize, tag: &str| match count {
+        0 => (),
+        1 => body.push(tag.to_string()),
+        n => body.push(format!("{}{}", n, tag)),
+    };
+    // blank rows pile up into one `$` run
+    let mut rows = 0;
+    for row in grid.iter() {
+        let mut runs: Vec<(usize, u8)> = Vec::new();
+        for state in row
+            .iter()
+            .map(|c| if multi { *c } else { u8::from(*c != 0) })
+        {
+            match runs.last_mut() {
+                Some((n, s)) if *s == state => *n += 1,
+                _ => runs.push((1, state)),
+            }
+        }
+        if runs.last().is_some_and(|(_, s)| *s == 0) {
+            runs.pop();
+        }
+        if runs.is_empty() {
+            rows += 1;
+            continue;
+        }
+        push(rows, "$");
+        rows = 1;
+        for (n, state) in runs {
+            push(n, &tag(state))
+        }
+    }
+    push(1, "!");
+
+    let mut result = String::new();
+    for key in ["N", "O"] {
+        if let Some(value) = metadata.get(key) {
+            result += &format!("#{} {}\n", key, value)
+        }
+    }
+    for comment in metadata.get("C").into_iter().flat_map(|c| c.lines()) {
+        result += &format!("#C {}\n", comment)
+    }
+    result += &format!("x = {}, y = {}, rule = {}\n", w, grid.len(), rule);
+    let mut line = 0;
+    for token in body {
+        if line + token.len() > 70 {
+            result.push('\n');
+            line = 0;
+        }
+        line += token.len();
+        result += &token;
+    }
+    result + "\n"
+}
+
+/// Writes `grid` as Life 1.06, one line per live cell
+pub fn to_life(grid: &Grid) -> String {
+    let mut result = "#Life 1.06\n".to_string();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, _) in row.iter().enumerate().filter(|(_, c)| **c != 0) {
+            result += &format!("{} {}\n", x, y)
+        }
+    }
+    result
+}
+
+/// Writes `grid` as whichever format `ext` says. Only `.rle` keeps `metadata`
+pub fn write(grid: &Grid, ext: &str, rule: &str, metadata: &HashMap<String, String>) -> String {
+    match ext {
+        "rle" => to_rle(grid, rule, metadata),
+        "cells" => to_cells(grid),
+        _ => to_life(grid),
+    }
+}
+
+/// Parses a pattern file as whichever format its extension says
+pub fn parse(text: &str, ext: &str, value: u8) -> Result<Grid, ParseError> {
+    match ext {
+        "rle" => parse_rle(text, value),
+        "cells" => parse_cells(text, value),
+        _ => parse_life(text, value),
+    }
+}
+
+pub fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .filter(|e| EXTENSIONS.contains(&e.as_str()))
+}
+// files }}}
+
+// raw {{{
+pub const RAW_MAGIC: [u8; 4] = *b"TIRW";
+pub const RAW_VERSION: u8 = 1;
+/// magic, version, width, height, cell size
+pub const RAW_HEADER: usize = 14;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawError {
+    /// where in the file it went wrong
+    pub offset: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for RawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for RawError {}
+
+/// Writes the whole grid in the raw layout from the module docs
+pub fn to_raw(grid: &Grid) -> Vec<u8> {
+    let w = grid.first().map(|r| r.len()).unwrap_or(0);
+    let mut result = Vec::with_capacity(RAW_HEADER + w * grid.len());
+    result.extend(RAW_MAGIC);
+    result.push(RAW_VERSION);
+    result.extend((w as u32).to_le_bytes());
+    result.extend((grid.len() as u32).to_le_bytes());
+    result.push(1);
+    grid.iter().for_each(|row| result.extend(row));
+    result
+}
+
+/// Reads a grid written by `to_raw`. Cells keep their values, clamping is up to the caller.
+/// A grid with no width comes back empty, and one past `MAX_CELLS` is refused
+pub fn parse_raw(bytes: &[u8]) -> Result<Grid, RawError> {
+    let err = |offset, message: &str| RawError {
+        offset,
+        message: message.to_string(),
+    };
+    if bytes.len() < RAW_HEADER {
+        return Err(err(bytes.len(), "shorter than the header"));
+    }
+    if bytes[..4] != RAW_MAGIC {
+        return Err(err(0, "not a raw grid, bad magic"));
+    }
+    if bytes[4] != RAW_VERSION {
+        return Err(err(4, &format!("unknown version {}", bytes[4])));
+    }
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let (w, h) = (u32_at(5), u32_at(9));
+    if bytes[13] != 1 {
+        return Err(err(
+            13,
+            &format!("{} byte cells aren't supported", bytes[13]),
+        ));
+    }
+    if w as u64 * h as u64 > MAX_CELLS {
+        return Err(err(
+            5,
+            &format!("{}×{} is more than {} cells", w, h, MAX_CELLS),
+        ));
+    }
+    // no cells to read, and a height alone would still be allocated row by row
+    if w == 0 {
+        return Ok(Grid::new());
+    }
+    let cells = &bytes[RAW_HEADER..];
+    if Some(cells.len()) != w.checked_mul(h) {
+        return Err(err(
+            bytes.len(),
+            &format!(
+                "{}×{} needs {} cell bytes, found {}",
+                w,
+                h,
+                w as u64 * h as u64,
+                cells.len()
+            ),
+        ));
+    }
+    Ok(Grid::from(
+        cells.chunks(w).map(|r| r.to_vec()).collect::<Vec<_>>(),
+    ))
+}
+// raw }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: [[u8; 3]; 3] = [[0, 1, 0], [0, 0, 1], [1, 1, 1]];
+
+    #[test]
+    fn rle() {
+        let text = "#N Glider\n#C a comment\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!";
+        assert_eq!(
+            parse_rle(text, 1),
+            Ok(Grid::from(GLIDER.map(|r| r.to_vec()).to_vec()))
+        );
+        assert_eq!(
+            header(text, "rle"),
+            Ok(Header {
+                name: Some("Glider".to_string()),
+                size: Some((3, 3)),
+                rule: Some("B3/S23".to_string()),
+            })
+        );
+        // blank rows and split lines
+        assert_eq!(
+            parse_rle("x = 2, y = 4\no\nb$2$bo!", 1),
+            Ok(Grid::from(vec![
+                vec![1, 0],
+                vec![0, 0],
+                vec![0, 0],
+                vec![0, 1]
+            ]))
+        );
+        assert!(parse_rle("x = 1, y = 1\n2?!", 1).is_err());
+
+        // multi-state, with runs still in front
+        assert_eq!(
+            parse_rle("x = 4, y = 2, rule = B3/S23/G4\n.A2B$C.pAyO!", u8::MAX),
+            Ok(Grid::from(vec![vec![0, 1, 2, 2], vec![3, 0, 25, 255]]))
+        );
+        assert_eq!(
+            parse_rle("3C$o!", 2),
+            Ok(Grid::from(vec![vec![2, 2, 2], vec![2, 0, 0]]))
+        );
+        assert!(parse_rle("yP!", u8::MAX).is_err());
+        assert!(parse_rle("Z!", u8::MAX).is_err());
+        assert!(header("bo$2bo$3o!", "rle").is_err());
+
+        // runs past the caps are refused before they're allocated, multi-state ones too
+        for (text, line) in [
+            ("99999999999o!", 1),
+            ("o\n99999999999$o!", 2),
+            ("65537b!", 1),
+            ("o\n65537pA!", 2),
+            // each side fits, not the area
+            ("8192o$\n40000$o!", 2),
+        ] {
+            assert_eq!(parse_rle(text, 1).unwrap_err().line, line, "{}", text);
+        }
+        assert_eq!(
+            parse_rle(&format!("{}o!", MAX_SIDE), 1).map(|g| g[0].len()),
+            Ok(MAX_SIDE as usize)
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let text = "#N Glider\n#O Richard K. Guy\n#C the first\n#c spaceship\n#r 23/3\n\
+                    x = 3, y = 3\nbo$2bo$3o!\n#C after the header";
+        let metadata = rle_metadata(text);
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(metadata["N"], "Glider");
+        assert_eq!(metadata["O"], "Richard K. Guy");
+        assert_eq!(metadata["C"], "the first\nspaceship");
+        assert!(rle_metadata("x = 1, y = 1\no!").is_empty());
+
+        let glider = Grid::from(GLIDER.map(|r| r.to_vec()).to_vec());
+        let written = to_rle(&glider, "B3/S23", &metadata);
+        assert_eq!(
+            written,
+            "#N Glider\n#O Richard K. Guy\n#C the first\n#C spaceship\n\
+             x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+        assert_eq!(rle_metadata(&written), metadata);
+        assert_eq!(parse_rle(&written, 1), Ok(glider));
+    }
+
+    #[test]
+    fn life() {
+        let text = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+        assert_eq!(
+            parse_life(text, 1),
+            Ok(Grid::from(GLIDER.map(|r| r.to_vec()).to_vec()))
+        );
+        assert_eq!(header(text, "lif"), Ok(Header::default()));
+        assert!(header("#Life 1.05\n", "lif").is_err());
+        assert!(parse_life("#Life 1.06\n0 x\n", 1).is_err());
+        // far apart cells would make a huge grid, or overflow working out its size
+        let far = format!("#Life 1.06\n0 0\n{} 0\n", MAX_SIDE);
+        assert_eq!(parse_life(&far, 1).unwrap_err().line, 3);
+        let ends = format!("#Life 1.06\n{} 0\n{} 0\n", i64::MIN, i64::MAX);
+        assert_eq!(parse_life(&ends, 1).unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut wide = Grid::from(vec![vec![0; 200]; 4]);
+        for x in (0..200).step_by(3) {
+            wide[3][x] = 1
+        }
+        // parsers only pad rows to the widest one
+        wide[0][199] = 1;
+        let glider = Grid::from(GLIDER.map(|r| r.to_vec()).to_vec());
+        for grid in [glider.clone(), wide] {
+            for ext in EXTENSIONS {
+                let text = write(&grid, ext, "B3/S23", &HashMap::new());
+                assert_eq!(parse(&text, ext, 1), Ok(grid.clone()), "{}\n{}", ext, text);
+                assert!(header(&text, ext).is_ok());
+            }
+            assert!(to_rle(&grid, "B3/S23", &HashMap::new())
+                .lines()
+                .all(|l| l.len() <= 70));
+        }
+        assert_eq!(
+            to_rle(&glider, "B3/S23", &HashMap::new()),
+            "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+        assert_eq!(to_cells(&glider), ".O\n..O\nOOO\n");
+    }
+
+    #[test]
+    fn multi_state_round_trip() {
+        let mut states = Grid::from(vec![vec![0; 51]; 3]);
+        for (x, state) in [1, 2, 24, 25, 48, 49, 240, 255].into_iter().enumerate() {
+            states[0][x * 7] = state;
+            states[0][x * 7 + 1] = state;
+        }
+        states[2][0] = 3;
+        let text = to_rle(&states, "B3/S23", &HashMap::new());
+        assert!(text.lines().all(|l| l.len() <= 70), "{}", text);
+        assert!(text.contains("2A") && text.contains("2pA") && text.contains("2yO"));
+        assert_eq!(parse_rle(&text, u8::MAX), Ok(states));
+        assert_eq!(
+            to_rle(&Grid::from(vec![vec![2, 0, 1]]), "B3/S23", &HashMap::new()),
+            "x = 3, y = 1, rule = B3/S23\nB.A!\n"
+        );
+    }
+
+    #[test]
+    fn raw() {
+        let mut full = Grid::from(vec![vec![u8::MAX; 5]; 3]);
+        full[1][2] = 0;
+        for grid in [
+            Grid::new(),
+            Grid::from(GLIDER.map(|r| r.to_vec()).to_vec()),
+            full,
+        ] {
+            let bytes = to_raw(&grid);
+            assert_eq!(
+                bytes.len(),
+                RAW_HEADER + grid.iter().map(|r| r.len()).sum::<usize>()
+            );
+            assert_eq!(parse_raw(&bytes), Ok(grid));
+        }
+
+        let glider = to_raw(&Grid::from(GLIDER.map(|r| r.to_vec()).to_vec()));
+        assert_eq!(
+            glider[..RAW_HEADER],
+            [b'T', b'I', b'R', b'W', 1, 3, 0, 0, 0, 3, 0, 0, 0, 1]
+        );
+        assert_eq!(parse_raw(&glider[..10]).unwrap_err().offset, 10);
+        assert_eq!(
+            parse_raw(&glider[..glider.len() - 1]).unwrap_err().offset,
+            22
+        );
+        let mut bad = glider.clone();
+        bad[0] = b'X';
+        assert_eq!(parse_raw(&bad).unwrap_err().offset, 0);
+        let mut bad = glider.clone();
+        bad[13] = 2;
+        assert_eq!(parse_raw(&bad).unwrap_err().offset, 13);
+
+        // rows without a width have no cells to keep, however many the header claims
+        assert_eq!(
+            parse_raw(&to_raw(&Grid::from(vec![Vec::new(); 4]))),
+            Ok(Grid::new())
+        );
+        let mut tall = to_raw(&Grid::new());
+        tall[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse_raw(&tall), Ok(Grid::new()));
+        let mut huge = glider;
+        huge[5..13].copy_from_slice(&[0, 0, 1, 0, 0, 0, 1, 0]);
+        assert_eq!(parse_raw(&huge).unwrap_err().offset, 5);
+    }
+
+    #[test]
+    fn plaintext() {
+        assert_eq!(
+            parse_cells("!Name: Glider\n.O\n..O\nOOO\n", 3),
+            Ok(Grid::from(
+                GLIDER.map(|r| r.map(|c| c * 3).to_vec()).to_vec()
+            ))
+        );
+        assert_eq!(
+            header("!Name: Glider\n.O\n", "cells").unwrap().name,
+            Some("Glider".to_string())
+        );
+        assert_eq!(
+            parse_cells(".O\nOx", 1),
+            Err(ParseError {
+                line: 2,
+                message: "unexpected 'x'".to_string()
+            })
+        );
+    }
+}
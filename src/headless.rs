@@ -0,0 +1,313 @@
+//! The `run` subcommand, a rule on random soup without the TUI
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::cells::{Game, PeriodDetector, StepInfo};
+use super::progress::{Progress, Target};
+use super::stats::{Guard, RunSummary};
+
+/// longest oscillation `--stop-on-stable` looks for
+const MAX_PERIOD: usize = 32;
+
+// HeadlessOpts {{{
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeadlessOpts {
+    pub size: (usize, usize),
+    /// chance of each cell starting alive
+    pub density: f32,
+    pub seed: u64,
+    pub target: Target,
+    pub guards: Guards,
+}
+
+/// Why a run ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    Generations,
+    Time,
+    Died,
+    Stabilized,
+    Interrupted,
+    Guard(Guard),
+}
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Generations => "reached --generations",
+            Self::Time => "reached --until",
+            Self::Died => "died out",
+            Self::Stabilized => "stabilized",
+            Self::Interrupted => "interrupted",
+            Self::Guard(guard) => return guard.fmt(f),
+        })
+    }
+}
+// HeadlessOpts }}}
+
+// Guards {{{
+/// Limits that end a run as a runaway rather than a finish, for sweeps that can't wait out
+/// a rule that fills the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Guards {
+    /// fraction of the grid alive
+    pub density: Option<f32>,
+    /// stop when the cells start repeating
+    pub stable: bool,
+    pub time_limit: Option<Duration>,
+}
+
+impl Guards {
+    pub fn watch(self, area: usize, start: Instant) -> Watch {
+        Watch {
+            guards: self,
+            area,
+            start,
+            period: self.stable.then(|| PeriodDetector::new(MAX_PERIOD)),
+        }
+    }
+}
+
+/// `Guards` over one run
+pub struct Watch {
+    guards: Guards,
+    area: usize,
+    start: Instant,
+    period: Option<PeriodDetector>,
+}
+
+impl Watch {
+    /// Checks the step `info` described, which left `game` as it is
+    pub fn check(&mut self, info: &StepInfo, game: &Game, now: Instant) -> Option<Guard> {
+        if let Some(period) = &mut self.period {
+            if period.observe(game).is_some() {
+                return Some(Guard::Period);
+            }
+        }
+        if self
+            .guards
+            .density
+            .is_some_and(|d| info.population as f32 >= d * self.area as f32)
+        {
+            return Some(Guard::Density);
+        }
+        if self
+            .guards
+            .time_limit
+            .is_some_and(|t| now.duration_since(self.start) >= t)
+        {
+            return Some(Guard::TimeLimit);
+        }
+        None
+    }
+}
+// Guards }}}
+
+// run {{{
+/// Seeds the soup and advances until a target is reached, the cells settle,
+/// or `interrupted` is set. The guards watch from the step hook, so `game` is left as the run
+/// ended without one
+pub fn run<W: Write>(
+    game: &mut Game,
+    opts: &HeadlessOpts,
+    mut progress: Option<Progress<W>>,
+    interrupted: &AtomicBool,
+) -> (RunSummary, Stop) {
+    game.resize(opts.size.0, opts.size.1);
+    game.randomize(opts.density, &mut StdRng::seed_from_u64(opts.seed));
+    let start = Instant::now();
+    let mut watch = opts.guards.watch(opts.size.0 * opts.size.1, start);
+    let fired = Arc::new(Mutex::new(None));
+    let tripped = fired.clone();
+    game.set_step_hook(move |info, game| {
+        let mut tripped = tripped.lock().unwrap();
+        if tripped.is_none() {
+            *tripped = watch.check(info, game, Instant::now())
+        }
+    });
+    let mut generations = 0;
+    let mut max_population = game.population();
+
+    let stop = loop {
+        if opts.target.generations.is_some_and(|g| generations >= g) {
+            break Stop::Generations;
+        }
+        if opts.target.time.is_some_and(|t| start.elapsed() >= t) {
+            break Stop::Time;
+        }
+        if interrupted.load(Ordering::Relaxed) {
+            break Stop::Interrupted;
+        }
+        let info = game.advance();
+        generations += 1;
+        max_population = max_population.max(info.population);
+        if info.population == 0 {
+            break Stop::Died;
+        } else if info.changed == 0 {
+            break Stop::Stabilized;
+        } else if let Some(guard) = fired.lock().unwrap().take() {
+            break Stop::Guard(guard);
+        }
+        // progress is nice to have, a closed stderr shouldn't end the run
+        if let Some(progress) = progress.as_mut() {
+            let _ = progress.update(generations, info.population, Instant::now());
+        }
+    };
+    game.clear_step_hook();
+    if let Some(progress) = progress.as_mut() {
+        let _ = progress.finish(
+            generations,
+            game.population(),
+            Instant::now(),
+            &stop.to_string(),
+        );
+    }
+
+    let summary = RunSummary {
+        rule: *game.opts(),
+        generations,
+        max_population,
+        population: game.population(),
+        area: opts.size.0 * opts.size.1,
+        stable: game.stable(),
+        neighbors: game.neighbor_histogram(),
+        guard: match stop {
+            Stop::Guard(guard) => Some(guard),
+            _ => None,
+        },
+    };
+    (summary, stop)
+}
+// run }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::{conway, CellOpts};
+
+    fn opts(generations: Option<u64>) -> HeadlessOpts {
+        HeadlessOpts {
+            size: (40, 20),
+            density: 0.4,
+            seed: 5,
+            target: Target {
+                generations,
+                time: None,
+            },
+            guards: Guards::default(),
+        }
+    }
+
+    #[test]
+    fn stops() {
+        let rule = CellOpts {
+            life: 9,
+            ..conway()
+        };
+        let never = AtomicBool::new(false);
+        let progress = Progress::new(Vec::new(), false, Target::default(), Instant::now());
+        let (summary, stop) = run(
+            &mut Game::new(rule),
+            &opts(Some(30)),
+            Some(progress),
+            &never,
+        );
+        assert_eq!(stop, Stop::Generations);
+        assert_eq!(summary.generations, 30);
+        assert!(summary.max_population >= summary.population);
+
+        // same seed, same soup
+        let (again, _) = run(
+            &mut Game::new(rule),
+            &opts(Some(30)),
+            None::<Progress<Vec<u8>>>,
+            &never,
+        );
+        assert_eq!(again, summary);
+
+        let empty = HeadlessOpts {
+            density: 0.0,
+            ..opts(None)
+        };
+        let (summary, stop) = run(
+            &mut Game::new(rule),
+            &empty,
+            None::<Progress<Vec<u8>>>,
+            &never,
+        );
+        assert_eq!((stop, summary.generations), (Stop::Died, 1));
+
+        let (summary, stop) = run(
+            &mut Game::new(rule),
+            &opts(None),
+            None::<Progress<Vec<u8>>>,
+            &AtomicBool::new(true),
+        );
+        assert_eq!((stop, summary.generations), (Stop::Interrupted, 0));
+    }
+
+    #[test]
+    fn guards() {
+        let never = AtomicBool::new(false);
+        let explodes: CellOpts = "B1/S012345678".parse().unwrap();
+        let dense = HeadlessOpts {
+            size: (8, 8),
+            density: 0.05,
+            guards: Guards {
+                density: Some(0.5),
+                ..Default::default()
+            },
+            ..opts(Some(1000))
+        };
+        let (summary, stop) = run(
+            &mut Game::new(explodes),
+            &dense,
+            None::<Progress<Vec<u8>>>,
+            &never,
+        );
+        assert_eq!(stop, Stop::Guard(Guard::Density));
+        assert_eq!(summary.guard, Some(Guard::Density));
+        assert!(summary.population >= 32 && summary.generations < 1000);
+
+        // a blinker repeats every other step, which stops nothing by itself. Watching starts
+        // with the first step, so the repeat shows on the third
+        let mut blinker = Game::new(conway());
+        blinker.resize(5, 5);
+        for x in 1..4 {
+            blinker.set_cell(x, 2, 1)
+        }
+        let start = Instant::now();
+        let mut watch = Guards::default().watch(25, start);
+        let mut stable = Guards {
+            stable: true,
+            ..Default::default()
+        }
+        .watch(25, start);
+        let mut fired = Vec::new();
+        for _ in 0..4 {
+            let info = blinker.advance();
+            assert_eq!(watch.check(&info, &blinker, start), None);
+            fired.push(stable.check(&info, &blinker, start));
+        }
+        assert_eq!(
+            fired,
+            [None, None, Some(Guard::Period), Some(Guard::Period)]
+        );
+
+        let mut timed = Guards {
+            time_limit: Some(Duration::from_secs(30)),
+            ..Default::default()
+        }
+        .watch(25, start);
+        let info = blinker.advance();
+        let mut check = |secs| timed.check(&info, &blinker, start + Duration::from_secs(secs));
+        assert_eq!(check(29), None);
+        assert_eq!(check(30), Some(Guard::TimeLimit));
+        assert_eq!(Guard::TimeLimit.exit_code(), 5);
+    }
+}
@@ -1,18 +1,117 @@
-use clap::Parser;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueSource};
+use rand::{rngs::StdRng, SeedableRng};
 
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod bell;
+mod browser;
 pub mod cells;
+mod color;
+mod config;
+mod demo;
+mod formats;
+mod headless;
+mod patterns;
+mod picture;
+mod progress;
+mod screensaver;
+mod selftest;
+mod slots;
+mod stats;
+mod template;
+mod terminal;
 mod tui;
+mod versus;
+
+use bell::BellOn;
+use cells::{CellOpts, Game, Pressure, PressurePolicy, COUNTS};
+use color::{ColorChoice, ColorDepth};
+use config::FileConfig;
+use headless::{Guards, HeadlessOpts};
+use picture::{ImageOpts, Picture};
+use progress::{Progress, Target};
+use stats::SortColumn;
+use template::Template;
+use tui::{CatchUp, Fit, Start, TuiOpts};
+
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Print a table of the rules recorded in the stats database
+    Stats {
+        #[clap(short, long, value_enum, default_value = "runs")]
+        /// Column to sort by
+        sort: SortColumn,
+    },
+    /// Run the rule on random soup without the TUI, then print how it went
+    Run {
+        #[clap(long, value_name = "WxH", default_value = "200x100", value_parser = parse_size)]
+        /// World size
+        size: (usize, usize),
+
+        #[clap(long, value_name = "FRACTION", default_value_t = 0.3, value_parser = parse_density)]
+        /// Chance of each cell starting alive
+        density: f32,
+
+        #[clap(long)]
+        /// Seed for the soup, random otherwise. Recorded with --stats-db either way
+        seed: Option<u64>,
+
+        #[clap(long)]
+        /// Stop after this many generations
+        generations: Option<u64>,
 
-use cells::{Game, CellOpts};
-use tui::TuiOpts;
+        #[clap(long, value_name = "SECONDS", value_parser = parse_seconds)]
+        /// Stop after this much time
+        until: Option<Duration>,
+
+        #[clap(long)]
+        /// Keep one line of progress on stderr, redrawn on a terminal and every 10 seconds otherwise
+        progress: bool,
+
+        #[clap(long, value_name = "PATH")]
+        /// Write the final grid as a raw dump, a 14 byte header then a byte per cell. See formats.rs
+        dump_raw: Option<PathBuf>,
+
+        #[clap(long, value_name = "FRACTION", value_parser = parse_density)]
+        /// Give up once this much of the grid is alive, exiting with 3
+        stop_on_density: Option<f32>,
+
+        #[clap(long)]
+        /// Give up once the cells start oscillating, exiting with 4. Still lifes stop either way
+        stop_on_stable: bool,
+
+        #[clap(long, value_name = "TIME", value_parser = parse_duration)]
+        /// Give up after this long, exiting with 5. Seconds, or with an ms, s or m suffix
+        time_limit: Option<Duration>,
+    },
+    /// Check every engine against a plain reference advance on every possible small grid
+    Selftest {
+        #[clap(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..=selftest::MAX_SIZE as i64))]
+        /// Side of the square grids. 5 takes a while
+        size: u8,
+    },
+}
 
 #[derive(Parser, Debug, Clone)]
-#[clap(author, version, about)]
+#[clap(author, version, about, subcommand_negates_reqs = true)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(short, long)]
-    /// Use numbers 0-9 instead of blocks '█'
+    /// Use numbers 0-9 instead of blocks '█', heatmapped like them unless --color never
     numeric: bool,
 
+    #[clap(long)]
+    /// Use each cell's own value as a digit, hex when life is 16 or less, or the last
+    /// decimal digit past that
+    numeric_abs: bool,
+
     #[clap(short, long)]
     /// Use term colors instead of heatmap
     monochrome: bool,
@@ -21,47 +120,801 @@ struct Args {
     /// Only count 'true' neighbors
     no_corners: bool,
 
-    #[clap(short, long, multiple_values = true, value_parser = clap::value_parser!(u8).range(0..=8))]
+    #[clap(long)]
+    /// Don't count the neighbors sharing a side, so only corners with the default, none with -c
+    no_polars: bool,
+
+    #[clap(long)]
+    /// Only count neighbors at full life, so decaying cells don't cause growth or death. /M in --config rules
+    mature_neighbors: bool,
+
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=cells::MAX_RADIUS as i64))]
+    /// How many cells out neighbors are counted. Past 1 needs the large-neighborhood feature. /R in --config rules
+    radius: u8,
+
+    #[clap(short, long, multiple_values = true, value_parser = clap::value_parser!(u8).range(0..COUNTS as i64))]
     /// #s of neighbors that cause growth
     grow: Vec<u8>,
 
-    #[clap(short, long, multiple_values = true, value_parser = clap::value_parser!(u8).range(0..=8))]
+    #[clap(short, long, multiple_values = true, value_parser = clap::value_parser!(u8).range(0..COUNTS as i64))]
     /// #s of neighbors that cause death
     die: Vec<u8>,
 
     #[clap(short, long)]
-    /// Maximum life of a cell
-    life: u8,
+    /// Maximum life of a cell. Needed unless --config has a rule, or with --demo or --random-rule
+    // Option so subcommands and a config rule can skip it
+    life: Option<u8>,
+
+    #[clap(long, default_value_t = 350)]
+    /// Max milliseconds between clicks of a double/triple click
+    double_click_ms: u64,
+
+    #[clap(long)]
+    /// Record a summary of each run to this JSON file, ex ~/.local/share/terminal_illness/stats.json
+    stats_db: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Pause auto advance when live cells touch the edge
+    edge_warn: bool,
+
+    #[clap(long, conflicts_with_all = &["edge-warn", "auto-expand"])]
+    /// Join opposite edges, so cells leaving one side come back on the other
+    wrap: bool,
+
+    #[clap(long, requires = "wrap")]
+    /// Show 3 dim cells of the far side past each edge of the view, toggled with F11. Clicking them paints there
+    seam_preview: bool,
+
+    #[clap(long, value_name = "WxH", value_parser = parse_size)]
+    /// Grow the world past the terminal when cells near its edge, up to this size
+    auto_expand: Option<(usize, usize)>,
+
+    #[clap(long, default_value_t = 32)]
+    /// Cells added to a side each time the world grows
+    expand_margin: usize,
+
+    #[clap(long)]
+    /// Also list the .rle, .cells, and .lif files in this directory in the pattern picker
+    patterns_dir: Option<PathBuf>,
+
+    #[clap(long, requires = "patterns-dir")]
+    /// Include subdirectories of --patterns-dir
+    patterns_recursive: bool,
+
+    #[clap(long, value_name = "GENERATIONS", conflicts_with_all = &["demo", "auto-expand"])]
+    /// Two player mode, the bigger population after this many generations wins
+    versus: Option<u64>,
+
+    #[clap(long, conflicts_with = "demo")]
+    /// Start with this .rle, .cells, or .lif pattern in the middle of the world
+    load: Option<PathBuf>,
+
+    #[clap(long, conflicts_with_all = &["demo", "load"])]
+    /// Start with a raw dump from `run --dump-raw` or :dumpraw in the middle of the world
+    load_raw: Option<PathBuf>,
+
+    #[clap(long, conflicts_with_all = &["demo", "load", "load-raw"])]
+    /// Start with a png or jpeg scaled to the terminal, bright pixels alive. Needs the image feature
+    image: Option<PathBuf>,
+
+    #[clap(long, value_name = "FRACTION", default_value_t = 0.5, value_parser = parse_density)]
+    /// Luminance a pixel of --image must pass to be alive
+    image_threshold: f32,
+
+    #[clap(long, requires = "image")]
+    /// Dark pixels of --image are alive instead
+    image_invert: bool,
+
+    #[clap(long, requires = "image")]
+    /// Give live cells of --image a life by luminance instead of the full life
+    image_gray: bool,
+
+    #[clap(long, value_enum, default_value = "world")]
+    /// What to do with a loaded pattern bigger than the terminal
+    fit: Fit,
+
+    #[clap(long, value_enum, default_value = "auto")]
+    /// Color output. auto honors NO_COLOR and TERM=dumb, the numbers force a palette size
+    color: ColorChoice,
+
+    #[clap(long)]
+    /// Print the settings and detected terminal colors, then exit
+    print_config: bool,
+
+    #[clap(long, conflicts_with_all = &["demo", "versus", "strict-determinism"])]
+    /// Run forever, throwing in noise whenever things settle. Any key or mouse movement quits
+    screensaver: bool,
+
+    #[clap(long, value_name = "max:N|reanchor", default_value = "max:5")]
+    /// After falling behind, as from a suspend, run at most N missed generations or skip them all
+    catch_up: CatchUp,
+
+    #[clap(long, default_value_t = 10.0, value_parser = parse_speed)]
+    /// Generations per second when auto advancing, changed with + and - while running
+    speed: f32,
+
+    #[clap(long, default_value_t = 0.05, value_parser = parse_speed)]
+    /// Slowest + and - go, in generations per second
+    min_speed: f32,
+
+    #[clap(long, default_value_t = 120.0, value_parser = parse_speed)]
+    /// Fastest + and - go. Past 1000 there's no wait between generations at all
+    max_speed: f32,
+
+    #[clap(long, value_name = "FRACTION", value_parser = parse_density)]
+    /// Thin the grid whenever more than this fraction of it is alive. Not part of any rule, off by default
+    max_density: Option<f32>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value = "suppress-births",
+        requires = "max-density"
+    )]
+    /// How --max-density thins the grid
+    density_policy: PressurePolicy,
+
+    #[clap(long)]
+    /// Print frame timing and size statistics on exit
+    stats: bool,
+
+    #[clap(long)]
+    /// Print the frame count and draw times on exit
+    perf: bool,
+
+    #[clap(long)]
+    /// Where F1-F4 quick save slots persist, ex ~/.local/share/terminal_illness/slots.json
+    slots_file: Option<PathBuf>,
+
+    #[clap(long, value_enum, default_value = "off")]
+    /// Ring the terminal bell when the cells die out, start repeating, or every 100 generations. At most once a second
+    bell: BellOn,
+
+    #[clap(long)]
+    /// Queue each frame straight to the terminal instead of building it in one buffer first
+    legacy_draw: bool,
+
+    #[clap(long)]
+    /// Refuse anything random that couldn't be replayed, like ctrl+n noise or unseeded random culls.
+    /// Without it a dim marker in the top right shows when that's happened
+    strict_determinism: bool,
+
+    #[clap(long)]
+    /// JSON settings, watched for changes while running. Defaults to ~/.config/terminal_illness/config.json, see config.rs
+    config: Option<PathBuf>,
+
+    #[clap(long, conflicts_with_all = &["demo", "versus"])]
+    /// Apply a changed rule from --config while running instead of on the next start
+    hot_reload_rules: bool,
+
+    /// the rule from the config file, when none of -g, -d, -l, or the rest of `RULE_FLAGS` were given
+    #[clap(skip)]
+    rule: Option<CellOpts>,
+
+    #[clap(long, value_name = "TEMPLATE")]
+    /// Keep the terminal title set to this, with {gen} {pop} {rule} {state} {speed} {seed} {brush} {frameskip} {births} {deaths} {survivors} {steptime} {name} {author} filled in. The last four are averaged over recent steps. Without it, a loaded pattern's name and author are shown. {{ and }} are braces
+    title_format: Option<Template>,
+
+    #[clap(long, value_name = "TEMPLATE")]
+    /// Keep this on the bottom row, with the same placeholders as --title-format
+    status_format: Option<Template>,
+
+    #[clap(long, value_name = "COMMANDS")]
+    /// Run these : commands before the first frame, separated by ;, ex ":rule B36/S23; :fill 0.3; :tick 40; run". Also takes run, pause, and step N
+    startup: Option<String>,
+
+    #[clap(long, conflicts_with_all = &["demo", "grow", "die", "strict-determinism", "load", "load-raw", "image"])]
+    /// Pick a random rule that probably isn't boring and run it on soup. In the TUI M rolls another
+    random_rule: bool,
+
+    #[clap(long, requires = "random-rule")]
+    /// Let --random-rule give birth with no neighbors
+    allow_b0: bool,
+
+    #[clap(long)]
+    /// Run rules that never change a cell, like B/S012345678, without a warning or `run` refusing them
+    allow_trivial: bool,
+
+    #[clap(long)]
+    /// Edit with a keyboard cursor instead of the mouse, as happens anyway on terminals without mouse support
+    no_mouse: bool,
+
+    #[clap(long, value_name = "BYTES", value_parser = parse_bandwidth)]
+    /// Pace frames to this many bytes a second, with a k or M suffix, ex 64k. Without it a slow link is noticed from blocked writes
+    target_bandwidth: Option<u64>,
+
+    #[clap(long, value_name = "GENERATIONS", default_value_t = 256)]
+    /// Cell value counts kept for F5's plot, one per generation from the first F5 on while --life is above 1. 0 keeps none
+    value_history: usize,
+
+    /// where the config file is and what it said at startup
+    #[clap(skip)]
+    file_config: Option<(PathBuf, FileConfig)>,
+
+    #[clap(long)]
+    /// Walk through a few scripted scenes showing what the program does
+    demo: bool,
+}
+
+fn parse_size(s: &str) -> Result<(usize, usize), String> {
+    let (w, h) = s.split_once('x').ok_or("expected WxH, ex 1200x800")?;
+    Ok((
+        w.parse().map_err(|e| format!("{}", e))?,
+        h.parse().map_err(|e| format!("{}", e))?,
+    ))
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs >= 0.0 && secs.is_finite() => Ok(Duration::from_secs_f64(secs)),
+        Ok(_) => Err("must be 0 or more".to_string()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// Seconds, or a number with an `ms`, `s` or `m` suffix
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (number, scale) = if let Some(n) = s.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60.0)
+    } else {
+        (s, 1.0)
+    };
+    parse_seconds(number).map(|d| d.mul_f64(scale))
+}
+
+fn parse_bandwidth(s: &str) -> Result<u64, String> {
+    let (number, scale) = if let Some(n) = s.strip_suffix('k') {
+        (n, 1 << 10)
+    } else if let Some(n) = s.strip_suffix('M') {
+        (n, 1 << 20)
+    } else {
+        (s, 1)
+    };
+    match number.parse::<u64>() {
+        Ok(0) => Err("must be above 0".to_string()),
+        Ok(n) => n.checked_mul(scale).ok_or_else(|| "too big".to_string()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+fn parse_speed(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        Ok(_) => Err("must be above 0".to_string()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// The TUI draws with escape codes, which would only make a mess of a file or pipe
+fn check_terminal(stdout_is_tty: bool) -> Result<(), String> {
+    if stdout_is_tty {
+        Ok(())
+    } else {
+        Err(
+            "stdout isn't a terminal, the run subcommand simulates headless, \
+             and stats, selftest, and --print-config work without one too"
+                .to_string(),
+        )
+    }
+}
+
+fn parse_density(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(density) if (0.0..=1.0).contains(&density) => Ok(density),
+        Ok(_) => Err("must be between 0 and 1".to_string()),
+        Err(e) => Err(format!("{}", e)),
+    }
 }
 
 impl Args {
     pub fn cellopts(&self) -> CellOpts {
+        if let Some(rule) = self.rule {
+            return rule;
+        }
         let mut result = CellOpts {
             corners: !self.no_corners,
-            life: self.life,
-            grow: [false; 9],
-            die: [false; 9],
+            polars: !self.no_polars,
+            radius: self.radius,
+            // 0 would leave every cell dead, main warns about it
+            life: self.life.unwrap_or(1).max(1),
+            grow: [false; COUNTS],
+            survive: [true; COUNTS],
+            mature_neighbors_only: self.mature_neighbors,
         };
 
         for n in self.grow.iter() {
             result.grow[*n as usize] = true
         }
         for n in self.die.iter() {
-            result.die[*n as usize] = true
+            result.survive[*n as usize] = false
         }
 
         result
     }
 
+    /// A `--random-rule` with the neighborhood and life the flags ask for
+    pub fn roll_rule(&self, rng: &mut impl rand::Rng) -> CellOpts {
+        CellOpts::random(&self.cellopts(), self.allow_b0, rng)
+    }
+
+    /// Lays the config file over the flags it names, except those `given` says were on the
+    /// command line. Any of the rule's flags there keeps the file's rule out
+    pub fn apply_config(&mut self, path: PathBuf, file: FileConfig, given: impl Fn(&str) -> bool) {
+        if let Some(color) = file
+            .color
+            .filter(|_| !given("color") && !given("monochrome"))
+        {
+            self.color = color;
+            self.monochrome = false
+        }
+        if !given("numeric") {
+            self.numeric = file.numeric.unwrap_or(self.numeric)
+        }
+        if !given("speed") {
+            self.speed = file.speed.unwrap_or(self.speed)
+        }
+        if !given("bell") {
+            self.bell = file.bell.unwrap_or(self.bell)
+        }
+        if !RULE_FLAGS.iter().any(|id| given(id)) {
+            self.rule = file.rule.or(self.rule)
+        }
+        self.file_config = Some((path, file))
+    }
+
+    pub fn pressure(&self) -> Option<Pressure> {
+        self.max_density.map(|max_density| Pressure {
+            max_density,
+            policy: self.density_policy,
+        })
+    }
+
+    pub fn imageopts(&self) -> ImageOpts {
+        ImageOpts {
+            threshold: self.image_threshold,
+            invert: self.image_invert,
+            gray: self.image_gray,
+        }
+    }
+
     pub fn tuiopts(&self) -> TuiOpts {
-        TuiOpts { numeric: self.numeric, monochrome: self.monochrome }
+        let color = if self.monochrome {
+            ColorDepth::Monochrome
+        } else {
+            ColorDepth::detect(self.color, |k| std::env::var(k).ok())
+        };
+        TuiOpts {
+            numeric: self.numeric,
+            numeric_abs: self.numeric_abs,
+            monochrome: color == ColorDepth::Monochrome,
+            color,
+            color_choice: if self.monochrome {
+                ColorChoice::Never
+            } else {
+                self.color
+            },
+            double_click: Duration::from_millis(self.double_click_ms),
+            edge_warn: self.edge_warn,
+            auto_expand: self.auto_expand,
+            expand_margin: self.expand_margin,
+            stats: self.stats,
+            show_perf_stats: self.perf,
+            patterns_dir: self.patterns_dir.as_deref().map(stats::expand_home),
+            patterns_recursive: self.patterns_recursive,
+            versus: self.versus,
+            fit: self.fit,
+            screensaver: self.screensaver,
+            catch_up: self.catch_up,
+            initial_speed: self.speed,
+            min_speed: self.min_speed,
+            max_speed: self.max_speed,
+            legacy_draw: self.legacy_draw,
+            seam_preview: self.seam_preview,
+            bell: self.bell,
+            config: self.file_config.clone(),
+            hot_reload_rules: self.hot_reload_rules,
+            title_format: self.title_format.clone(),
+            status_format: self.status_format.clone(),
+            startup: self
+                .startup
+                .as_deref()
+                .map(tui::parse_startup)
+                .unwrap_or_default(),
+            mouse: !self.no_mouse,
+            allow_b0: self.allow_b0,
+            target_bandwidth: self.target_bandwidth,
+            value_history: self.value_history,
+            slots_file: self
+                .slots_file
+                .as_deref()
+                .map(stats::expand_home)
+                .or_else(slots::default_path),
+        }
     }
 }
 
+/// Flags the rule is built from, see `Args::cellopts`
+const RULE_FLAGS: &[&str] = &[
+    "no-corners",
+    "no-polars",
+    "mature-neighbors",
+    "radius",
+    "grow",
+    "die",
+    "life",
+];
+
+/// Whether the flag with clap's `id` was typed rather than left at its default
+fn on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
 fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.life == Some(0) {
+        eprintln!("--life 0 would keep every cell dead, using 1")
+    }
+
+    if let Some(Command::Stats { sort }) = args.command {
+        let Some(path) = args
+            .stats_db
+            .map(|p| stats::expand_home(&p))
+            .or_else(stats::default_path)
+        else {
+            eprintln!("Could not find a stats database, pass --stats-db");
+            std::process::exit(1)
+        };
+        match stats::load(&path) {
+            Ok(db) => print!("{}", stats::table(&db, sort)),
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                std::process::exit(1)
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Selftest { size }) = args.command {
+        let (size, rule) = (size as usize, args.cellopts());
+        if size >= selftest::SLOW_SIZE {
+            eprintln!(
+                "{}×{} is {} grids per engine, this takes a while",
+                size,
+                size,
+                1u64 << (size * size)
+            )
+        }
+        match selftest::check((size, size), &rule) {
+            Ok(grids) => println!(
+                "{}: all {} engines agree with the reference on all {} {}×{} grids",
+                rule,
+                selftest::ENGINES.len(),
+                grids,
+                size,
+                size
+            ),
+            Err(mismatch) => {
+                eprintln!("{}: {}", rule, mismatch);
+                std::process::exit(1)
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Run {
+        size,
+        density,
+        seed,
+        generations,
+        until,
+        progress,
+        ref dump_raw,
+        stop_on_density,
+        stop_on_stable,
+        time_limit,
+    }) = args.command
+    {
+        let seed = seed.unwrap_or_else(rand::random);
+        // the same seed rolls the same rule
+        let rule = if args.random_rule {
+            args.roll_rule(&mut StdRng::seed_from_u64(seed))
+        } else {
+            args.cellopts()
+        };
+        if rule.is_trivial() && !args.allow_trivial {
+            eprintln!(
+                "{} never changes a cell, pass --allow-trivial to run it anyway",
+                rule
+            );
+            std::process::exit(1)
+        }
+        let opts = HeadlessOpts {
+            size,
+            density,
+            seed,
+            target: Target {
+                generations,
+                time: until,
+            },
+            guards: Guards {
+                density: stop_on_density,
+                stable: stop_on_stable,
+                time_limit,
+            },
+        };
+        // Ctrl-C ends the run like reaching a target, so it still gets summed up and recorded
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let flag = interrupted.clone();
+        if let Err(e) = ctrlc::set_handler(move || flag.store(true, Ordering::Relaxed)) {
+            eprintln!("Could not catch Ctrl-C: {}", e)
+        }
+        let progress = progress.then(|| {
+            let tty = std::io::stderr().is_terminal();
+            Progress::new(std::io::stderr(), tty, opts.target, Instant::now())
+        });
+
+        let mut game = Game::new(rule);
+        game.set_pressure(args.pressure(), opts.seed);
+        game.set_wrap(args.wrap);
+        let (summary, stop) = headless::run(&mut game, &opts, progress, &interrupted);
+        if let Some(path) = dump_raw {
+            let path = stats::expand_home(path);
+            if let Err(e) = std::fs::write(&path, formats::to_raw(game.grid())) {
+                eprintln!("Could not write {}: {}", path.display(), e)
+            }
+        }
+        println!(
+            "{}: {:?} after {} generations, {}, population {} of {}, max {}, seed {}",
+            summary.rule,
+            summary.outcome(),
+            summary.generations,
+            stop,
+            summary.population,
+            summary.area,
+            summary.max_population,
+            opts.seed
+        );
 
-    tui::run(Game::new(args.cellopts()), args.tuiopts());
+        if let Some(path) = &args.stats_db {
+            let path = stats::expand_home(path);
+            let record = summary.record(Some(opts.seed));
+            if let Err(e) = stats::append(&path, &summary.rule.rulestring(), record) {
+                eprintln!("Could not record stats to {}: {}", path.display(), e)
+            }
+        }
+        if let Some(guard) = summary.guard {
+            std::process::exit(guard.exit_code())
+        }
+        return;
+    }
 
-    println!("{:?}", args.cellopts())
+    let mut args = args;
+    let path = args
+        .config
+        .as_deref()
+        .map(stats::expand_home)
+        .or_else(config::default_path);
+    if let Some(path) = path {
+        match config::load(&path) {
+            Ok(file) => args.apply_config(path, file, |id| on_command_line(&matches, id)),
+            Err(e) => {
+                eprintln!("Could not load the config, {}", e);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    if args.random_rule {
+        args.rule = Some(args.roll_rule(&mut rand::thread_rng()))
+    }
+    // only known to be missing once the config had its say
+    if args.life.is_none() && args.rule.is_none() && !args.demo && !args.print_config {
+        Args::command()
+            .error(
+                clap::ErrorKind::MissingRequiredArgument,
+                "--life is needed without a rule in --config",
+            )
+            .exit()
+    }
+
+    if args.print_config {
+        let var = |k| std::env::var(k).unwrap_or_else(|_| "unset".to_string());
+        println!("rule: {}", args.cellopts());
+        println!("pressure: {:?}", args.pressure());
+        println!(
+            "color: {} (TERM {}, COLORTERM {}, NO_COLOR {})",
+            args.tuiopts().color,
+            var("TERM"),
+            var("COLORTERM"),
+            var("NO_COLOR")
+        );
+        println!("{:#?}", args.tuiopts());
+        return;
+    }
+
+    if args.cellopts().is_trivial() && !args.allow_trivial && !args.demo {
+        eprintln!(
+            "{} never changes a cell, the grid will only change by hand",
+            args.cellopts()
+        )
+    }
+
+    let pattern = args.load.as_ref().map(|path| {
+        let path = stats::expand_home(path);
+        let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            std::process::exit(1)
+        });
+        let ext = formats::extension(&path).unwrap_or_else(|| {
+            eprintln!("{} isn't a .rle, .cells, or .lif file", path.display());
+            std::process::exit(1)
+        });
+        let pattern = formats::parse(&text, &ext, u8::MAX).unwrap_or_else(|e| {
+            eprintln!("Could not load {}: {}", path.display(), e);
+            std::process::exit(1)
+        });
+        let metadata = match ext.as_str() {
+            "rle" => formats::rle_metadata(&text),
+            _ => HashMap::new(),
+        };
+        (pattern, metadata)
+    });
+    let pattern = pattern.or_else(|| {
+        let path = stats::expand_home(args.load_raw.as_ref()?);
+        let bytes = std::fs::read(&path).unwrap_or_else(|e| {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            std::process::exit(1)
+        });
+        let pattern = formats::parse_raw(&bytes).unwrap_or_else(|e| {
+            eprintln!("Could not load {}: {}", path.display(), e);
+            std::process::exit(1)
+        });
+        Some((pattern, HashMap::new()))
+    });
+    // decoded now so errors land before the TUI, scaled once it knows the terminal size
+    let image = args.image.as_ref().map(|path| {
+        let path = stats::expand_home(path);
+        Picture::open(&path).unwrap_or_else(|e| {
+            eprintln!("Could not load {}: {}", path.display(), e);
+            std::process::exit(1)
+        })
+    });
+
+    if args.min_speed > args.max_speed {
+        eprintln!("--min-speed can't be above --max-speed");
+        std::process::exit(1)
+    }
+
+    if let Err(e) = check_terminal(std::io::stdout().is_terminal()) {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    }
+
+    let mut game = Game::new(args.cellopts());
+    game.set_strict_determinism(args.strict_determinism);
+    if let Err(e) = game.set_pressure_from_entropy(args.pressure()) {
+        eprintln!("--strict-determinism refuses {}", e);
+        std::process::exit(1)
+    }
+    game.set_wrap(args.wrap);
+    let summary = tui::run(
+        game,
+        args.tuiopts(),
+        args.demo.then(|| demo::Demo::new(demo::steps())),
+        pattern
+            .map(|(pattern, metadata)| Start::Pattern(pattern, metadata))
+            .or(image.map(|i| Start::Image(i, args.imageopts())))
+            .or(args.random_rule.then_some(Start::Soup)),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    println!("{:?}", summary.rule);
+
+    if let Some(path) = &args.stats_db {
+        let path = stats::expand_home(path);
+        if let Err(e) = stats::append(&path, &summary.rule.rulestring(), summary.record(None)) {
+            eprintln!("Could not record stats to {}: {}", path.display(), e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_required() {
+        assert_eq!(check_terminal(true), Ok(()));
+        let error = check_terminal(false).unwrap_err();
+        assert!(error.contains("--print-config"));
+        assert!(error.contains("run subcommand"));
+    }
+
+    #[test]
+    fn flags_over_config() {
+        let file = FileConfig {
+            numeric: Some(true),
+            speed: Some(2.5),
+            bell: Some(BellOn::Every100),
+            rule: Some("B36/S23".parse().unwrap()),
+            ..Default::default()
+        };
+        let apply = |argv: &[&str]| {
+            let matches = Args::command().get_matches_from(argv);
+            let mut args = Args::from_arg_matches(&matches).unwrap();
+            args.apply_config(PathBuf::new(), file, |id| on_command_line(&matches, id));
+            args
+        };
+
+        let args = apply(&["terminal_illness"]);
+        assert!(args.numeric);
+        assert_eq!((args.speed, args.bell), (2.5, BellOn::Every100));
+        assert_eq!(args.cellopts().rulestring(), "B36/S23");
+
+        let args = apply(&[
+            "terminal_illness",
+            "--speed",
+            "3",
+            "--bell",
+            "off",
+            "-l",
+            "2",
+        ]);
+        assert_eq!((args.speed, args.bell), (3.0, BellOn::Off));
+        assert_eq!(args.cellopts().life, 2);
+        assert!(!apply(&["terminal_illness", "-g", "3"]).cellopts().grow[6]);
+
+        let command = Args::command();
+        for id in RULE_FLAGS
+            .iter()
+            .chain(&["color", "monochrome", "numeric", "speed", "bell"])
+        {
+            assert!(command.get_arguments().any(|a| a.get_id() == *id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn life_at_least_one() {
+        let args = Args::parse_from(["terminal_illness", "-l", "0"]);
+        assert_eq!(args.cellopts().life, 1);
+        let args = Args::parse_from(["terminal_illness", "-l", "7"]);
+        assert_eq!(args.cellopts().life, 7);
+    }
+
+    #[test]
+    fn templates_checked_at_startup() {
+        let parse =
+            |flag, template| Args::try_parse_from(["terminal_illness", "-l", "1", flag, template]);
+        assert!(parse("--title-format", "gen {gen}").is_ok());
+        assert!(parse("--title-format", "gen {generation}").is_err());
+        assert!(parse("--status-format", "{pop").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert!(parse_duration("2h").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+
+    #[test]
+    fn bandwidths() {
+        assert_eq!(parse_bandwidth("9600"), Ok(9600));
+        assert_eq!(parse_bandwidth("64k"), Ok(64 << 10));
+        assert_eq!(parse_bandwidth("2M"), Ok(2 << 20));
+        assert!(parse_bandwidth("0k").is_err());
+        assert!(parse_bandwidth("1G").is_err());
+        assert!(parse_bandwidth("-5").is_err());
+    }
 }
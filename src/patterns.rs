@@ -0,0 +1,261 @@
+//! Pattern library, built-ins plus whatever is found in `--patterns-dir`
+
+use super::cells::Grid;
+use super::formats;
+
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// only this many lines are read looking for a file's header
+const HEADER_LINES: usize = 100;
+
+// Pattern {{{
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Body {
+    /// live cells are `u8::MAX` so they stamp at full life under any rule
+    Grid(Grid),
+    /// parsed when picked
+    File(PathBuf),
+    /// the header couldn't be read, so it can't be picked
+    Broken(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: String,
+    /// where the pattern came from, for grouping in the picker
+    pub source: String,
+    pub size: Option<(usize, usize)>,
+    pub rule: Option<String>,
+    pub body: Body,
+}
+
+impl Pattern {
+    /// The cells, reading the file if needed
+    pub fn load(&self) -> Result<Grid, String> {
+        match &self.body {
+            Body::Grid(grid) => Ok(grid.clone()),
+            Body::File(path) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", self.name, e))?;
+                let ext = formats::extension(path).unwrap_or_default();
+                formats::parse(&text, &ext, u8::MAX).map_err(|e| format!("{}: {}", self.name, e))
+            }
+            Body::Broken(e) => Err(e.clone()),
+        }
+    }
+
+    pub fn selectable(&self) -> bool {
+        !matches!(self.body, Body::Broken(_))
+    }
+}
+// Pattern }}}
+
+// builtins {{{
+pub const GLIDER: &str = "\
+.O
+..O
+OOO";
+
+pub const LWSS: &str = "\
+.O..O
+O
+O...O
+OOOO";
+
+pub const BLINKER: &str = "OOO";
+
+pub const TOAD: &str = "\
+.OOO
+OOO";
+
+pub const BEACON: &str = "\
+OO
+OO
+..OO
+..OO";
+
+pub const PULSAR: &str = "\
+..OOO...OOO
+.
+O....O.O....O
+O....O.O....O
+O....O.O....O
+..OOO...OOO
+.
+..OOO...OOO
+O....O.O....O
+O....O.O....O
+O....O.O....O
+.
+..OOO...OOO";
+
+pub const PENTADECATHLON: &str = "\
+..O....O
+OO.OOOO.OO
+..O....O";
+
+pub const GOSPER_GUN: &str = "\
+........................O
+......................O.O
+............OO......OO............OO
+...........O...O....OO............OO
+OO........O.....O...OO
+OO........O...O.OO....O.O
+..........O.....O.......O
+...........O...O
+............OO";
+
+pub const R_PENTOMINO: &str = "\
+.OO
+OO
+.O";
+
+pub const ACORN: &str = "\
+.O
+...O
+OO..OOO";
+
+pub const DIEHARD: &str = "\
+......O
+OO
+.O...OOO";
+
+const BUILTINS: &[(&str, &str)] = &[
+    ("Glider", GLIDER),
+    ("Lightweight spaceship", LWSS),
+    ("Blinker", BLINKER),
+    ("Toad", TOAD),
+    ("Beacon", BEACON),
+    ("Pulsar", PULSAR),
+    ("Pentadecathlon", PENTADECATHLON),
+    ("Gosper glider gun", GOSPER_GUN),
+    ("R-pentomino", R_PENTOMINO),
+    ("Acorn", ACORN),
+    ("Diehard", DIEHARD),
+];
+
+pub fn builtins() -> Vec<Pattern> {
+    BUILTINS
+        .iter()
+        .map(|(name, text)| {
+            let grid = formats::parse_cells(text, u8::MAX).expect("built-in patterns are valid");
+            Pattern {
+                name: name.to_string(),
+                source: "built-in".to_string(),
+                size: Some((grid.first().map(|r| r.len()).unwrap_or(0), grid.len())),
+                rule: None,
+                body: Body::Grid(grid),
+            }
+        })
+        .collect()
+}
+// builtins }}}
+
+// scan_dir {{{
+/// Lists the pattern files in a directory by their headers alone, sorted by path
+pub fn scan_dir(dir: &Path, recursive: bool) -> io::Result<Vec<Pattern>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    paths.sort();
+
+    let mut result = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            if recursive {
+                // an unreadable subdirectory shouldn't hide everything else
+                result.extend(scan_dir(&path, recursive).unwrap_or_default())
+            }
+            continue;
+        }
+        let Some(ext) = formats::extension(&path) else {
+            continue;
+        };
+
+        let mut pattern = Pattern {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            source: dir.display().to_string(),
+            size: None,
+            rule: None,
+            body: Body::File(path.clone()),
+        };
+
+        let head = fs::File::open(&path).and_then(|f| {
+            BufReader::new(f)
+                .lines()
+                .take(HEADER_LINES)
+                .collect::<io::Result<Vec<String>>>()
+        });
+        match head.map(|lines| formats::header(&lines.join("\n"), &ext)) {
+            Ok(Ok(header)) => {
+                pattern.name = header.name.unwrap_or(pattern.name);
+                pattern.size = header.size;
+                pattern.rule = header.rule;
+            }
+            Ok(Err(e)) => pattern.body = Body::Broken(format!("{}: {}", pattern.name, e)),
+            Err(e) => pattern.body = Body::Broken(format!("{}: {}", pattern.name, e)),
+        }
+        result.push(pattern)
+    }
+    Ok(result)
+}
+// scan_dir }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_parse() {
+        let patterns = builtins();
+        assert_eq!(patterns.len(), BUILTINS.len());
+        let gun = patterns
+            .iter()
+            .find(|p| p.name == "Gosper glider gun")
+            .unwrap();
+        assert_eq!(gun.size, Some((36, 9)));
+        let pulsar = patterns.iter().find(|p| p.name == "Pulsar").unwrap();
+        assert_eq!(pulsar.size, Some((13, 13)));
+        assert_eq!(pulsar.load().unwrap().len(), 13);
+    }
+
+    #[test]
+    fn scan() {
+        let dir = std::env::temp_dir().join(format!("ti_patterns_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("a.rle"),
+            "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!",
+        )
+        .unwrap();
+        fs::write(dir.join("b.cells"), "!Name: Blinker\nOOO\n").unwrap();
+        fs::write(dir.join("c.rle"), "bo$2bo$3o!").unwrap();
+        fs::write(dir.join("d.lif"), "#Life 1.06\n0 0\n1 x\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a pattern").unwrap();
+        fs::write(dir.join("sub/e.cells"), "O").unwrap();
+
+        let flat = scan_dir(&dir, false).unwrap();
+        let deep = scan_dir(&dir, true).unwrap();
+
+        let names: Vec<&str> = flat.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Glider", "Blinker", "c.rle", "d.lif"]);
+        assert_eq!(flat[0].size, Some((3, 3)));
+        assert_eq!(flat[0].rule.as_deref(), Some("B3/S23"));
+        assert_eq!(flat[1].load().unwrap(), vec![vec![u8::MAX; 3]]);
+        // bad header
+        assert!(!flat[2].selectable());
+        assert!(flat[2].load().is_err());
+        // good header, bad body
+        assert!(flat[3].selectable());
+        assert!(flat[3].load().is_err());
+
+        assert_eq!(deep.len(), 5);
+        assert!(deep[4].source.ends_with("sub"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Seeding the world from an image with `--image`.
+//!
+//! Decoding needs the `image` feature, the scaling and thresholding don't.
+
+use super::cells::Grid;
+
+use std::path::Path;
+
+// ImageOpts {{{
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageOpts {
+    /// luminance 0..=1 a pixel must pass to be alive
+    pub threshold: f32,
+    /// dark pixels are alive instead
+    pub invert: bool,
+    /// live cells get a life by luminance instead of all of it
+    pub gray: bool,
+}
+// ImageOpts }}}
+
+// Picture {{{
+/// A decoded image's luminance, 0 black to 1 white, row major
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picture {
+    pub width: usize,
+    pub height: usize,
+    pub luma: Vec<f32>,
+}
+
+impl Picture {
+    #[cfg(feature = "image")]
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let image = image::open(path).map_err(|e| e.to_string())?.to_luma32f();
+        Ok(Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            luma: image.into_raw(),
+        })
+    }
+
+    #[cfg(not(feature = "image"))]
+    pub fn open(_path: &Path) -> Result<Self, String> {
+        Err("built without the image feature, rebuild with --features image".to_string())
+    }
+
+    /// Averages the pixels under each of `w`×`h` cells.
+    /// Smaller images repeat pixels instead
+    pub fn downscale(&self, w: usize, h: usize) -> Vec<f32> {
+        if self.width == 0 || self.height == 0 {
+            return vec![0.0; w * h];
+        }
+        // pixels [start, end) along one axis, at least 1
+        let span = |n: usize, cells: usize, pixels: usize| {
+            let start = (n * pixels / cells).min(pixels - 1);
+            (start, ((n + 1) * pixels / cells).max(start + 1))
+        };
+
+        let mut result = Vec::with_capacity(w * h);
+        for y in 0..h {
+            let (y0, y1) = span(y, h, self.height);
+            for x in 0..w {
+                let (x0, x1) = span(x, w, self.width);
+                let sum: f32 = (y0..y1)
+                    .flat_map(|py| &self.luma[py * self.width + x0..py * self.width + x1])
+                    .sum();
+                result.push(sum / ((x1 - x0) * (y1 - y0)) as f32)
+            }
+        }
+        result
+    }
+
+    /// Scales to `w`×`h` cells, alive where the luminance passes `opts.threshold`. In gray a
+    /// cell that passes is at least 1, however dim
+    pub fn grid(&self, w: usize, h: usize, opts: &ImageOpts, life: u8) -> Grid {
+        let luma = self.downscale(w, h);
+        (0..h)
+            .map(|y| {
+                luma[y * w..(y + 1) * w]
+                    .iter()
+                    .map(|l| {
+                        let l = if opts.invert { 1.0 - l } else { *l };
+                        if l <= opts.threshold {
+                            0
+                        } else if opts.gray {
+                            ((l * life as f32).round() as u8).max(1)
+                        } else {
+                            life
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+// Picture }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// black on the left to white on the right
+    fn gradient(width: usize, height: usize) -> Picture {
+        Picture {
+            width,
+            height,
+            luma: (0..width * height)
+                .map(|n| (n % width) as f32 / (width - 1) as f32)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn downscale() {
+        let picture = gradient(100, 10);
+        let luma = picture.downscale(10, 2);
+        assert_eq!(luma.len(), 20);
+        for (n, l) in luma.iter().enumerate() {
+            // mean of x / 99 over 10 columns
+            let expected = ((n % 10) * 10) as f32 / 99.0 + 4.5 / 99.0;
+            assert!((l - expected).abs() < 1e-4, "{} {}", l, expected);
+        }
+        // repeating pixels
+        assert_eq!(
+            gradient(2, 1).downscale(4, 2),
+            [0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0]
+        );
+        let empty = Picture {
+            width: 0,
+            height: 0,
+            luma: Vec::new(),
+        };
+        assert_eq!(empty.downscale(2, 1), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn threshold() {
+        let picture = gradient(100, 10);
+        let mut opts = ImageOpts {
+            threshold: 0.5,
+            invert: false,
+            gray: false,
+        };
+        assert_eq!(
+            picture.grid(10, 1, &opts, 3),
+            vec![vec![0, 0, 0, 0, 0, 3, 3, 3, 3, 3]]
+        );
+        opts.invert = true;
+        assert_eq!(
+            picture.grid(10, 1, &opts, 3),
+            vec![vec![3, 3, 3, 3, 3, 0, 0, 0, 0, 0]]
+        );
+
+        opts = ImageOpts {
+            threshold: 0.0,
+            invert: false,
+            gray: true,
+        };
+        // the darkest column still passes, and loads alive
+        assert_eq!(
+            picture.grid(10, 1, &opts, 9),
+            vec![vec![1, 1, 2, 3, 4, 5, 6, 7, 8, 9]]
+        );
+        opts.threshold = 0.1;
+        assert_eq!(picture.grid(10, 1, &opts, 2)[0][..3], [0, 1, 1]);
+    }
+}
@@ -0,0 +1,247 @@
+//! One line of progress on stderr for long runs without the TUI, with `--progress`.
+//!
+//! On a terminal the line is redrawn in place, anywhere else a new one is written
+//! now and then so logs stay readable. Time is always passed in so tests can fake it.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Least time between redraws on a terminal, at most 5 a second
+const TTY_INTERVAL: Duration = Duration::from_millis(200);
+/// Least time between lines anywhere else
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+// Target {{{
+/// Where a run stops, for the ETA. Whichever comes first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Target {
+    pub generations: Option<u64>,
+    pub time: Option<Duration>,
+}
+// Target }}}
+
+// Progress {{{
+#[derive(Debug)]
+pub struct Progress<W: Write> {
+    out: W,
+    tty: bool,
+    target: Target,
+    start: Instant,
+    last: Instant,
+    /// length of the line on screen, so a shorter one can cover it
+    width: usize,
+}
+
+impl<W: Write> Progress<W> {
+    pub fn new(out: W, tty: bool, target: Target, now: Instant) -> Self {
+        Self {
+            out,
+            tty,
+            target,
+            start: now,
+            last: now,
+            width: 0,
+        }
+    }
+
+    /// Generations per second since the start
+    pub fn rate(&self, generation: u64, now: Instant) -> f64 {
+        let elapsed = now.duration_since(self.start).as_secs_f64();
+        if elapsed > 0.0 {
+            generation as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Time left until the nearest target at the rate so far. No rate yet, or a wait too long
+    /// for a `Duration`, gives no estimate for the generation target
+    pub fn eta(&self, generation: u64, now: Instant) -> Option<Duration> {
+        let rate = self.rate(generation, now);
+        let by_generations = self
+            .target
+            .generations
+            .filter(|_| rate > 0.0)
+            .and_then(|g| {
+                Duration::try_from_secs_f64(g.saturating_sub(generation) as f64 / rate).ok()
+            });
+        let by_time = self
+            .target
+            .time
+            .map(|t| t.saturating_sub(now.duration_since(self.start)));
+        match (by_generations, by_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    pub fn line(&self, generation: u64, population: usize, now: Instant) -> String {
+        let mut result = format!(
+            "generation {}  {:.1} gen/s  population {}",
+            generation,
+            self.rate(generation, now),
+            population
+        );
+        if let Some(eta) = self.eta(generation, now) {
+            result += &format!("  eta {}", format_duration(eta))
+        }
+        result
+    }
+
+    /// Writes the line if it's been long enough since the last one. True if it did
+    pub fn update(&mut self, generation: u64, population: usize, now: Instant) -> io::Result<bool> {
+        let interval = if self.tty { TTY_INTERVAL } else { LOG_INTERVAL };
+        if now.duration_since(self.last) < interval {
+            return Ok(false);
+        }
+        self.last = now;
+        self.write(&self.line(generation, population, now))?;
+        Ok(true)
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        if self.tty {
+            let pad = self.width.saturating_sub(line.len());
+            write!(self.out, "\r{}{:pad$}", line, "")?;
+            self.width = line.len();
+        } else {
+            writeln!(self.out, "{}", line)?;
+        }
+        self.out.flush()
+    }
+
+    /// Writes the line one last time, ends it, and sums up the run
+    pub fn finish(
+        &mut self,
+        generation: u64,
+        population: usize,
+        now: Instant,
+        reason: &str,
+    ) -> io::Result<()> {
+        self.write(&self.line(generation, population, now))?;
+        if self.tty {
+            writeln!(self.out)?
+        }
+        writeln!(
+            self.out,
+            "{}, {} generations in {} at {:.1} gen/s",
+            reason,
+            generation,
+            format_duration(now.duration_since(self.start)),
+            self.rate(generation, now)
+        )?;
+        self.out.flush()
+    }
+}
+// Progress }}}
+
+/// `1h02m03s`, `2m03s`, or `3.2s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cadence() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let target = Target {
+            generations: Some(1000),
+            time: None,
+        };
+
+        let mut tty = Progress::new(Vec::new(), true, target, start);
+        let written: Vec<bool> = (1..=10)
+            .map(|n| tty.update(n * 10, 5, start + ms(n * 100)).unwrap())
+            .collect();
+        assert_eq!(
+            written,
+            [false, true, false, true, false, true, false, true, false, true]
+        );
+        let text = String::from_utf8(tty.out.clone()).unwrap();
+        assert_eq!(text.matches('\r').count(), 5);
+        assert!(!text.contains('\n'));
+        // 100 generations a second, 900 to go
+        assert!(text.ends_with("generation 100  100.0 gen/s  population 5  eta 9.0s"));
+
+        let mut log = Progress::new(Vec::new(), false, Target::default(), start);
+        let written = (1..=25)
+            .filter(|n| log.update(*n, 5, start + Duration::from_secs(*n)).unwrap())
+            .count();
+        assert_eq!(written, 2);
+        let text = String::from_utf8(log.out).unwrap();
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                "generation 10  1.0 gen/s  population 5",
+                "generation 20  1.0 gen/s  population 5"
+            ]
+        );
+    }
+
+    #[test]
+    fn finish() {
+        let start = Instant::now();
+        let secs = Duration::from_secs;
+        let mut progress = Progress::new(Vec::new(), true, Target::default(), start);
+        progress.update(1000, 12345, start + secs(1)).unwrap();
+        progress
+            .finish(2000, 7, start + secs(2), "reached 2000 generations")
+            .unwrap();
+        let longer = "generation 1000  1000.0 gen/s  population 12345";
+        let shorter = "generation 2000  1000.0 gen/s  population 7";
+        assert_eq!(
+            String::from_utf8(progress.out).unwrap(),
+            format!(
+                "\r{}\r{}{}\nreached 2000 generations, 2000 generations in 2.0s at 1000.0 gen/s\n",
+                longer,
+                shorter,
+                " ".repeat(longer.len() - shorter.len())
+            )
+        );
+    }
+
+    #[test]
+    fn eta() {
+        let start = Instant::now();
+        let target = Target {
+            generations: Some(100),
+            time: Some(Duration::from_secs(60)),
+        };
+        let progress = Progress::new(Vec::new(), false, target, start);
+        let now = start + Duration::from_secs(10);
+        assert_eq!(progress.eta(50, now), Some(Duration::from_secs(10)));
+        assert_eq!(progress.eta(1, now), Some(Duration::from_secs(50)));
+        assert_eq!(progress.eta(0, start), Some(Duration::from_secs(60)));
+        // a huge target at a slow start is past what a Duration holds
+        let huge = Target {
+            generations: Some(u64::MAX),
+            time: None,
+        };
+        let progress = Progress::new(Vec::new(), false, huge, start);
+        let later = start + Duration::from_secs(1_000_000);
+        assert_eq!(progress.eta(1, later), None);
+        let progress = Progress::new(
+            Vec::new(),
+            false,
+            Target {
+                time: Some(Duration::from_secs(2_000_000)),
+                ..huge
+            },
+            start,
+        );
+        assert_eq!(progress.eta(1, later), Some(Duration::from_secs(1_000_000)));
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+        assert_eq!(format_duration(Duration::from_secs(123)), "2m03s");
+    }
+}
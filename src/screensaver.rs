@@ -0,0 +1,208 @@
+//! `--screensaver`, which keeps a simulation from ever settling down.
+//!
+//! The tui feeds what period detection saw each generation to `Screensaver::react`,
+//! which decides whether to stir things up. The decisions are plain data so the
+//! policy can be tested without a game.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::cells::Game;
+
+// Policy {{{
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Policy {
+    /// radius in cells of the noise disc dropped on a stagnant grid
+    pub patch_radius: usize,
+    /// chance of each cell in new noise being alive
+    pub density: f32,
+    /// generations after stirring before stagnation counts again, giving noise time to settle
+    pub cooldown: u64,
+    /// stagnant detections in a row answered with patches before the whole grid is redone
+    pub patches_before_reset: u32,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            patch_radius: 6,
+            density: 0.4,
+            cooldown: 30,
+            patches_before_reset: 5,
+        }
+    }
+}
+// Policy }}}
+
+// Screensaver {{{
+/// What period detection saw this generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Active,
+    /// repeating with some period, still lifes included
+    Stagnant,
+    Extinct,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Nothing,
+    /// noise disc centered on x, y
+    Patch {
+        x: usize,
+        y: usize,
+    },
+    /// noise over the whole grid
+    Reset,
+}
+
+#[derive(Clone, Debug)]
+pub struct Screensaver {
+    pub policy: Policy,
+    cooldown: u64,
+    /// patches since the grid was last active on its own
+    patches: u32,
+    rng: StdRng,
+}
+
+impl Screensaver {
+    pub fn new(policy: Policy, seed: u64) -> Self {
+        Self {
+            policy,
+            cooldown: 0,
+            patches: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Decides what to do about a generation of a `w`×`h` grid
+    pub fn react(&mut self, signal: Signal, w: usize, h: usize) -> Action {
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return Action::Nothing;
+        }
+        let action = match signal {
+            Signal::Active => {
+                self.patches = 0;
+                return Action::Nothing;
+            }
+            Signal::Extinct => Action::Reset,
+            Signal::Stagnant if self.patches >= self.policy.patches_before_reset => Action::Reset,
+            Signal::Stagnant => {
+                self.patches += 1;
+                Action::Patch {
+                    x: self.rng.gen_range(0..w.max(1)),
+                    y: self.rng.gen_range(0..h.max(1)),
+                }
+            }
+        };
+        if action == Action::Reset {
+            self.patches = 0
+        }
+        self.cooldown = self.policy.cooldown;
+        action
+    }
+
+    pub fn apply(&mut self, action: Action, game: &mut Game) {
+        let life = game.opts().life;
+        match action {
+            Action::Nothing => (),
+            Action::Reset => game.randomize(self.policy.density, &mut self.rng),
+            Action::Patch { x, y } => {
+                let r = self.policy.patch_radius;
+                for py in y.saturating_sub(r)..=y + r {
+                    for px in x.saturating_sub(r)..=x + r {
+                        let (dx, dy) = (px.abs_diff(x), py.abs_diff(y));
+                        if dx * dx + dy * dy <= r * r {
+                            let val = if self.rng.gen::<f32>() < self.policy.density {
+                                life
+                            } else {
+                                0
+                            };
+                            game.set_cell(px, py, val)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+// Screensaver }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::conway;
+
+    fn policy() -> Policy {
+        Policy {
+            patch_radius: 2,
+            density: 1.0,
+            cooldown: 2,
+            patches_before_reset: 2,
+        }
+    }
+
+    #[test]
+    fn patches_then_reset() {
+        let mut saver = Screensaver::new(policy(), 1);
+        assert_eq!(saver.react(Signal::Active, 10, 10), Action::Nothing);
+        assert!(matches!(
+            saver.react(Signal::Stagnant, 10, 10),
+            Action::Patch { .. }
+        ));
+        // cooling down
+        assert_eq!(saver.react(Signal::Stagnant, 10, 10), Action::Nothing);
+        assert_eq!(saver.react(Signal::Stagnant, 10, 10), Action::Nothing);
+        assert!(matches!(
+            saver.react(Signal::Stagnant, 10, 10),
+            Action::Patch { .. }
+        ));
+        saver.react(Signal::Stagnant, 10, 10);
+        saver.react(Signal::Stagnant, 10, 10);
+        assert_eq!(saver.react(Signal::Stagnant, 10, 10), Action::Reset);
+        saver.react(Signal::Stagnant, 10, 10);
+        saver.react(Signal::Stagnant, 10, 10);
+        // the count starts over after a reset
+        assert!(matches!(
+            saver.react(Signal::Stagnant, 10, 10),
+            Action::Patch { .. }
+        ));
+    }
+
+    #[test]
+    fn activity_forgives() {
+        let mut saver = Screensaver::new(
+            Policy {
+                cooldown: 0,
+                ..policy()
+            },
+            1,
+        );
+        saver.react(Signal::Stagnant, 10, 10);
+        saver.react(Signal::Stagnant, 10, 10);
+        assert_eq!(saver.react(Signal::Active, 10, 10), Action::Nothing);
+        assert!(matches!(
+            saver.react(Signal::Stagnant, 10, 10),
+            Action::Patch { .. }
+        ));
+        assert_eq!(saver.react(Signal::Extinct, 10, 10), Action::Reset);
+    }
+
+    #[test]
+    fn patch_is_a_disc() {
+        let mut game = Game::new(conway());
+        game.resize(20, 20);
+        let mut saver = Screensaver::new(policy(), 1);
+        saver.apply(Action::Patch { x: 10, y: 10 }, &mut game);
+        // radius 2 at full density
+        assert_eq!(game.population(), 13);
+        assert_eq!(game.get_cell(12, 10), Some(1));
+        assert_eq!(game.get_cell(12, 12), Some(0));
+
+        // clipped at the corner
+        let mut game = Game::new(conway());
+        game.resize(20, 20);
+        saver.apply(Action::Patch { x: 0, y: 0 }, &mut game);
+        assert_eq!(game.population(), 6);
+    }
+}
@@ -0,0 +1,260 @@
+//! The `selftest` subcommand, every engine against `cells::reference` on every possible
+//! small grid.
+//!
+//! Each binary grid of the size is advanced one step with and without wrapping, and the
+//! cells and population each engine gives have to match the reference exactly. 3×3 and 4×4
+//! run with `cargo test`, 5×5 is 2²⁵ grids and only worth it by hand.
+
+use std::fmt;
+
+use super::cells::{advance_sumtable, reference, CellOpts, Game, Grid};
+
+/// Largest side `selftest --size` takes, past it the grids can't be counted in a `u64`
+pub const MAX_SIZE: usize = 5;
+
+/// Sides this big or more get a warning first
+pub const SLOW_SIZE: usize = 5;
+
+// Engine {{{
+/// The next generation and its population, `None` for grids or rules the engine
+/// doesn't handle
+type Step = fn(&Grid, &CellOpts, bool) -> Option<(Grid, usize)>;
+
+#[derive(Clone, Copy)]
+pub struct Engine {
+    pub name: &'static str,
+    step: Step,
+}
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+fn masked(from: &Grid, opts: &CellOpts, wrap: bool) -> Option<(Grid, usize)> {
+    let mut to = from.clone();
+    let population = from
+        .advance_masked(&mut to, *opts, opts.life, None, wrap)
+        .population;
+    Some((to, population))
+}
+
+fn binary(from: &Grid, opts: &CellOpts, wrap: bool) -> Option<(Grid, usize)> {
+    if opts.life != 1 {
+        return None;
+    }
+    let mut to = from.clone();
+    let population = from.advance_binary(&mut to, *opts, None, wrap).population;
+    Some((to, population))
+}
+
+fn sumtable(from: &Grid, opts: &CellOpts, wrap: bool) -> Option<(Grid, usize)> {
+    if wrap {
+        return None;
+    }
+    let mut to = from.clone();
+    let population = advance_sumtable(from, &mut to, *opts);
+    Some((to, population))
+}
+
+/// Whichever path `Game::advance` picks, with its own buffers and population count
+fn game(from: &Grid, opts: &CellOpts, wrap: bool) -> Option<(Grid, usize)> {
+    let mut game = Game::new(*opts);
+    game.resize(from.first().map(|r| r.len()).unwrap_or(0), from.len());
+    game.set_wrap(wrap);
+    game.stamp(from, 0, 0);
+    let info = game.advance();
+    Some((game.grid().clone(), info.population))
+}
+
+pub const ENGINES: [Engine; 4] = [
+    Engine {
+        name: "masked",
+        step: masked,
+    },
+    Engine {
+        name: "binary",
+        step: binary,
+    },
+    Engine {
+        name: "sumtable",
+        step: sumtable,
+    },
+    Engine {
+        name: "game",
+        step: game,
+    },
+];
+// Engine }}}
+
+// Mismatch {{{
+/// The first grid an engine got wrong
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub engine: &'static str,
+    pub wrap: bool,
+    pub before: Grid,
+    pub expected: (Grid, usize),
+    pub got: (Grid, usize),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (w, h) = (
+            self.before.first().map(|r| r.len()).unwrap_or(0),
+            self.before.len(),
+        );
+        writeln!(
+            f,
+            "{} differs from the reference on this {}×{} grid{}:",
+            self.engine,
+            w,
+            h,
+            if self.wrap { ", wrapped" } else { "" }
+        )?;
+        let columns = [
+            ("before", &self.before),
+            ("reference", &self.expected.0),
+            (self.engine, &self.got.0),
+        ];
+        let width = columns
+            .iter()
+            .map(|(n, _)| n.len())
+            .max()
+            .unwrap_or(0)
+            .max(w)
+            + 2;
+        for (name, _) in columns {
+            write!(f, "{:width$}", name)?
+        }
+        writeln!(f)?;
+        for y in 0..h {
+            for (_, grid) in columns {
+                let line: String = grid[y]
+                    .iter()
+                    .map(|c| if *c == 0 { '.' } else { '#' })
+                    .collect();
+                write!(f, "{:width$}", line)?
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "population {} from the reference, {} from {}",
+            self.expected.1, self.got.1, self.engine
+        )
+    }
+}
+// Mismatch }}}
+
+/// The grid numbered `bits`, cell `i` row major alive at `opts.life` when bit `i` is set
+fn nth_grid(bits: u64, (w, h): (usize, usize), life: u8) -> Grid {
+    (0..h)
+        .map(|y| {
+            (0..w)
+                .map(|x| {
+                    if bits >> (y * w + x) & 1 != 0 {
+                        life
+                    } else {
+                        0
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Runs `engines` against the reference on all 2^(w×h) grids of `size`, leaving off at
+/// the first mismatch. Returns how many grids were checked
+pub fn check_with(
+    engines: &[Engine],
+    size: (usize, usize),
+    opts: &CellOpts,
+) -> Result<u64, Box<Mismatch>> {
+    let cells = size.0 * size.1;
+    assert!(cells < 64, "{}×{} is too many grids", size.0, size.1);
+    for bits in 0..1 << cells {
+        let before = nth_grid(bits, size, opts.life);
+        for wrap in [false, true] {
+            let next = reference::advance(&before, opts, wrap);
+            let population = next.iter().flatten().filter(|c| **c != 0).count();
+            let expected = (next, population);
+            for engine in engines {
+                match (engine.step)(&before, opts, wrap) {
+                    Some(got) if got != expected => {
+                        return Err(Box::new(Mismatch {
+                            engine: engine.name,
+                            wrap,
+                            before,
+                            expected,
+                            got,
+                        }))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    Ok(1 << cells)
+}
+
+/// `check_with` every engine
+pub fn check(size: (usize, usize), opts: &CellOpts) -> Result<u64, Box<Mismatch>> {
+    check_with(&ENGINES, size, opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::MAX_RADIUS;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn three_by_three() {
+        let mut rng = StdRng::seed_from_u64(200);
+        let mut life: CellOpts = "B3/S23".parse().unwrap();
+        for i in 0..16 * MAX_RADIUS as usize {
+            life.radius = 1 + (i / 16) as u8;
+            let mut opts = CellOpts::random(&life, true, &mut rng);
+            opts.corners = i & 1 == 0;
+            opts.polars = i & 2 == 0;
+            opts.mature_neighbors_only = i & 4 == 0;
+            opts.life = if i & 8 == 0 { 1 } else { 3 };
+            assert_eq!(check((3, 3), &opts), Ok(512), "{}", opts);
+        }
+    }
+
+    #[test]
+    fn four_by_four() {
+        // a few seconds a rule unoptimized, the random ones get their turn at 3×3
+        let opts: CellOpts = "B3/S23".parse().unwrap();
+        if let Err(e) = check((4, 4), &opts) {
+            panic!("{}", e)
+        }
+    }
+
+    #[test]
+    fn catches_a_broken_engine() {
+        let frozen = Engine {
+            name: "frozen",
+            step: |from, _, _| Some((from.clone(), 0)),
+        };
+        let opts: CellOpts = "B3/S23".parse().unwrap();
+        let mismatch = check_with(&[ENGINES[0], frozen], (3, 3), &opts).unwrap_err();
+        assert_eq!(mismatch.engine, "frozen");
+        assert!(!mismatch.wrap);
+        // the first grid with anything alive dies under life
+        assert_eq!(mismatch.before, nth_grid(1, (3, 3), 1));
+        assert_eq!(mismatch.expected, (nth_grid(0, (3, 3), 1), 0));
+        assert_eq!(
+            mismatch.to_string(),
+            "frozen differs from the reference on this 3×3 grid:\n\
+             before     reference  frozen     \n\
+             #..        ...        #..        \n\
+             ...        ...        ...        \n\
+             ...        ...        ...        \n\
+             population 0 from the reference, 0 from frozen"
+        );
+    }
+}
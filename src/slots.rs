@@ -0,0 +1,156 @@
+//! Quick save slots, snapshots of the grid kept in memory and mirrored to a JSON file.
+//!
+//! The file is only read when a slot is first used and only written when one is saved,
+//! or on exit if that write failed.
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Slots 1 to this many
+pub const SLOTS: usize = 4;
+
+// Snapshot {{{
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub generation: u64,
+    /// rows of cell values
+    pub cells: Vec<Vec<u8>>,
+    /// nothing random went unrecorded on the way here, true for older files
+    #[serde(default = "yes")]
+    pub reproducible: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+// Snapshot }}}
+
+// SlotStore {{{
+type Slots = [Option<Snapshot>; SLOTS];
+
+/// `slots.json` next to the stats database
+pub fn default_path() -> Option<PathBuf> {
+    super::stats::data_dir().map(|d| d.join("slots.json"))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SlotStore {
+    /// kept in memory only without one
+    path: Option<PathBuf>,
+    /// read from `path` on first use
+    slots: Option<Slots>,
+    /// saved since the last successful write
+    dirty: bool,
+}
+
+fn read(path: &Path) -> io::Result<Slots> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+        Err(e) => Err(e),
+    }
+}
+
+impl SlotStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    fn slots(&mut self) -> io::Result<&mut Slots> {
+        if self.slots.is_none() {
+            self.slots = Some(match &self.path {
+                Some(path) => read(path)?,
+                None => Default::default(),
+            })
+        }
+        Ok(self.slots.as_mut().unwrap())
+    }
+
+    /// Slot `n`, 1 indexed. None when it's empty
+    pub fn get(&mut self, n: usize) -> io::Result<Option<&Snapshot>> {
+        Ok(self
+            .slots()?
+            .get(n.wrapping_sub(1))
+            .and_then(|s| s.as_ref()))
+    }
+
+    /// Fills slot `n`, 1 indexed, and writes the file
+    pub fn save(&mut self, n: usize, snapshot: Snapshot) -> io::Result<()> {
+        let slot = self
+            .slots()?
+            .get_mut(n.wrapping_sub(1))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("no slot {}", n)))?;
+        *slot = Some(snapshot);
+        self.dirty = true;
+        self.flush()
+    }
+
+    /// Writes the file if a save hasn't reached it yet
+    pub fn flush(&mut self) -> io::Result<()> {
+        let (Some(path), Some(slots), true) = (&self.path, &self.slots, self.dirty) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?
+        }
+        let text = serde_json::to_string(slots)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+// SlotStore }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(generation: u64) -> Snapshot {
+        Snapshot {
+            generation,
+            cells: vec![vec![0, 1, 2], vec![3, 0, 0]],
+            reproducible: true,
+        }
+    }
+
+    #[test]
+    fn persisted() {
+        let path = std::env::temp_dir().join(format!("ti_slots_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut store = SlotStore::new(Some(path.clone()));
+        assert_eq!(store.get(1).unwrap(), None);
+        // reading doesn't make a file
+        assert!(!path.exists());
+        store.save(2, snapshot(7)).unwrap();
+        store.save(4, snapshot(9)).unwrap();
+        assert!(store.save(5, snapshot(1)).is_err());
+        assert_eq!(store.get(0).unwrap(), None);
+
+        let mut reopened = SlotStore::new(Some(path.clone()));
+        assert_eq!(reopened.get(2).unwrap(), Some(&snapshot(7)));
+        assert_eq!(reopened.get(4).unwrap().map(|s| s.generation), Some(9));
+        assert_eq!(reopened.get(3).unwrap(), None);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(SlotStore::new(Some(path.clone())).get(1).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn in_memory() {
+        let mut store = SlotStore::new(None);
+        store.save(1, snapshot(3)).unwrap();
+        assert_eq!(store.get(1).unwrap(), Some(&snapshot(3)));
+        store.flush().unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+//! Persistent log of explored rules.
+//!
+//! The database is a JSON object mapping canonical rulestrings to a list of runs.
+//! Every write happens under an exclusive file lock so parallel instances can share it.
+
+use serde::{Deserialize, Serialize};
+
+use super::cells::{CellOpts, COUNTS};
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Oldest runs of a rule are dropped past this many
+const MAX_RUNS: usize = 100;
+
+// Outcome {{{
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// no cells left
+    Died,
+    /// last step changed nothing
+    Stabilized,
+    /// population passed half the grid
+    Exploded,
+    /// none of the above when the run ended
+    Active,
+}
+// Outcome }}}
+
+// Guard {{{
+/// A `run` guard that cut a run short, see `headless::Guards`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Guard {
+    /// population reached `--stop-on-density`
+    Density,
+    /// the cells repeated with a period over 1, under `--stop-on-stable`
+    Period,
+    /// `--time-limit` ran out
+    TimeLimit,
+}
+
+impl Guard {
+    /// What `run` exits with, so scripts can sort runs without reading the summary
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Density => 3,
+            Self::Period => 4,
+            Self::TimeLimit => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Density => "reached --stop-on-density",
+            Self::Period => "started repeating",
+            Self::TimeLimit => "reached --time-limit",
+        })
+    }
+}
+// Guard }}}
+
+// RunRecord {{{
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub generations: u64,
+    pub max_population: usize,
+    pub outcome: Outcome,
+    pub seed: Option<u64>,
+    /// cells with each count of live neighbors at the end, missing from older records.
+    /// As long as `COUNTS` was in the build that wrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub neighbors: Option<Vec<usize>>,
+    /// the guard that ended the run, if one did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<Guard>,
+}
+// RunRecord }}}
+
+// RunSummary {{{
+/// What a run looked like when it ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunSummary {
+    /// the rule at the end, which a random reroll may have changed
+    pub rule: CellOpts,
+    pub generations: u64,
+    pub max_population: usize,
+    pub population: usize,
+    pub area: usize,
+    pub stable: bool,
+    pub neighbors: [usize; COUNTS],
+    pub guard: Option<Guard>,
+}
+
+impl RunSummary {
+    pub fn outcome(&self) -> Outcome {
+        if self.population == 0 {
+            Outcome::Died
+        } else if self.stable {
+            Outcome::Stabilized
+        } else if self.max_population * 2 > self.area {
+            Outcome::Exploded
+        } else {
+            Outcome::Active
+        }
+    }
+
+    pub fn record(&self, seed: Option<u64>) -> RunRecord {
+        RunRecord {
+            generations: self.generations,
+            max_population: self.max_population,
+            outcome: self.outcome(),
+            seed,
+            neighbors: Some(self.neighbors.to_vec()),
+            guard: self.guard,
+        }
+    }
+}
+// RunSummary }}}
+
+// db {{{
+pub type StatsDb = BTreeMap<String, Vec<RunRecord>>;
+
+/// `$XDG_DATA_HOME/terminal_illness` or `~/.local/share/terminal_illness`
+pub fn data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+        .map(|p| p.join("terminal_illness"))
+}
+
+/// `stats.json` in `data_dir`
+pub fn default_path() -> Option<PathBuf> {
+    data_dir().map(|d| d.join("stats.json"))
+}
+
+/// Expands a leading `~/` since the path may not have gone through a shell
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn parse(file: &mut File) -> io::Result<StatsDb> {
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    if text.trim().is_empty() {
+        Ok(StatsDb::new())
+    } else {
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Reads the whole database. A missing file is an empty database
+pub fn load(path: &Path) -> io::Result<StatsDb> {
+    let mut file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(StatsDb::new()),
+        Err(e) => return Err(e),
+    };
+    file.lock_shared()?;
+    parse(&mut file)
+}
+
+/// Adds a run under `rule`, trimming that rule's history to the newest `MAX_RUNS`
+pub fn append(path: &Path, rule: &str, record: RunRecord) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.lock()?;
+
+    let mut db = parse(&mut file)?;
+    let runs = db.entry(rule.to_string()).or_default();
+    runs.push(record);
+    if runs.len() > MAX_RUNS {
+        runs.drain(..runs.len() - MAX_RUNS);
+    }
+
+    let text = serde_json::to_string_pretty(&db)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(text.as_bytes())?;
+    file.flush()
+}
+// db }}}
+
+// table {{{
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortColumn {
+    Rule,
+    Runs,
+    Generations,
+    Population,
+    Died,
+    Stabilized,
+    Exploded,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Row {
+    rule: String,
+    runs: usize,
+    generations: u64,
+    population: usize,
+    died: usize,
+    stabilized: usize,
+    exploded: usize,
+}
+
+fn rows(db: &StatsDb) -> Vec<Row> {
+    db.iter()
+        .map(|(rule, runs)| {
+            let count = |o| runs.iter().filter(|r| r.outcome == o).count();
+            Row {
+                rule: rule.clone(),
+                runs: runs.len(),
+                generations: runs.iter().map(|r| r.generations).max().unwrap_or(0),
+                population: runs.iter().map(|r| r.max_population).max().unwrap_or(0),
+                died: count(Outcome::Died),
+                stabilized: count(Outcome::Stabilized),
+                exploded: count(Outcome::Exploded),
+            }
+        })
+        .collect()
+}
+
+/// Per-rule summary, numeric columns sorted descending
+pub fn table(db: &StatsDb, sort: SortColumn) -> String {
+    let mut rows = rows(db);
+    rows.sort_by(|a, b| match sort {
+        SortColumn::Rule => a.rule.cmp(&b.rule),
+        SortColumn::Runs => b.runs.cmp(&a.runs),
+        SortColumn::Generations => b.generations.cmp(&a.generations),
+        SortColumn::Population => b.population.cmp(&a.population),
+        SortColumn::Died => b.died.cmp(&a.died),
+        SortColumn::Stabilized => b.stabilized.cmp(&a.stabilized),
+        SortColumn::Exploded => b.exploded.cmp(&a.exploded),
+    });
+
+    let width = rows.iter().map(|r| r.rule.len()).max().unwrap_or(0).max(4);
+    let mut result = format!(
+        "{:width$}  {:>5}  {:>11}  {:>10}  {:>5}  {:>10}  {:>8}\n",
+        "RULE", "RUNS", "GENERATIONS", "POPULATION", "DIED", "STABILIZED", "EXPLODED"
+    );
+    for r in rows {
+        result += &format!(
+            "{:width$}  {:>5}  {:>11}  {:>10}  {:>5}  {:>10}  {:>8}\n",
+            r.rule, r.runs, r.generations, r.population, r.died, r.stabilized, r.exploded
+        );
+    }
+    result
+}
+// table }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(generations: u64, outcome: Outcome) -> RunRecord {
+        RunRecord {
+            generations,
+            max_population: 10,
+            outcome,
+            seed: None,
+            neighbors: None,
+            guard: None,
+        }
+    }
+
+    #[test]
+    fn outcome() {
+        let summary = RunSummary {
+            rule: CellOpts::default(),
+            generations: 5,
+            max_population: 10,
+            population: 4,
+            area: 100,
+            stable: false,
+            neighbors: [0; COUNTS],
+            guard: None,
+        };
+        assert_eq!(summary.outcome(), Outcome::Active);
+        assert_eq!(
+            RunSummary {
+                stable: true,
+                ..summary
+            }
+            .outcome(),
+            Outcome::Stabilized
+        );
+        assert_eq!(
+            RunSummary {
+                population: 0,
+                ..summary
+            }
+            .outcome(),
+            Outcome::Died
+        );
+        assert_eq!(
+            RunSummary {
+                max_population: 51,
+                ..summary
+            }
+            .outcome(),
+            Outcome::Exploded
+        );
+    }
+
+    #[test]
+    fn append_and_compact() {
+        let path = std::env::temp_dir().join(format!("ti_stats_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        for n in 0..MAX_RUNS as u64 + 5 {
+            append(&path, "B3/S23", record(n, Outcome::Active)).unwrap();
+        }
+        append(&path, "B36/S23", record(7, Outcome::Died)).unwrap();
+
+        let db = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(db["B3/S23"].len(), MAX_RUNS);
+        assert_eq!(db["B3/S23"][0].generations, 5);
+        assert_eq!(db["B36/S23"], vec![record(7, Outcome::Died)]);
+    }
+
+    #[test]
+    fn neighbors_optional() {
+        let old: RunRecord = serde_json::from_str(
+            r#"{"generations": 3, "max_population": 10, "outcome": "died", "seed": null}"#,
+        )
+        .unwrap();
+        assert_eq!(old, record(3, Outcome::Died));
+
+        let new = RunRecord {
+            neighbors: Some(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            guard: Some(Guard::TimeLimit),
+            ..old
+        };
+        let text = serde_json::to_string(&new).unwrap();
+        assert!(text.contains(r#""guard":"time-limit""#), "{}", text);
+        assert_eq!(serde_json::from_str::<RunRecord>(&text).unwrap(), new);
+        let text = serde_json::to_string(&old).unwrap();
+        assert!(!text.contains("neighbors") && !text.contains("guard"));
+    }
+
+    #[test]
+    fn sorted_table() {
+        let mut db = StatsDb::new();
+        db.insert("B3/S23".into(), vec![record(50, Outcome::Active)]);
+        db.insert(
+            "B1/S1".into(),
+            vec![record(3, Outcome::Exploded), record(4, Outcome::Exploded)],
+        );
+
+        let table = table(&db, SortColumn::Generations);
+        let rules: Vec<&str> = table
+            .lines()
+            .skip(1)
+            .map(|l| l.split(' ').next().unwrap())
+            .collect();
+        assert_eq!(rules, ["B3/S23", "B1/S1"]);
+
+        let table = super::table(&db, SortColumn::Runs);
+        assert!(table.lines().nth(1).unwrap().starts_with("B1/S1"));
+    }
+}
@@ -0,0 +1,227 @@
+//! `--title-format` and `--status-format`, text with `{key}` placeholders filled in from the
+//! running world. `{{` and `}}` are literal braces.
+//!
+//! ```text
+//! --title-format 'life {rule} gen {gen} ({state})'
+//! ```
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Gen,
+    Pop,
+    Rule,
+    State,
+    Speed,
+    Seed,
+    Brush,
+    Frameskip,
+    Births,
+    Deaths,
+    Survivors,
+    Steptime,
+    Name,
+    Author,
+}
+
+const KEYS: [(&str, Key); 14] = [
+    ("gen", Key::Gen),
+    ("pop", Key::Pop),
+    ("rule", Key::Rule),
+    ("state", Key::State),
+    ("speed", Key::Speed),
+    ("seed", Key::Seed),
+    ("brush", Key::Brush),
+    ("frameskip", Key::Frameskip),
+    ("births", Key::Births),
+    ("deaths", Key::Deaths),
+    ("survivors", Key::Survivors),
+    ("steptime", Key::Steptime),
+    ("name", Key::Name),
+    ("author", Key::Author),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Key(Key),
+}
+
+/// A parsed format string, so a bad placeholder fails at startup instead of while drawing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template(Vec<Piece>);
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => text.push('}'),
+                '{' => {
+                    let rest = &s[i + 1..];
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("unclosed {{ at byte {}", i))?;
+                    let name = &rest[..end];
+                    let key = KEYS
+                        .iter()
+                        .find(|(k, _)| *k == name)
+                        .map(|(_, key)| *key)
+                        .ok_or_else(|| {
+                            let known: Vec<String> =
+                                KEYS.iter().map(|(k, _)| format!("{{{}}}", k)).collect();
+                            format!(
+                                "unknown placeholder {{{}}}, expected one of {}",
+                                name,
+                                known.join(" ")
+                            )
+                        })?;
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)))
+                    }
+                    pieces.push(Piece::Key(key));
+                    while chars.next_if(|(j, _)| *j <= i + 1 + end).is_some() {}
+                }
+                '}' => return Err(format!("lone }} at byte {}, write }}}} for a brace", i)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text))
+        }
+        Ok(Self(pieces))
+    }
+}
+
+/// What the placeholders stand for
+#[derive(Clone, Debug, PartialEq)]
+pub struct Values {
+    pub gen: u64,
+    pub pop: usize,
+    pub rule: String,
+    /// running, paused, or extinct
+    pub state: &'static str,
+    /// generations per second, or `max` with no wait between them
+    pub speed: String,
+    /// `-` when the randomness came from the OS
+    pub seed: Option<u64>,
+    /// set, or add with the additive brush
+    pub brush: &'static str,
+    /// frames left out to let the cells catch up
+    pub frameskip: u32,
+    /// per step, averaged over the last few
+    pub births: String,
+    pub deaths: String,
+    pub survivors: String,
+    /// milliseconds the cells took to advance, averaged like `births`
+    pub steptime: String,
+    /// the loaded pattern's `#N` and `#O`, empty without them
+    pub name: String,
+    pub author: String,
+}
+
+pub fn render(template: &Template, values: &Values) -> String {
+    let mut result = String::new();
+    for piece in &template.0 {
+        // writing to a String can't fail
+        let _ = match piece {
+            Piece::Text(text) => result.write_str(text),
+            Piece::Key(Key::Gen) => write!(result, "{}", values.gen),
+            Piece::Key(Key::Pop) => write!(result, "{}", values.pop),
+            Piece::Key(Key::Rule) => result.write_str(&values.rule),
+            Piece::Key(Key::State) => result.write_str(values.state),
+            Piece::Key(Key::Speed) => result.write_str(&values.speed),
+            Piece::Key(Key::Brush) => result.write_str(values.brush),
+            Piece::Key(Key::Frameskip) => write!(result, "{}", values.frameskip),
+            Piece::Key(Key::Births) => result.write_str(&values.births),
+            Piece::Key(Key::Deaths) => result.write_str(&values.deaths),
+            Piece::Key(Key::Survivors) => result.write_str(&values.survivors),
+            Piece::Key(Key::Steptime) => result.write_str(&values.steptime),
+            Piece::Key(Key::Name) => result.write_str(&values.name),
+            Piece::Key(Key::Author) => result.write_str(&values.author),
+            Piece::Key(Key::Seed) => match values.seed {
+                Some(seed) => write!(result, "{}", seed),
+                None => result.write_str("-"),
+            },
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Values {
+        Values {
+            gen: 120,
+            pop: 45,
+            rule: "B3/S23".to_string(),
+            state: "paused",
+            speed: "10.0".to_string(),
+            seed: None,
+            brush: "set",
+            frameskip: 2,
+            births: "1.5".to_string(),
+            deaths: "2.0".to_string(),
+            survivors: "40.5".to_string(),
+            steptime: "0.125".to_string(),
+            name: "Glider".to_string(),
+            author: String::new(),
+        }
+    }
+
+    fn rendered(s: &str) -> String {
+        render(&s.parse().unwrap(), &values())
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            rendered("{rule} gen {gen}, {pop} alive ({state} at {speed}/s) seed {seed}"),
+            "B3/S23 gen 120, 45 alive (paused at 10.0/s) seed -"
+        );
+        assert_eq!(rendered(""), "");
+        assert_eq!(rendered("{gen}{gen}"), "120120");
+        assert_eq!(rendered("é{pop}ü"), "é45ü");
+        let seeded = Values {
+            seed: Some(7),
+            ..values()
+        };
+        assert_eq!(render(&"{seed}".parse().unwrap(), &seeded), "7");
+        assert_eq!(rendered("{brush} brush"), "set brush");
+        assert_eq!(rendered("{frameskip} skipped"), "2 skipped");
+        assert_eq!(
+            rendered("+{births} -{deaths} ={survivors} in {steptime}ms"),
+            "+1.5 -2.0 =40.5 in 0.125ms"
+        );
+        assert_eq!(rendered("{name} by {author}"), "Glider by ");
+    }
+
+    #[test]
+    fn braces() {
+        assert_eq!(rendered("{{gen}}"), "{gen}");
+        assert_eq!(rendered("{{{gen}}}"), "{120}");
+        assert_eq!(rendered("}}{{"), "}{");
+
+        assert!("{gen".parse::<Template>().unwrap_err().contains("unclosed"));
+        assert!("gen}".parse::<Template>().unwrap_err().contains("lone }"));
+        assert!("{}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let err = "{generation}".parse::<Template>().unwrap_err();
+        assert!(err.contains("{generation}"), "{}", err);
+        assert!(err.contains("{gen} {pop}"), "{}", err);
+        assert!("{ gen }".parse::<Template>().is_err());
+        assert!("{GEN}".parse::<Template>().is_err());
+    }
+}
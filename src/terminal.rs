@@ -0,0 +1,221 @@
+//! Terminal modes the TUI turns on, and turning exactly those back off.
+//!
+//! Everything goes through a `TerminalGuard`, which only remembers a mode once enabling it
+//! worked. Restoring, on purpose or when the guard drops during a panic, disables those in
+//! reverse order and skips the rest, so a setup that failed halfway still leaves the
+//! terminal as it found it.
+
+use crossterm::{cursor, event, execute, terminal};
+
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Raw,
+    AltScreen,
+    Mouse,
+    NoLineWrap,
+    HiddenCursor,
+    /// the title as it was, pushed on xterm's title stack and popped back.
+    /// Terminals without one ignore both
+    Title,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Raw => "raw mode",
+            Self::AltScreen => "the alternate screen",
+            Self::Mouse => "mouse capture",
+            Self::NoLineWrap => "line wrapping",
+            Self::HiddenCursor => "the hidden cursor",
+            Self::Title => "the title",
+        })
+    }
+}
+
+/// Whatever actually flips the modes, so tests can stand in for a terminal
+pub trait Backend {
+    fn enable(&mut self, mode: Mode) -> io::Result<()>;
+    fn disable(&mut self, mode: Mode) -> io::Result<()>;
+}
+
+/// XTWINOPS 22 and 23, saving and restoring both the icon and window titles
+const PUSH_TITLE: &[u8] = b"\x1b[22;0t";
+const POP_TITLE: &[u8] = b"\x1b[23;0t";
+
+/// The real terminal, with escape codes written to `W`
+pub struct Crossterm<W: Write>(pub W);
+
+impl<W: Write> Backend for Crossterm<W> {
+    fn enable(&mut self, mode: Mode) -> io::Result<()> {
+        match mode {
+            Mode::Raw => terminal::enable_raw_mode(),
+            Mode::AltScreen => execute!(self.0, terminal::EnterAlternateScreen),
+            Mode::Mouse => execute!(self.0, event::EnableMouseCapture),
+            Mode::NoLineWrap => execute!(self.0, terminal::DisableLineWrap),
+            Mode::HiddenCursor => execute!(self.0, cursor::Hide),
+            Mode::Title => self.0.write_all(PUSH_TITLE).and_then(|_| self.0.flush()),
+        }
+    }
+
+    fn disable(&mut self, mode: Mode) -> io::Result<()> {
+        match mode {
+            Mode::Raw => terminal::disable_raw_mode(),
+            // the main screen's cursor may be mid line
+            Mode::AltScreen => execute!(
+                self.0,
+                terminal::LeaveAlternateScreen,
+                cursor::MoveToColumn(0)
+            ),
+            Mode::Mouse => execute!(self.0, event::DisableMouseCapture),
+            Mode::NoLineWrap => execute!(self.0, terminal::EnableLineWrap),
+            Mode::HiddenCursor => execute!(self.0, cursor::Show),
+            Mode::Title => self.0.write_all(POP_TITLE).and_then(|_| self.0.flush()),
+        }
+    }
+}
+
+// TerminalGuard {{{
+pub struct TerminalGuard<B: Backend> {
+    backend: B,
+    /// oldest first
+    enabled: Vec<Mode>,
+}
+
+impl<B: Backend> TerminalGuard<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            enabled: Vec::new(),
+        }
+    }
+
+    /// Turns `mode` on, remembering it for `restore` only if that worked
+    pub fn enable(&mut self, mode: Mode) -> io::Result<()> {
+        self.backend.enable(mode)?;
+        self.enabled.push(mode);
+        Ok(())
+    }
+
+    /// Turns off everything `enable` turned on, newest first, carrying on past failures.
+    /// Returns the failures. Nothing is left to restore afterwards
+    pub fn restore(&mut self) -> Vec<(Mode, io::Error)> {
+        let mut failed = Vec::new();
+        while let Some(mode) = self.enabled.pop() {
+            if let Err(e) = self.backend.disable(mode) {
+                failed.push((mode, e))
+            }
+        }
+        failed
+    }
+}
+
+impl<B: Backend> Drop for TerminalGuard<B> {
+    fn drop(&mut self) {
+        for (mode, e) in self.restore() {
+            eprintln!("Could not restore {}: {}", mode, e)
+        }
+    }
+}
+// TerminalGuard }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Logs calls, failing the ones it's told to
+    #[derive(Default)]
+    struct Mock {
+        calls: Vec<(&'static str, Mode)>,
+        refuse: Vec<(&'static str, Mode)>,
+    }
+
+    impl Mock {
+        fn call(&mut self, what: &'static str, mode: Mode) -> io::Result<()> {
+            self.calls.push((what, mode));
+            if self.refuse.contains(&(what, mode)) {
+                Err(io::Error::other("refused"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Backend for &mut Mock {
+        fn enable(&mut self, mode: Mode) -> io::Result<()> {
+            self.call("on", mode)
+        }
+        fn disable(&mut self, mode: Mode) -> io::Result<()> {
+            self.call("off", mode)
+        }
+    }
+
+    #[test]
+    fn reverse_order() {
+        let mut mock = Mock::default();
+        let mut guard = TerminalGuard::new(&mut mock);
+        for mode in [Mode::Raw, Mode::AltScreen, Mode::Mouse] {
+            guard.enable(mode).unwrap()
+        }
+        assert!(guard.restore().is_empty());
+        assert!(guard.restore().is_empty());
+        drop(guard);
+        assert_eq!(
+            mock.calls[3..],
+            [
+                ("off", Mode::Mouse),
+                ("off", Mode::AltScreen),
+                ("off", Mode::Raw)
+            ]
+        );
+    }
+
+    #[test]
+    fn only_what_was_enabled() {
+        let mut mock = Mock {
+            refuse: vec![("on", Mode::NoLineWrap), ("off", Mode::AltScreen)],
+            ..Default::default()
+        };
+        let mut guard = TerminalGuard::new(&mut mock);
+        guard.enable(Mode::Raw).unwrap();
+        guard.enable(Mode::AltScreen).unwrap();
+        assert!(guard.enable(Mode::NoLineWrap).is_err());
+        guard.enable(Mode::HiddenCursor).unwrap();
+        assert!(!guard.enabled.contains(&Mode::NoLineWrap));
+
+        // a failure doesn't stop the rest
+        let failed = guard.restore();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, Mode::AltScreen);
+        drop(guard);
+        assert_eq!(
+            mock.calls[4..],
+            [
+                ("off", Mode::HiddenCursor),
+                ("off", Mode::AltScreen),
+                ("off", Mode::Raw)
+            ]
+        );
+    }
+
+    #[test]
+    fn restores_on_drop() {
+        let mut mock = Mock::default();
+        {
+            let mut guard = TerminalGuard::new(&mut mock);
+            guard.enable(Mode::Raw).unwrap();
+            guard.enable(Mode::Mouse).unwrap();
+        }
+        assert_eq!(mock.calls[2..], [("off", Mode::Mouse), ("off", Mode::Raw)]);
+    }
+
+    #[test]
+    fn title_stack() {
+        let mut term = Crossterm(Vec::new());
+        term.enable(Mode::Title).unwrap();
+        term.disable(Mode::Title).unwrap();
+        assert_eq!(term.0, [PUSH_TITLE, POP_TITLE].concat());
+    }
+}
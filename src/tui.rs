@@ -2,35 +2,904 @@
 
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind},
-    queue, style, terminal,
+    event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    queue,
+    style::{self, Color},
+    terminal,
 };
 
-use std::io::{Stdout, Write};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use std::time::{Duration, Instant};
 
+use super::bell::{BellEvents, BellOn, BellPolicy};
+use super::browser::{Browser, Choice, Purpose, MAX_ENTRIES as MAX_BROWSER_ENTRIES};
+use super::cells;
+use super::cells::downsample;
+use super::cells::CellOpts;
+use super::cells::Combine;
+use super::cells::Nondeterminism;
+use super::cells::PeriodDetector;
+use super::cells::Side;
+use super::cells::StepInfo;
 pub use super::cells::{Game, Grid};
+use super::color::{self, ColorChoice, ColorDepth};
+use super::config::{self, merge, FileConfig, Live, Watcher};
+use super::demo::Demo;
+use super::formats;
+use super::patterns::{self, Pattern};
+use super::picture::{ImageOpts, Picture};
+use super::screensaver::{Action, Policy, Screensaver, Signal};
+use super::slots::{SlotStore, Snapshot};
+use super::stats::{self, RunSummary};
+use super::template::{self, Template, Values};
+use super::terminal::{Backend, Crossterm, Mode, TerminalGuard};
+use super::versus::{Phase, Versus};
 
 // imports }}}
 
 // TuiOpts {{{
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// What to do with a loaded pattern bigger than the terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Fit {
+    /// cut off whatever doesn't fit
+    Clip,
+    /// make the world big enough and pan around it
+    World,
+    /// shrink the view until the whole pattern is on screen
+    Scale,
+}
+
+/// How to handle falling more than a tick behind, as after a suspend or a slow draw
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatchUp {
+    /// run up to this many missed generations at once, then drop the rest
+    Max(u32),
+    /// drop every missed generation and carry on from now
+    Reanchor,
+}
+
+impl std::str::FromStr for CatchUp {
+    type Err = String;
+
+    /// `max:N` or `reanchor`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s == "reanchor" => Ok(Self::Reanchor),
+            Some(("max", n)) => n
+                .parse()
+                .map(Self::Max)
+                .map_err(|e| format!("bad max '{}': {}", n, e)),
+            _ => Err("expected max:N or reanchor".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TuiOpts {
     pub numeric: bool,
+    /// digits show the cell's value instead of its share of life, overriding `numeric`
+    pub numeric_abs: bool,
     pub monochrome: bool,
+    /// what colors the terminal can show, `Monochrome` when `monochrome` is set
+    pub color: ColorDepth,
+    /// what `color` was detected from, so a config reload can tell if it changed
+    pub color_choice: ColorChoice,
+    /// max time between clicks for them to count as a double/triple click
+    pub double_click: Duration,
+    /// pause when live cells touch the edge of the world
+    pub edge_warn: bool,
+    /// grow the world when cells near its edge, up to this (w, h)
+    pub auto_expand: Option<(usize, usize)>,
+    /// cells added per expansion
+    pub expand_margin: usize,
+    /// collect and print frame timings
+    pub stats: bool,
+    /// print the `DrawStats` line on exit, they're kept either way
+    pub show_perf_stats: bool,
+    /// extra patterns for the picker
+    pub patterns_dir: Option<PathBuf>,
+    pub patterns_recursive: bool,
+    /// two player rounds of this many generations
+    pub versus: Option<u64>,
+    pub fit: Fit,
+    /// run forever, stirring the grid when it settles, and quit on any input
+    pub screensaver: bool,
+    pub catch_up: CatchUp,
+    /// generations per second when auto advancing, see `tick_interval`
+    pub initial_speed: f32,
+    /// slowest and fastest the `+` and `-` keys go, in generations per second
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// queue straight into a buffered stdout instead of building each frame in `FrameBuffer`
+    pub legacy_draw: bool,
+    /// start with the strips of wrapped cells around the view, toggled with F11
+    pub seam_preview: bool,
+    /// where quick save slots persist, memory only without one
+    pub slots_file: Option<PathBuf>,
+    pub bell: BellOn,
+    /// watched for changes, with what it said at startup
+    pub config: Option<(PathBuf, FileConfig)>,
+    /// a changed rule in the config applies while running
+    pub hot_reload_rules: bool,
+    /// kept in the terminal's title, at most every `TITLE_INTERVAL`
+    pub title_format: Option<Template>,
+    /// kept on the bottom row when no message or command is there
+    pub status_format: Option<Template>,
+    /// commands run before the first frame as if typed after `:`, see `parse_startup`
+    pub startup: Vec<String>,
+    /// capture the mouse, otherwise edit with the keyboard cursor
+    pub mouse: bool,
+    /// rerolled rules may give birth with no neighbors
+    pub allow_b0: bool,
+    /// bytes a second frames are paced to, see `Link`
+    pub target_bandwidth: Option<u64>,
+    /// generations of cell value counts F5 can plot
+    pub value_history: usize,
 }
 // TuiOpts }}}
 
+// CountingWriter {{{
+/// Passes writes through while counting the bytes
+struct CountingWriter<W: Write> {
+    inner: W,
+    bytes: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, bytes: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+// CountingWriter }}}
+
+// DrawStats {{{
+/// Running totals of whole frame times, kept every frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    pub count: u64,
+    pub sum: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub last: Duration,
+    /// full repaints identical to the screen, never written
+    pub skipped: u64,
+}
+
+impl DrawStats {
+    fn record(&mut self, time: Duration) {
+        self.min = if self.count == 0 {
+            time
+        } else {
+            self.min.min(time)
+        };
+        self.max = self.max.max(time);
+        self.sum += time;
+        self.last = time;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.sum.checked_div(self.count as u32).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for DrawStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} frames, mean {:.3}ms, min {:.3}ms, max {:.3}ms, {} unchanged skipped",
+            self.count,
+            self.mean().as_secs_f64() * 1000.0,
+            self.min.as_secs_f64() * 1000.0,
+            self.max.as_secs_f64() * 1000.0,
+            self.skipped,
+        )
+    }
+}
+// DrawStats }}}
+
+// SimStats {{{
+/// Steps `SimStats` averages over
+const SIM_WINDOW: usize = 64;
+
+/// The last `SIM_WINDOW` steps, for rolling averages beside `DrawStats`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimStats {
+    steps: VecDeque<StepInfo>,
+}
+
+impl SimStats {
+    fn record(&mut self, info: StepInfo) {
+        if self.steps.len() == SIM_WINDOW {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(info)
+    }
+
+    fn mean(&self, count: impl Fn(&StepInfo) -> usize) -> f64 {
+        if self.steps.is_empty() {
+            return 0.0;
+        }
+        self.steps.iter().map(count).sum::<usize>() as f64 / self.steps.len() as f64
+    }
+
+    pub fn births(&self) -> f64 {
+        self.mean(|s| s.births)
+    }
+
+    pub fn deaths(&self) -> f64 {
+        self.mean(|s| s.deaths)
+    }
+
+    pub fn survivors(&self) -> f64 {
+        self.mean(|s| s.survivors)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        let total: Duration = self.steps.iter().map(|s| s.elapsed).sum();
+        total
+            .checked_div(self.steps.len() as u32)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for SimStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "last {} steps, mean {:.3}ms, {:.1} births, {:.1} deaths, {:.1} survivors",
+            self.steps.len(),
+            self.elapsed().as_secs_f64() * 1000.0,
+            self.births(),
+            self.deaths(),
+            self.survivors(),
+        )
+    }
+}
+// SimStats }}}
+
+// FrameBuffer {{{
+/// Past this many bytes of capacity a small frame gives the memory back
+const MAX_FRAME_BUFFER: usize = 1 << 22;
+
+/// A whole frame's bytes, handed to the terminal in one write.
+/// Kept between frames so it's only allocated once
+#[derive(Clone, Debug, Default)]
+struct FrameBuffer {
+    bytes: Vec<u8>,
+}
+
+impl FrameBuffer {
+    /// Writes out and empties the buffer, returning how many bytes went
+    fn send(&mut self, out: &mut impl Write) -> std::io::Result<usize> {
+        out.write_all(&self.bytes)?;
+        out.flush()?;
+        let sent = self.bytes.len();
+        self.bytes.clear();
+        if self.bytes.capacity() > MAX_FRAME_BUFFER && sent < MAX_FRAME_BUFFER / 4 {
+            self.bytes.shrink_to(MAX_FRAME_BUFFER / 4)
+        }
+        Ok(sent)
+    }
+}
+// FrameBuffer }}}
+
+// FrameStats {{{
+/// Per frame timings for `--stats`
+#[derive(Clone, Debug, Default)]
+struct FrameStats {
+    /// time spent queueing commands
+    build: Vec<Duration>,
+    flush: Vec<Duration>,
+    bytes: Vec<u64>,
+}
+
+/// Nearest rank percentile of sorted data
+fn percentile<T: Copy + Default>(sorted: &[T], p: f64) -> T {
+    if sorted.is_empty() {
+        T::default()
+    } else {
+        sorted[((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1]
+    }
+}
+
+impl FrameStats {
+    fn record(&mut self, build: Duration, flush: Duration, bytes: u64) {
+        self.build.push(build);
+        self.flush.push(flush);
+        self.bytes.push(bytes)
+    }
+
+    fn summary(&self) -> String {
+        fn row<T: Copy + Default + Ord>(
+            name: &str,
+            data: &[T],
+            fmt: impl Fn(T) -> String,
+        ) -> String {
+            let mut sorted = data.to_vec();
+            sorted.sort_unstable();
+            format!(
+                "{:5}  min {}  p50 {}  p90 {}  p99 {}  max {}\n",
+                name,
+                fmt(percentile(&sorted, 0.0)),
+                fmt(percentile(&sorted, 0.5)),
+                fmt(percentile(&sorted, 0.9)),
+                fmt(percentile(&sorted, 0.99)),
+                fmt(percentile(&sorted, 1.0)),
+            )
+        }
+        let ms = |d: Duration| format!("{:.3}ms", d.as_secs_f64() * 1000.0);
+
+        format!("frames {}\n", self.build.len())
+            + &row("build", &self.build, ms)
+            + &row("flush", &self.flush, ms)
+            + &row("bytes", &self.bytes, |b| b.to_string())
+    }
+}
+// FrameStats }}}
+
+// Link {{{
+/// A flush this long means the terminal's buffer was full and the frame waited on the link
+const BLOCKED_FLUSH: Duration = Duration::from_millis(15);
+/// Longest a frame may take to go out at `--target-bandwidth` before the link counts as
+/// slow, about 15 a second
+const SLOW_FRAME: Duration = Duration::from_millis(66);
+/// strained frames in a row before the link counts as slow, and easy ones before it doesn't
+const SLOW_AFTER: u32 = 3;
+const RECOVER_AFTER: u32 = 30;
+/// bottom right while the link is slow
+const SLOW_LINK: &str = "SLOW LINK";
+
+/// How fast frames leave for the terminal. Once they back up, they're paced to what the
+/// link takes and drawn monochrome, sending only rows that changed. The cells and input
+/// carry on at full speed either way
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Link {
+    /// bytes a second from `--target-bandwidth`
+    target: Option<u64>,
+    /// bytes a second blocked flushes managed, smoothed
+    measured: Option<f64>,
+    slow: bool,
+    /// frames in a row that disagreed with `slow`
+    streak: u32,
+    /// no frame goes out before this
+    hold: Option<Instant>,
+    /// hash of each screen row as last sent while slow, empty once anything else drew there
+    rows: Vec<u64>,
+}
+
+impl Link {
+    fn new(target: Option<u64>) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    /// Notes a frame of `bytes` that took `flush` to go out, done at `now`.
+    /// Returns whether that made the link slow or fast again
+    fn record(&mut self, bytes: u64, flush: Duration, now: Instant) -> bool {
+        let blocked = flush >= BLOCKED_FLUSH;
+        if blocked && bytes > 0 {
+            let sample = bytes as f64 / flush.as_secs_f64();
+            self.measured = Some(self.measured.map_or(sample, |m| m + (sample - m) / 4.0))
+        }
+        let strained = match self.target {
+            Some(target) => bytes as f64 / target as f64 > SLOW_FRAME.as_secs_f64(),
+            None => blocked,
+        };
+        self.streak = if strained != self.slow {
+            self.streak + 1
+        } else {
+            0
+        };
+        let changed = self.streak >= if self.slow { RECOVER_AFTER } else { SLOW_AFTER };
+        if changed {
+            self.slow = !self.slow;
+            self.streak = 0;
+            self.rows.clear()
+        }
+        // a target paces every frame, a measured link only once it's slow
+        let bandwidth = (self.target.map(|t| t as f64)).or(self.measured.filter(|_| self.slow));
+        self.hold = bandwidth.map(|b| now + Duration::from_secs_f64(bytes as f64 / b));
+        changed
+    }
+
+    /// Whether the frame due at `now` has to wait for the last one to drain
+    fn holding(&self, now: Instant) -> bool {
+        self.hold.is_some_and(|hold| now < hold)
+    }
+}
+// Link }}}
+
+/// how close live cells get to an edge before the world grows past it
+const EXPAND_DISTANCE: usize = 2;
+/// resizes only apply once the terminal has stopped changing size for this long
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+/// longest wait for input, so messages and countdowns update while paused
+const MAX_POLL: Duration = Duration::from_millis(100);
+/// most frames in a row `skip_frame` drops, so a slow terminal still sees the cells move
+const MAX_FRAME_SKIP: u8 = 5;
+/// how long a status message stays up
+const MESSAGE_TIME: Duration = Duration::from_secs(3);
+/// empty space around a loaded pattern that didn't fit the terminal
+const LOAD_MARGIN: usize = 16;
+/// backgrounds of selected cells and static regions
+const SELECTION_TINT: (u8, u8, u8) = (60, 70, 120);
+const STATIC_TINT: (u8, u8, u8) = (28, 32, 44);
+/// tint of every 10th row and column with `Rulers::Lines`
+const GRID_TINT: (u8, u8, u8) = (24, 24, 24);
+/// columns the row numbers take, one is a space
+const RULER_WIDTH: u16 = 5;
+/// cells of the far side shown past each edge with the seam preview
+const SEAM_WIDTH: u16 = 3;
+const SEAM_TINT: (u8, u8, u8) = (48, 24, 48);
+/// largest brush radius
+const MAX_BRUSH: usize = 16;
+/// screen cells moved per arrow key
+const PAN_STEP: usize = 4;
+/// longest repeat the screensaver notices
+const MAX_PERIOD: usize = 32;
+/// top right while the cells can't be replayed exactly
+const NONDETERMINISTIC: &str = "~nondeterministic";
+/// most time one catch-up may take before the rest of what's due is skipped
+const CATCH_UP_BUDGET: Duration = Duration::from_millis(100);
+/// how often the config file is checked for changes
+const CONFIG_POLL: Duration = Duration::from_secs(2);
+/// chance of each cell flipping on ctrl+n
+const NOISE_DENSITY: f32 = 0.01;
+/// chance of each cell starting alive in soup from `--random-rule` or a reroll
+const SOUP_DENSITY: f32 = 0.3;
+/// used when the terminal won't say how big it is
+const FALLBACK_SIZE: (u16, u16) = (80, 24);
+/// longest wait between generations, however low `--min-speed` goes
+const SLOWEST_TICK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shortest and longest time between generations, from `opts.max_speed` and `opts.min_speed`.
+/// Past 1000 generations a second the shortest rounds to no wait at all, and the longest is
+/// never more than `SLOWEST_TICK`
+fn tick_bounds(opts: &TuiOpts) -> (Duration, Duration) {
+    let interval = |speed: f32| Duration::try_from_secs_f64(1.0 / speed as f64);
+    let longest = interval(opts.min_speed)
+        .unwrap_or(SLOWEST_TICK)
+        .min(SLOWEST_TICK);
+    (
+        interval(opts.max_speed)
+            .unwrap_or(Duration::ZERO)
+            .min(longest),
+        longest,
+    )
+}
+
+/// Time between generations at `speed` generations per second, `1 / speed` seconds,
+/// within `tick_bounds`. `tick_interval(opts.initial_speed, opts)` is where a run starts
+fn tick_interval(speed: f32, opts: &TuiOpts) -> Duration {
+    let (shortest, longest) = tick_bounds(opts);
+    Duration::try_from_secs_f64(1.0 / speed as f64)
+        .unwrap_or(longest)
+        .clamp(shortest, longest)
+}
+
+/// `+` or scrolling up halves the interval, `-` or scrolling down doubles it
+fn change_speed(state: &mut State, faster: bool) {
+    let (shortest, longest) = tick_bounds(&state.opts);
+    state.tick_interval = if faster {
+        state.tick_interval / 2
+    } else {
+        state.tick_interval.saturating_mul(2)
+    }
+    .clamp(shortest, longest);
+    // polls can't wait less than a millisecond, so there's no waiting at all
+    let message = if state.tick_interval < Duration::from_millis(1) {
+        "as fast as possible".to_string()
+    } else {
+        format!(
+            "{:.1} generations/s",
+            1.0 / state.tick_interval.as_secs_f64()
+        )
+    };
+    set_message(state, message)
+}
+
+// Scheduler {{{
+/// Deadline for the next generation, so slow frames or floods of input don't slow the
+/// simulation. Fed the time from outside to keep it testable
+#[derive(Clone, Copy, Debug)]
+struct Scheduler {
+    next_tick: Instant,
+    catch_up: CatchUp,
+    /// generations the last `due` gave up on
+    dropped: u32,
+}
+
+impl Scheduler {
+    fn new(catch_up: CatchUp, now: Instant) -> Self {
+        Self {
+            next_tick: now,
+            catch_up,
+            dropped: 0,
+        }
+    }
+
+    /// Starts counting from `now`, as when auto advance is switched on
+    fn start(&mut self, now: Instant, interval: Duration) {
+        self.next_tick = Self::after(now, interval)
+    }
+
+    /// `now + interval`, or a day on for an interval too long for the clock
+    fn after(now: Instant, interval: Duration) -> Instant {
+        now.checked_add(interval)
+            .unwrap_or_else(|| now + SLOWEST_TICK)
+    }
+
+    /// How long input may be waited on before a generation is due
+    fn timeout(&self, now: Instant) -> Duration {
+        self.next_tick.saturating_duration_since(now).min(MAX_POLL)
+    }
+
+    /// Generations to run now, moving the deadline past them
+    fn due(&mut self, now: Instant, interval: Duration) -> u32 {
+        if now < self.next_tick {
+            self.dropped = 0;
+            return 0;
+        }
+        let behind = match interval.as_nanos() {
+            0 => 1,
+            i => ((now - self.next_tick).as_nanos() / i + 1).min(u32::MAX as u128) as u32,
+        };
+        let steps = match self.catch_up {
+            CatchUp::Max(max) => behind.min(max.max(1)),
+            CatchUp::Reanchor => 1,
+        };
+        self.dropped = behind - steps;
+        if steps < behind {
+            // too far behind, what's left is dropped
+            self.next_tick = Self::after(now, interval)
+        } else {
+            self.next_tick = Self::after(
+                self.next_tick,
+                interval.checked_mul(steps).unwrap_or(Duration::MAX),
+            )
+        }
+        steps
+    }
+}
+/// Starts the scheduler when auto advance turns on, and again when the speed changes so it
+/// applies from now rather than after the old deadline. Returns how long input may be waited on
+fn schedule(state: &mut State, now: Instant) -> Duration {
+    let timeout = if !state.auto_advance {
+        state.scheduled = None;
+        MAX_POLL
+    } else {
+        if state.scheduled != Some(state.tick_interval) {
+            state.scheduler.start(now, state.tick_interval);
+            state.scheduled = Some(state.tick_interval)
+        }
+        state.scheduler.timeout(now)
+    };
+    // a held frame goes out once the link has drained, a hold already past is no reason to wake
+    match state.link.hold.filter(|hold| *hold > now) {
+        Some(hold) => timeout.min(hold - now),
+        None => timeout,
+    }
+}
+
+/// Something in `step` past `tally` that has to see every generation, not just the last of
+/// a catch-up
+fn watches_generations(state: &State) -> bool {
+    state.demo.is_some()
+        || state.versus.is_some()
+        || state.screensaver.is_some()
+        || state.bell.is_some()
+        || state.birth_trail.is_some()
+        || state.value_history.keeps(&state.game)
+        || state.opts.edge_warn
+        || state.opts.auto_expand.is_some()
+}
+
+/// Runs every generation due by `now`. After a suspend or a breakpoint that's several at
+/// once, up to `--catch-up` and `CATCH_UP_BUDGET`. Returns how many ran
+fn catch_up(state: &mut State, now: Instant) -> u32 {
+    if !state.auto_advance || state.scheduled.is_none() {
+        return 0;
+    }
+    let due = state.scheduler.due(now, state.tick_interval);
+    let mut dropped = state.scheduler.dropped;
+    let ran = if due > 1 && !watches_generations(state) {
+        // only the counts look at the ones between, so they skip the rest of `step`
+        let start = Instant::now();
+        let mut ran = 0;
+        while ran + 1 < due && start.elapsed() < CATCH_UP_BUDGET {
+            let info = state.game.advance();
+            tally(state, info);
+            ran += 1
+        }
+        step(state);
+        if ran + 1 < due {
+            dropped += due - ran - 1;
+            state.scheduler.start(now, state.tick_interval)
+        }
+        ran + 1
+    } else {
+        let start = Instant::now();
+        let mut ran = 0;
+        while ran < due && state.auto_advance {
+            step(state);
+            ran += 1;
+            if start.elapsed() >= CATCH_UP_BUDGET && ran < due {
+                dropped += due - ran;
+                state.scheduler.start(now, state.tick_interval);
+                break;
+            }
+        }
+        ran
+    };
+    if dropped > 0 {
+        let message = format!("ran {} generations to catch up, skipped {}", ran, dropped);
+        set_message(state, message)
+    }
+    ran
+}
+// Scheduler }}}
+
+// ClickTracker {{{
+/// cell, time of the last click, and clicks so far
+type Click = ((u16, u16), Instant, u8);
+
+/// Counts repeated Down events of the same button on the same cell.
+/// Never withholds a click, it only reports how many came in a row.
+#[derive(Clone, Debug, Default)]
+struct ClickTracker {
+    window: Duration,
+    /// left, right, middle
+    buttons: [Option<Click>; 3],
+}
+
+impl ClickTracker {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            buttons: [None; 3],
+        }
+    }
+
+    /// Registers a click and returns its place in the streak, cycling 1, 2, 3, 1...
+    fn click(&mut self, button: MouseButton, x: u16, y: u16, now: Instant) -> u8 {
+        let slot = &mut self.buttons[match button {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+        }];
+
+        let count = match slot {
+            Some((xy, time, count))
+                if *xy == (x, y)
+                    && *count < 3
+                    && now.saturating_duration_since(*time) <= self.window =>
+            {
+                *count + 1
+            }
+            _ => 1,
+        };
+
+        *slot = Some(((x, y), now, count));
+        count
+    }
+}
+// ClickTracker }}}
+
 // State {{{
 #[derive(Clone)]
 struct State {
     opts: TuiOpts,
     game: Game,
     xy: (u16, u16),
+    /// newest size from a resize event and when it came in
+    pending_resize: Option<((u16, u16), Instant)>,
+    /// world position of the top left of the screen
+    origin: (usize, usize),
+    /// world cells per screen cell along each axis
+    zoom: usize,
+    /// the world doesn't shrink past this when the terminal does
+    min_world: (usize, usize),
+    /// shown on the bottom row until it expires
+    message: Option<(String, Instant)>,
+    clicks: ClickTracker,
+    /// last used pattern, stamped by triple click
+    stamp: Option<Grid>,
+    /// cells painted around the cursor, 0 for just the one under it
+    brush: usize,
+    /// life given to painted cells, capped at the rule's
+    paint: u8,
+    erase_mode: EraseMode,
+    /// `b`, dragging adds and takes 1 life instead of painting and erasing
+    additive: bool,
+    /// the cell an additive stroke last changed, so resting on it doesn't keep adding
+    stroke: Option<(usize, usize)>,
+    /// `v` was pressed, so the mouse selects instead of painting
+    selecting: bool,
+    /// world corners of the selection, for `F`
+    selection: Option<((usize, usize), (usize, usize))>,
+    rulers: Rulers,
+    /// `V` put a second view beside the first
+    split: Option<Split>,
+    /// `SEAM_WIDTH` strips around the cells showing what's past each edge, wrapped
+    seam_preview: bool,
+    /// F9 colors newborns by where their parents were
+    birth_trail: Option<BirthTrail>,
+    /// how many cells had each value, for F5's plot
+    value_history: ValueHistory,
+    value_plot: bool,
+    /// F1 to F4, saved with ctrl
+    slots: SlotStore,
+    /// patterns listed in the picker
+    library: Vec<Pattern>,
+    picker: Option<Picker>,
+    /// `:load` or `:save` without a path
+    browser: Option<Browser>,
+    /// text typed after `:`
+    command: Option<String>,
+    /// neighbor counts from `:hist` and the generation they were taken at
+    histogram: Option<([usize; cells::COUNTS], u64)>,
+    /// screen cell the mouse was last seen over, where `I` inspects
+    pointer: Option<(u16, u16)>,
+    /// world cell shown by the inspector and the screen cell it was opened from
+    inspect: Option<((usize, usize), (u16, u16))>,
+    /// the terminal reports the mouse this session
+    mouse: bool,
+    /// screen cell the keyboard edits at, when there's no mouse
+    cursor: Option<(u16, u16)>,
+    generations: u64,
+    max_population: usize,
+    tick_interval: Duration,
+    scheduler: Scheduler,
+    /// the interval the scheduler is running at, `None` while paused
+    scheduled: Option<Duration>,
+    demo: Option<Demo>,
+    /// the rule from before the demo, put back when it's left
+    pre_demo: Option<CellOpts>,
+    versus: Option<Versus>,
+    screensaver: Option<(Screensaver, PeriodDetector)>,
+    /// `None` with `--bell off`
+    bell: Option<(BellPolicy, PeriodDetector)>,
+    /// a bell waiting to go out with the next frame
+    ring: bool,
+    /// the config file, what it said last, and when it was last looked at
+    config: Option<(Watcher, FileConfig, Instant)>,
+    /// overlays on screen as of the last frame
+    shown: Vec<Overlay>,
+    /// hash of the last full repaint's bytes while nothing else has drawn over it
+    painted: Option<u64>,
+    /// the last `--title-format` title sent and when
+    title: Option<(String, Instant)>,
+    draw_stats: DrawStats,
+    sim_stats: SimStats,
+    link: Link,
+    /// when `frame` last ran while running, `None` while paused
+    frame_at: Option<Instant>,
+    /// frames `skip_frame` dropped, in total and in a row
+    frame_skip_count: u32,
+    frame_skips: u8,
+    /// an event is waiting to be drawn, which `skip_frame` never holds back
+    input_frame: bool,
     update: bool,
-    advance: bool,
+    auto_advance: bool,
     quit: bool,
 }
+
+impl State {
+    fn new(game: Game, opts: TuiOpts, xy: (u16, u16), demo: Option<Demo>) -> Self {
+        let link = Link::new(opts.target_bandwidth);
+        let pre_demo = demo.is_some().then(|| *game.opts());
+        let mut result = Self {
+            game,
+            clicks: ClickTracker::new(opts.double_click),
+            versus: opts.versus.map(|g| Versus::new(g, Instant::now())),
+            screensaver: opts.screensaver.then(|| {
+                (
+                    Screensaver::new(Policy::default(), rand::random()),
+                    PeriodDetector::new(MAX_PERIOD),
+                )
+            }),
+            bell: (opts.bell != BellOn::Off)
+                .then(|| (BellPolicy::new(opts.bell), PeriodDetector::new(MAX_PERIOD))),
+            ring: false,
+            config: opts
+                .config
+                .clone()
+                .map(|(path, file)| (Watcher::new(path), file, Instant::now())),
+            auto_advance: opts.screensaver,
+            tick_interval: tick_interval(opts.initial_speed, &opts),
+            scheduler: Scheduler::new(opts.catch_up, Instant::now()),
+            scheduled: None,
+            slots: SlotStore::new(opts.slots_file.clone()),
+            seam_preview: opts.seam_preview,
+            birth_trail: None,
+            value_history: ValueHistory::new(opts.value_history),
+            value_plot: false,
+            opts,
+            xy,
+            pending_resize: None,
+            origin: (0, 0),
+            zoom: 1,
+            min_world: (0, 0),
+            message: None,
+            stamp: None,
+            brush: 0,
+            paint: u8::MAX,
+            erase_mode: EraseMode::Hard,
+            additive: false,
+            stroke: None,
+            selecting: false,
+            selection: None,
+            rulers: Rulers::Off,
+            split: None,
+            library: Vec::new(),
+            picker: None,
+            browser: None,
+            command: None,
+            histogram: None,
+            pointer: None,
+            inspect: None,
+            mouse: true,
+            cursor: None,
+            generations: 0,
+            max_population: 0,
+            pre_demo,
+            demo,
+            shown: Vec::new(),
+            painted: None,
+            title: None,
+            draw_stats: DrawStats::default(),
+            sim_stats: SimStats::default(),
+            link,
+            frame_at: None,
+            frame_skip_count: 0,
+            frame_skips: 0,
+            input_frame: false,
+            update: false,
+            quit: false,
+        };
+
+        let (w, h) = view_size(&result);
+        result.game.resize(w, h);
+        if let Err(e) = rescan(&mut result) {
+            set_message(&mut result, e)
+        }
+        start_scene(&mut result);
+        result.max_population = result.game.population();
+        result
+    }
+
+    pub fn draw_stats(&self) -> &DrawStats {
+        &self.draw_stats
+    }
+
+    pub fn sim_stats(&self) -> &SimStats {
+        &self.sim_stats
+    }
+}
 // State }}}
 
 // try_read {{{
@@ -39,8 +908,7 @@ fn try_read(secs: f32) -> Option<Event> {
     if secs.is_sign_positive() {
         event::poll(Duration::from_secs_f32(secs))
             .ok()
-            .map(|b| b.then(|| event::read().ok()))
-            .flatten()
+            .and_then(|b| b.then(|| event::read().ok()))
             .flatten()
     } else {
         event::read().ok()
@@ -48,155 +916,4794 @@ fn try_read(secs: f32) -> Option<Event> {
 }
 // try_read }}}
 
-// draw {{{
-fn draw(stdout: &mut Stdout, state: &State) {
-    queue!(stdout, cursor::SavePosition, cursor::MoveTo(0, 0)).expect("Cursor move fail");
+// set_message {{{
+fn set_message(state: &mut State, message: String) {
+    // nothing but cells in a screensaver
+    if state.opts.screensaver {
+        return;
+    }
+    state.message = Some((message, Instant::now()));
+    state.update = true
+}
+// set_message }}}
 
-    let grid = state.game.grid();
+// viewport {{{
+/// What F6 shows around the cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Rulers {
+    #[default]
+    Off,
+    /// world coordinates along the top and left
+    Numbers,
+    /// and a tint every 10 cells
+    Lines,
+}
 
-    for (n, row) in grid.iter().enumerate() {
-        for cell in row {
-            queue!(
-                stdout,
-                style::Print(if *cell == 0 {
-                    ' '.to_string()
-                } else if state.opts.numeric {
-                    if state.game.opts().life > 9 {
-                        (((*cell as f32 / state.game.opts().life as f32) * 9.0).round() as u8)
-                            .min(9)
-                            .to_string()
-                    } else {
-                        cell.to_string()
-                    }
-                } else {
-                    '█'.to_string()
-                })
-            )
-            .expect("print cell fail")
+/// `V`, two views of one world side by side. The focused half pans with `state.origin`,
+/// the other keeps its place here
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Split {
+    other: (usize, usize),
+    /// the right half has focus
+    right: bool,
+}
+
+/// The left and right halves of `area`, either side of a divider column
+fn halves(area: Rect) -> (Rect, Rect) {
+    let left = area.w.saturating_sub(1) / 2;
+    (
+        Rect { w: left, ..area },
+        Rect {
+            x: area.x + left + 1,
+            w: area.w.saturating_sub(left + 1),
+            ..area
+        },
+    )
+}
+
+/// The cells `state.origin` is the top left of, the focused half when split
+fn focused_area(state: &State) -> Rect {
+    let area = cell_area(state);
+    match state.split {
+        Some(split) if split.right => halves(area).1,
+        Some(_) => halves(area).0,
+        None => area,
+    }
+}
+
+/// The world cells screen `rect` shows with `origin` at its top left, which may run past
+/// the world's edges
+fn world_span(origin: (usize, usize), rect: Rect, zoom: usize) -> cells::Rect {
+    cells::Rect {
+        x: origin.0,
+        y: origin.1,
+        w: rect.w as usize * zoom,
+        h: rect.h as usize * zoom,
+    }
+}
+
+/// Splits the view with both halves centered where it was, or goes back to one view
+/// centered where the focused half was
+fn toggle_split(state: &mut State) {
+    let area = cell_area(state);
+    let zoom = state.zoom;
+    let center = |origin: (usize, usize), rect: Rect| {
+        let span = world_span(origin, rect, zoom);
+        (span.x + span.w / 2, span.y + span.h / 2)
+    };
+    let around = |center: (usize, usize), rect: Rect| {
+        (
+            center.0.saturating_sub(rect.w as usize * zoom / 2),
+            center.1.saturating_sub(rect.h as usize * zoom / 2),
+        )
+    };
+    match state.split {
+        None => {
+            let middle = center(state.origin, area);
+            let (left, right) = halves(area);
+            state.origin = around(middle, left);
+            state.split = Some(Split {
+                other: around(middle, right),
+                right: false,
+            });
+            set_message(state, "split, shift+tab pans the other half".to_string())
+        }
+        Some(_) => {
+            let middle = center(state.origin, focused_area(state));
+            state.split = None;
+            state.origin = around(middle, area)
         }
-        if n != grid.len() {
-            queue!(stdout, cursor::MoveTo(0, 1 + n as u16)).expect("Cursor move fail");
-        };
     }
+    // seam strips go away while split
+    apply_resize(state, state.xy.0, state.xy.1)
+}
 
-    queue!(stdout, cursor::RestorePosition).expect("Cursor move fail");
+/// Shift+tab while split
+fn switch_half(state: &mut State) {
+    if let Some(split) = &mut state.split {
+        std::mem::swap(&mut state.origin, &mut split.other);
+        split.right = !split.right
+    }
+}
 
-    stdout.flush().expect("Terminal flush fail");
+/// Screen space for cells, everything but the rulers and seam strips.
+/// The strips take their space from the view rather than covering its edge cells
+fn cell_area(state: &State) -> Rect {
+    let (x, y) = match state.rulers {
+        Rulers::Off => (0, 0),
+        Rulers::Numbers | Rulers::Lines => (RULER_WIDTH, 1),
+    };
+    let seam = seam_width(state);
+    Rect {
+        x: (x + seam).min(state.xy.0),
+        y: (y + seam).min(state.xy.1),
+        w: state.xy.0.saturating_sub(x + 2 * seam),
+        h: state.xy.1.saturating_sub(y + 2 * seam),
+    }
 }
-// draw }}}
 
-// process_event {{{
-fn process_event(state: &mut State, event: Event) {
-    match event {
-        Event::Key(kevt) => match kevt.code {
-            KeyCode::Char('n') => {
-                state.game.advance();
-                state.update = true
-            }
-            KeyCode::Char('a') => {
-                state.advance = !state.advance;
-            }
-            KeyCode::Esc => state.quit = true,
-            KeyCode::Char('c') if kevt.modifiers.contains(KeyModifiers::CONTROL) => {
-                state.quit = true
-            }
-            _ => (),
-        },
-        Event::Mouse(mevt) => match mevt.kind {
-            MouseEventKind::Drag(MouseButton::Left) | MouseEventKind::Down(MouseButton::Left) => {
-                let val = state.game.opts().life;
-                if state.game.get_cell(mevt.column.into(), mevt.row.into()) != Some(val) {
-                    state
-                        .game
-                        .set_cell(mevt.column.into(), mevt.row.into(), val);
-                    state.update = true;
-                }
-            }
-            MouseEventKind::Drag(MouseButton::Right) | MouseEventKind::Down(MouseButton::Right) => {
-                if state.game.get_cell(mevt.column.into(), mevt.row.into()) != Some(0) {
-                    state.game.set_cell(mevt.column.into(), mevt.row.into(), 0);
-                    state.update = true;
-                }
+/// No strips while split, they'd wrap around the wrong half
+fn seam_width(state: &State) -> u16 {
+    if state.seam_preview && state.split.is_none() {
+        SEAM_WIDTH
+    } else {
+        0
+    }
+}
+
+/// Whether a screen position is on a seam strip rather than the view
+fn on_seam(state: &State, column: u16, row: u16) -> bool {
+    let area = cell_area(state);
+    seam_width(state) > 0
+        && (column < area.x || row < area.y || column >= area.x + area.w || row >= area.y + area.h)
+}
+
+/// World cell under a screen position, `None` on the rulers and the split's divider.
+/// The seam strips wrap around to the cells past the view's edges
+fn world_at(state: &State, column: u16, row: u16) -> Option<(usize, usize)> {
+    let area = cell_area(state);
+    if seam_width(state) > 0 {
+        let seam = SEAM_WIDTH;
+        let (w, h) = state.game.size();
+        let inside = |s: u16, start: u16, len: u16| s + seam >= start && s < start + len + seam;
+        if w == 0 || h == 0 || !inside(column, area.x, area.w) || !inside(row, area.y, area.h) {
+            return None;
+        }
+        let wrap = |s: u16, start: u16, origin: usize, size: usize| {
+            let offset = (s as isize - start as isize) * state.zoom as isize;
+            (origin as isize + offset).rem_euclid(size as isize) as usize
+        };
+        return Some((
+            wrap(column, area.x, state.origin.0, w),
+            wrap(row, area.y, state.origin.1, h),
+        ));
+    }
+    let (rect, origin) = match state.split {
+        None => (area, state.origin),
+        Some(split) => {
+            let (left, right) = halves(area);
+            let (focused, other) = if split.right {
+                (right, left)
+            } else {
+                (left, right)
+            };
+            let inside = |rect: Rect| column >= rect.x && column < rect.x + rect.w;
+            if inside(focused) {
+                (focused, state.origin)
+            } else if inside(other) {
+                (other, split.other)
+            } else {
+                return None;
             }
-            _ => (),
-        },
-        Event::Resize(x, y) => {
-            state.xy = (x, y);
-            state.game.resize(x.into(), y.into());
-            state.update = true;
         }
-        _ => (),
+    };
+    (column >= rect.x && row >= rect.y).then(|| {
+        (
+            origin.0 + (column - rect.x) as usize * state.zoom,
+            origin.1 + (row - rect.y) as usize * state.zoom,
+        )
+    })
+}
+
+/// F11, only with wrapping edges
+fn toggle_seam(state: &mut State) {
+    if !state.game.wraps() {
+        return set_message(state, "the edges don't wrap, start with --wrap".to_string());
     }
+    state.seam_preview = !state.seam_preview;
+    apply_resize(state, state.xy.0, state.xy.1)
 }
-// process_event }}}
 
-// run {{{
-pub fn run(game: Game, opts: TuiOpts) {
-    let mut stdout = std::io::stdout();
+/// Whether a screen cell starting at world `n` covers a multiple of 10
+fn on_line(n: usize, zoom: usize) -> bool {
+    n.div_ceil(10) * 10 < n + zoom
+}
 
-    // Initialize
-    terminal::enable_raw_mode().expect("Terminal could not enter raw");
-
-    let mut state = State {
-        game,
-        opts,
-        xy: terminal::size().expect("Could not query terminal size"),
-        update: false,
-        advance: false,
-        quit: false,
-    };
-
-    state.game.resize(state.xy.0.into(), state.xy.1.into());
-
-    queue!(
-        stdout,
-        terminal::EnterAlternateScreen,
-        event::EnableMouseCapture,
-        terminal::DisableLineWrap,
-        cursor::Hide,
-        cursor::MoveTo(state.xy.0 / 2, state.xy.1 / 2)
-    )
-    .expect("Terminal init fail");
+/// F6, from off to numbers to numbers and lines
+fn cycle_rulers(state: &mut State) {
+    state.rulers = match state.rulers {
+        Rulers::Off => Rulers::Numbers,
+        Rulers::Numbers => Rulers::Lines,
+        Rulers::Lines => Rulers::Off,
+    };
+    // the world may need to grow to cover the screen again
+    apply_resize(state, state.xy.0, state.xy.1)
+}
 
-    draw(&mut stdout, &mut state);
+/// World cells covered by the screen
+fn view_size(state: &State) -> (usize, usize) {
+    let area = cell_area(state);
+    (area.w as usize * state.zoom, area.h as usize * state.zoom)
+}
 
-    let mut draw_times = vec![];
-    // Main loop
-    while !state.quit {
-        if let Some(evt) = try_read(0.1) {
-            process_event(&mut state, evt)
-        } else if state.advance {
-            state.game.advance();
-            state.update = true;
-        }
+/// Keeps the screen inside the world, both halves of it when split
+fn clamp_origin(state: &mut State) {
+    let ((w, h), zoom) = (state.game.size(), state.zoom);
+    let clamp = |origin: (usize, usize), rect: Rect| {
+        let span = world_span(origin, rect, zoom);
+        (
+            origin.0.min(w.saturating_sub(span.w)),
+            origin.1.min(h.saturating_sub(span.h)),
+        )
+    };
+    state.origin = clamp(state.origin, focused_area(state));
+    if let Some(split) = state.split {
+        let (left, right) = halves(cell_area(state));
+        let other = if split.right { left } else { right };
+        state.split = Some(Split {
+            other: clamp(split.other, other),
+            ..split
+        })
+    }
+}
+
+fn pan(state: &mut State, dx: isize, dy: isize) {
+    let step = (PAN_STEP * state.zoom) as isize;
+    state.origin = (
+        state.origin.0.saturating_add_signed(dx * step),
+        state.origin.1.saturating_add_signed(dy * step),
+    );
+    clamp_origin(state);
+    state.update = true;
+}
 
-        if state.update {
-            let dt = Instant::now();
-            draw(&mut stdout, &mut state);
-            state.update = false;
-            draw_times.push(Instant::now() - dt);
+/// Sizes the world for `pattern` according to `opts.fit`, stamps it in the middle,
+/// and centers the screen on it
+fn load_pattern(state: &mut State, pattern: &Grid) {
+    let (pw, ph) = (pattern.first().map(|r| r.len()).unwrap_or(0), pattern.len());
+    let area = cell_area(state);
+    let (sw, sh) = (area.w as usize, area.h as usize);
+
+    state.zoom = match state.opts.fit {
+        Fit::Scale => pw.div_ceil(sw.max(1)).max(ph.div_ceil(sh.max(1))).max(1),
+        Fit::Clip | Fit::World => 1,
+    };
+    let (vw, vh) = view_size(state);
+    let world = match state.opts.fit {
+        Fit::World => {
+            let side = |p: usize, s: usize| if p > s { p + 2 * LOAD_MARGIN } else { s };
+            (side(pw, sw), side(ph, sh))
         }
+        Fit::Clip | Fit::Scale => (vw, vh),
+    };
+    if state.opts.fit != Fit::Clip {
+        state.min_world = world;
     }
 
-    // Cleanup
-    queue!(
-        stdout,
-        terminal::LeaveAlternateScreen,
-        event::DisableMouseCapture,
-        cursor::Show,
-        terminal::EnableLineWrap
-    )
-    .expect("Terminal cleanup fail");
-    terminal::disable_raw_mode().expect("Terminal could not exit raw");
-    stdout.flush().expect("Terminal flush fail");
+    state.game.resize(world.0, world.1);
+    let (x, y) = (
+        world.0.saturating_sub(pw) / 2,
+        world.1.saturating_sub(ph) / 2,
+    );
+    state.game.stamp(pattern, x, y);
+    state.max_population = state.max_population.max(state.game.population());
 
-    println!(
-        "DRAW_AVG: {}",
-        (draw_times.iter().sum::<Duration>() / draw_times.len() as u32).as_millis()
+    state.origin = (
+        (x + pw / 2).saturating_sub(vw / 2),
+        (y + ph / 2).saturating_sub(vh / 2),
     );
-    println!(
-        "DRAW_MEDIAN: {}",
-        draw_times[draw_times.len() / 2].as_millis()
-    )
+    clamp_origin(state);
+    state.update = true;
+}
+
+/// Replaces the world's cells with `picture` scaled to fit it
+fn load_image(state: &mut State, picture: &Picture, opts: &ImageOpts) {
+    let (w, h) = state.game.size();
+    let grid = picture.grid(w, h, opts, state.game.opts().life);
+    state.game.clear();
+    state.game.stamp(&grid, 0, 0);
+    state.max_population = state.max_population.max(state.game.population());
+    state.update = true;
+}
+// viewport }}}
+
+// step {{{
+/// The cheap bookkeeping every generation gets, even those a catch-up runs through
+fn tally(state: &mut State, info: StepInfo) {
+    state.sim_stats.record(info);
+    state.generations += 1;
+    state.max_population = state.max_population.max(info.population)
+}
+
+/// Advances once, handing the `StepInfo` along with the game it left to everything
+/// watching the run
+fn step(state: &mut State) {
+    let info = state.game.advance();
+    tally(state, info);
+    state.value_history.record(&info, &state.game);
+    if let Some(trail) = &mut state.birth_trail {
+        trail.record(&info, &state.game)
+    }
+    state.update = true;
+
+    if let Some(max) = state.opts.auto_expand {
+        let edges = state.game.near_edges(EXPAND_DISTANCE);
+        if edges.any() {
+            let size = state.game.size();
+            let (dx, dy) = state.game.expand(edges, state.opts.expand_margin, max);
+            state.origin = (state.origin.0 + dx, state.origin.1 + dy);
+            if let Some(split) = &mut state.split {
+                split.other = (split.other.0 + dx, split.other.1 + dy)
+            }
+            if state.game.size() != size {
+                let (w, h) = state.game.size();
+                set_message(state, format!("world grew to {}×{}", w, h))
+            }
+        }
+    } else if state.opts.edge_warn && state.auto_advance && state.game.near_edges(0).any() {
+        state.auto_advance = false;
+        set_message(state, "live cells reached the edge, paused".to_string())
+    }
+
+    if state.demo.as_mut().is_some_and(|d| d.tick()) {
+        state.auto_advance = false
+    }
+    if state
+        .versus
+        .as_mut()
+        .is_some_and(|v| v.record(&info, &state.game))
+    {
+        state.auto_advance = false
+    }
+    if let Some((saver, periods)) = &mut state.screensaver {
+        let signal = if info.population == 0 {
+            Signal::Extinct
+        } else if periods.observe(&state.game).is_some() {
+            Signal::Stagnant
+        } else {
+            Signal::Active
+        };
+        let (w, h) = state.game.size();
+        let action = saver.react(signal, w, h);
+        if action != Action::Nothing {
+            // the screensaver conflicts with --strict-determinism, so this is never refused
+            let _ = state
+                .game
+                .note_nondeterminism(Nondeterminism::Entropy("screensaver"));
+            saver.apply(action, &mut state.game);
+            periods.clear()
+        }
+    }
+    if let Some((policy, periods)) = &mut state.bell {
+        // hashing every generation is only worth it when repeats matter
+        let period = (policy.on == BellOn::OnPeriod)
+            .then(|| periods.observe(&state.game))
+            .flatten();
+        let events = BellEvents {
+            generation: state.generations,
+            population: info.population,
+            deaths: info.deaths,
+            period,
+        };
+        state.ring |= policy.ring(events, Instant::now())
+    }
+}
+// step }}}
+
+// birth trail {{{
+/// Generations a newborn's side color takes to fade into the heatmap
+const BIRTH_FADE: u8 = 4;
+
+fn side_color(side: Side) -> (u8, u8, u8) {
+    match side {
+        Side::Up => (70, 130, 255),
+        Side::Down => (255, 220, 40),
+        Side::Left => (40, 220, 90),
+        Side::Right => (230, 60, 230),
+    }
+}
+
+/// Recent births by `Side` and how many generations ago, row major over the world
+#[derive(Clone, Debug, Default)]
+struct BirthTrail {
+    width: usize,
+    cells: Vec<Option<(Side, u8)>>,
+}
+
+impl BirthTrail {
+    /// Ages everything a generation and adds the newest births of the step `info` described.
+    /// Cells that died or are done fading drop out, as does everything when the world
+    /// changed size
+    fn record(&mut self, info: &StepInfo, game: &Game) {
+        let (w, h) = game.size();
+        if self.width != w || self.cells.len() != w * h {
+            self.width = w;
+            self.cells = vec![None; w * h]
+        }
+        let sides = match info.births {
+            0 => &[],
+            _ => game.birth_sides().unwrap_or(&[]),
+        };
+        let live = game.grid().iter().flatten().map(|c| *c != 0);
+        for (n, (cell, alive)) in self.cells.iter_mut().zip(live).enumerate() {
+            *cell = match sides.get(n).copied().flatten() {
+                Some(side) => Some((side, 0)),
+                None => cell
+                    .filter(|(_, age)| alive && age + 1 < BIRTH_FADE)
+                    .map(|(side, age)| (side, age + 1)),
+            }
+        }
+    }
+
+    fn at(&self, x: usize, y: usize) -> Option<(Side, u8)> {
+        (x < self.width)
+            .then(|| self.cells.get(y * self.width + x).copied().flatten())
+            .flatten()
+    }
+}
+
+/// F9
+fn toggle_birth_trail(state: &mut State) {
+    let on = state.birth_trail.is_none();
+    state.birth_trail = on.then(BirthTrail::default);
+    state.game.set_birth_tracking(on);
+    state.update = true;
+    set_message(
+        state,
+        if on {
+            "newborns by their parents: above blue, below yellow, left green, right magenta"
+        } else {
+            "newborns in the heatmap"
+        }
+        .to_string(),
+    )
+}
+// birth trail }}}
+
+// value plot {{{
+/// Cell value counts for the last few generations, oldest first. Only kept while a cell
+/// can be more than alive and once the plot was first opened, and never more than
+/// `capacity` of them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct ValueHistory {
+    capacity: usize,
+    /// F5 was pressed at some point, nobody looks before that
+    started: bool,
+    /// generation, then `Game::value_histogram`
+    entries: VecDeque<(u64, Vec<usize>)>,
+}
+
+impl ValueHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Whether `record` does anything, it's another full scan each generation
+    fn keeps(&self, game: &Game) -> bool {
+        self.started && self.capacity > 0 && game.opts().life > 1
+    }
+
+    /// Keeps the values `game` was left with by the step `info` described. Starts over when
+    /// the generation doesn't follow the last one, as after a reset
+    fn record(&mut self, info: &StepInfo, game: &Game) {
+        if !self.keeps(game) {
+            return;
+        }
+        let (generation, counts) = (info.generation, game.value_histogram());
+        if self
+            .entries
+            .back()
+            .is_some_and(|(g, c)| *g >= generation || c.len() != counts.len())
+        {
+            self.entries.clear()
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((generation, counts))
+    }
+}
+
+fn toggle_value_plot(state: &mut State) {
+    if state.value_history.capacity == 0 {
+        return set_message(state, "--value-history 0 keeps no history".to_string());
+    }
+    if state.game.opts().life < 2 && !state.value_plot {
+        return set_message(state, "cell values need a --life above 1".to_string());
+    }
+    state.value_history.started = true;
+    state.value_plot = !state.value_plot
+}
+
+/// F5, one column per recent generation, stacked from value 1 at the bottom to full life at
+/// the top in the heatmap's colors. The height is scaled to the biggest population kept
+fn value_plot_overlay(state: &State) -> Overlay {
+    let life = state.game.opts().life.max(1);
+    let (w, h) = (state.xy.0 as usize, state.xy.1 as usize);
+    let entries = &state.value_history.entries;
+    let top = entries
+        .iter()
+        .map(|(_, c)| c.iter().sum::<usize>())
+        .max()
+        .unwrap_or(0);
+    let label = top.to_string().len();
+    // box borders and padding, then the axis label and its space
+    let columns = w.saturating_sub(4 + label + 1).max(1);
+    let rows = h.saturating_sub(2 + 3).max(1);
+    let shown: Vec<&(u64, Vec<usize>)> = entries
+        .iter()
+        .skip(entries.len().saturating_sub(columns))
+        .collect();
+
+    let mut lines = vec![match (shown.first(), shown.last()) {
+        (Some((first, _)), Some((last, _))) => {
+            format!("Cell values, generations {} to {}", first, last)
+        }
+        _ => "Cell values, nothing recorded yet".to_string(),
+    }];
+    let mut colors = Vec::new();
+    for row in (0..rows).rev() {
+        let axis = match row {
+            _ if row + 1 == rows => top.to_string(),
+            0 => "0".to_string(),
+            _ => String::new(),
+        };
+        let mut line = format!("{:>label$} ", axis);
+        let mut tint = vec![None; label + 1];
+        // the middle of this row, in cells
+        let level = (2 * row + 1) * top;
+        for (_, counts) in &shown {
+            let mut below = 0;
+            let value = counts.iter().position(|c| {
+                below += c;
+                2 * rows * below > level
+            });
+            match value {
+                Some(v) => {
+                    line.push('█');
+                    let rgb = color::heat((v + 1) as f32 / life as f32);
+                    tint.push(color_depth(state).color(rgb))
+                }
+                None => {
+                    line.push(' ');
+                    tint.push(None)
+                }
+            }
+        }
+        // the box adds a border and a space in front
+        colors.push((lines.len() + 1, [vec![None; 2], tint].concat()));
+        lines.push(line)
+    }
+    lines.push(String::new());
+    lines.push(format!("values 1 up to {}, F5 to close", life));
+
+    let width = (label + 1 + columns).min(w.saturating_sub(4));
+    Overlay {
+        colors,
+        ..Overlay::boxed(0, 0, &lines, width)
+    }
+}
+// value plot }}}
+
+// versus {{{
+/// Player colors, swapped on a rematch
+const PLAYER_COLORS: [((u8, u8, u8), &str); 2] = [((220, 40, 40), "red"), ((40, 90, 230), "blue")];
+
+fn player_color(versus: &Versus, player: u8) -> ((u8, u8, u8), &'static str) {
+    PLAYER_COLORS[usize::from((player == 2) != versus.swapped)]
+}
+
+/// Ends seeding, on time or early with `early`
+fn start_round(state: &mut State, early: bool) {
+    if state
+        .versus
+        .as_mut()
+        .is_some_and(|v| v.start(Instant::now(), early))
+    {
+        state.auto_advance = true
+    }
+}
+
+fn new_round(state: &mut State, swap: bool) {
+    let Some(versus) = &mut state.versus else {
+        return;
+    };
+    if swap {
+        versus.rematch(Instant::now())
+    } else {
+        versus.restart(Instant::now())
+    }
+    state.game.clear();
+    state.auto_advance = false;
+    state.update = true;
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One character per bucket of `values`, each showing the bucket's max against `top`
+fn sparkline(values: &[usize], top: usize, width: usize) -> String {
+    let bucket = values.len().div_ceil(width.max(1)).max(1);
+    values
+        .chunks(bucket)
+        .map(|c| {
+            let v = c.iter().copied().max().unwrap_or(0);
+            SPARKS[(v * (SPARKS.len() - 1)).checked_div(top).unwrap_or(0)]
+        })
+        .collect()
+}
+
+fn versus_overlay(state: &State, versus: &Versus) -> Overlay {
+    let name = |p| format!("player {} ({})", p, player_color(versus, p).1);
+    let scores = versus.scores();
+    match versus.phase {
+        Phase::Seeding { until } => Overlay::plain(
+            0,
+            0,
+            format!(
+                "{} left click, {} middle or ctrl click. Starting in {}s, Enter to start now",
+                name(1),
+                name(2),
+                until
+                    .saturating_duration_since(Instant::now())
+                    .as_secs_f32()
+                    .ceil()
+            ),
+        ),
+        Phase::Running { remaining } => Overlay::plain(
+            0,
+            0,
+            format!(
+                "{} {}  {} {}  generation {} of {}",
+                name(1),
+                scores[0],
+                name(2),
+                scores[1],
+                versus.generations - remaining,
+                versus.generations
+            ),
+        ),
+        Phase::Results => {
+            let width = (state.xy.0 as usize).saturating_sub(4).min(50);
+            let top = versus
+                .history(1)
+                .iter()
+                .chain(versus.history(2))
+                .copied()
+                .max()
+                .unwrap_or(0);
+            let mut lines = vec![
+                match versus.winner() {
+                    Some(p) => format!("{} wins!", name(p)),
+                    None => "It's a draw!".to_string(),
+                },
+                String::new(),
+            ];
+            for p in [1, 2] {
+                lines.push(format!("{}: {}", name(p), scores[usize::from(p) - 1]));
+                lines.push(sparkline(versus.history(p), top, width));
+            }
+            lines.push(String::new());
+            lines.push("R to restart, M for a rematch with colors swapped".to_string());
+            let x = (state.xy.0 as usize).saturating_sub(width + 4) as u16 / 2;
+            Overlay::boxed(x, 1, &lines, width)
+        }
+    }
+}
+// versus }}}
+
+// demo {{{
+fn start_scene(state: &mut State) {
+    if let Some(demo) = &state.demo {
+        demo.load(&mut state.game);
+        state.tick_interval = demo
+            .current()
+            .map(|s| s.tick)
+            .unwrap_or(tick_interval(state.opts.initial_speed, &state.opts));
+        state.auto_advance = true;
+        state.update = true;
+    }
+}
+
+/// Esc leaves the demo for the rule it started from, anything else moves on once a scene
+/// is done
+fn demo_key(state: &mut State, code: KeyCode) {
+    let Some(demo) = &mut state.demo else { return };
+    if code != KeyCode::Esc && !demo.waiting() {
+        // scene still running
+    } else if code != KeyCode::Esc && demo.next() {
+        start_scene(state)
+    } else {
+        state.demo = None;
+        if let Some(rule) = state.pre_demo.take() {
+            state.game.set_opts(rule)
+        }
+        state.game.clear();
+        state.auto_advance = false;
+        state.tick_interval = tick_interval(state.opts.initial_speed, &state.opts);
+        state.update = true;
+    }
+}
+// demo }}}
+
+// wrap {{{
+/// Splits text into lines of at most `width` chars on spaces
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut result = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = result.last_mut().unwrap();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            result.push(word.to_string())
+        } else {
+            if !line.is_empty() {
+                line.push(' ')
+            }
+            line.push_str(word)
+        }
+    }
+    result
+}
+// wrap }}}
+
+// Rect {{{
+/// Screen area, `w`×`h` cells with its top left at x, y
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Rect {
+    x: u16,
+    y: u16,
+    w: u16,
+    h: u16,
+}
+// Rect }}}
+
+// braille {{{
+/// Braille dot bits, indexed by [y][x] within a 2×4 character cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Packs a grid into braille characters, downsampling until it fits in `cols`×`rows`
+fn braille(grid: &Grid, cols: usize, rows: usize) -> Vec<String> {
+    let (w, h) = (grid.first().map(|r| r.len()).unwrap_or(0), grid.len());
+    let scale = w
+        .div_ceil(cols.max(1) * 2)
+        .max(h.div_ceil(rows.max(1) * 4))
+        .max(1);
+    let grid = downsample(grid, scale);
+
+    grid.chunks(4)
+        .map(|block| {
+            (0..grid.first().map(|r| r.len()).unwrap_or(0).div_ceil(2))
+                .map(|cx| {
+                    let mut bits = 0;
+                    for (dy, row) in block.iter().enumerate() {
+                        for (dx, bit) in BRAILLE_DOTS[dy].iter().enumerate() {
+                            if row.get(cx * 2 + dx).is_some_and(|c| *c != 0) {
+                                bits |= bit
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + bits).unwrap_or(' ')
+                })
+                .collect()
+        })
+        .collect()
+}
+// braille }}}
+
+// Overlay {{{
+/// Text drawn over the grid. The compositor repaints the cells under `rect` when it goes away
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Overlay {
+    rect: Rect,
+    lines: Vec<String>,
+    /// indices of lines drawn dimmed
+    dim: Vec<usize>,
+    /// and bold
+    bold: Vec<usize>,
+    /// lines drawn a character at a time, each in its own color
+    colors: Vec<(usize, Vec<Option<Color>>)>,
+}
+
+impl Overlay {
+    fn plain(x: u16, y: u16, text: String) -> Self {
+        Self {
+            rect: Rect {
+                x,
+                y,
+                w: text.chars().count() as u16,
+                h: 1,
+            },
+            lines: vec![text],
+            dim: Vec::new(),
+            bold: Vec::new(),
+            colors: Vec::new(),
+        }
+    }
+
+    /// Bordered box around `lines`, each padded to `width`
+    fn boxed(x: u16, y: u16, lines: &[String], width: usize) -> Self {
+        let border = "─".repeat(width + 2);
+        let mut result = vec![format!("┌{}┐", border)];
+        result.extend(lines.iter().map(|l| format!("│ {:width$} │", l)));
+        result.push(format!("└{}┘", border));
+        Self {
+            rect: Rect {
+                x,
+                y,
+                w: width as u16 + 4,
+                h: result.len() as u16,
+            },
+            lines: result,
+            dim: Vec::new(),
+            bold: Vec::new(),
+            colors: Vec::new(),
+        }
+    }
+}
+
+/// Every overlay that should currently be on screen, bottom to top
+fn overlays(state: &State) -> Vec<Overlay> {
+    let mut result = Vec::new();
+
+    if let (Some(split), area) = (state.split, cell_area(state)) {
+        let (left, _) = halves(area);
+        let mut lines = vec!["│".to_string(); area.h as usize];
+        if let Some(top) = lines.first_mut() {
+            *top = if split.right { "▶" } else { "◀" }.to_string()
+        }
+        result.push(Overlay {
+            rect: Rect {
+                x: left.x + left.w,
+                y: area.y,
+                w: 1,
+                h: area.h,
+            },
+            dim: (1..lines.len()).collect(),
+            bold: vec![0],
+            colors: Vec::new(),
+            lines,
+        })
+    }
+
+    if let Some(scene) = state.demo.as_ref().and_then(|d| d.current()) {
+        let width = (state.xy.0 as usize).saturating_sub(4).min(60);
+        let mut lines = wrap(scene.caption, width);
+        lines.push(String::new());
+        lines.push(if state.demo.as_ref().is_some_and(|d| d.waiting()) {
+            "Press any key to continue, Esc to exit".to_string()
+        } else {
+            "Esc to exit".to_string()
+        });
+        let x = (state.xy.0 as usize).saturating_sub(width + 4) as u16 / 2;
+        result.push(Overlay::boxed(x, 1, &lines, width))
+    }
+
+    if let Some(versus) = &state.versus {
+        result.push(versus_overlay(state, versus))
+    }
+
+    if let Some(picker) = &state.picker {
+        result.push(picker_overlay(state, picker))
+    }
+
+    if let Some(browser) = &state.browser {
+        result.push(browser_overlay(state, browser))
+    }
+
+    if state.value_plot {
+        result.push(value_plot_overlay(state))
+    }
+
+    if let Some((counts, generation)) = &state.histogram {
+        result.push(histogram_overlay(state, counts, *generation))
+    }
+
+    if let Some(((x, y), at)) = state.inspect {
+        result.push(inspector_overlay(state, x, y, at))
+    }
+
+    if state.game.nondeterminism().is_some() && !state.opts.screensaver {
+        let mut marker = Overlay::plain(0, 0, NONDETERMINISTIC.to_string());
+        marker.rect.x = state.xy.0.saturating_sub(marker.rect.w);
+        marker.dim.push(0);
+        result.push(marker)
+    }
+
+    if state.link.slow {
+        let mut marker = Overlay::plain(0, state.xy.1.saturating_sub(1), SLOW_LINK.to_string());
+        marker.rect.x = state.xy.0.saturating_sub(marker.rect.w);
+        marker.bold.push(0);
+        result.push(marker)
+    }
+    // left of the slow link marker, which is usually why
+    if state.frame_skip_count > 0 && !state.opts.screensaver {
+        let text = format!("{} frames skipped", state.frame_skip_count);
+        let mut marker = Overlay::plain(0, state.xy.1.saturating_sub(1), text);
+        let slow = if state.link.slow {
+            SLOW_LINK.len() + 1
+        } else {
+            0
+        };
+        marker.rect.x = state.xy.0.saturating_sub(marker.rect.w + slow as u16);
+        marker.dim.push(0);
+        result.push(marker)
+    }
+
+    if let (Some(status), None, None, false) = (
+        &state.opts.status_format,
+        &state.message,
+        &state.command,
+        state.opts.screensaver,
+    ) {
+        result.push(Overlay::plain(
+            0,
+            state.xy.1.saturating_sub(1),
+            template::render(status, &template_values(state)),
+        ))
+    }
+
+    if let Some((x, y)) = state.cursor {
+        let mut cursor = Overlay::plain(x, y, CURSOR.to_string());
+        cursor.bold.push(0);
+        result.push(cursor)
+    }
+
+    if let Some((message, _)) = &state.message {
+        result.push(Overlay::plain(
+            0,
+            state.xy.1.saturating_sub(1),
+            message.clone(),
+        ))
+    }
+
+    if let Some(command) = &state.command {
+        result.push(Overlay::plain(
+            0,
+            state.xy.1.saturating_sub(1),
+            format!(":{}", command),
+        ))
+    }
+
+    result
+}
+const HISTOGRAM_BARS: usize = 40;
+
+/// Bar chart of `:hist`, a snapshot that doesn't follow the simulation
+fn histogram_overlay(state: &State, counts: &[usize; cells::COUNTS], generation: u64) -> Overlay {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let digits = max.to_string().len();
+    let mut lines = vec![
+        format!("Neighbor counts at generation {}", generation),
+        String::new(),
+    ];
+    let shown = state.game.opts().reachable() + 1;
+    for (n, count) in counts.iter().enumerate().take(shown) {
+        // rare counts still get a sliver
+        let bar = (count * HISTOGRAM_BARS)
+            .checked_div(max)
+            .unwrap_or(0)
+            .max(usize::from(*count > 0));
+        lines.push(format!(
+            "{:>labels$} {:>digits$} {}",
+            n,
+            count,
+            "█".repeat(bar),
+            labels = (shown - 1).to_string().len(),
+            digits = digits
+        ));
+    }
+    lines.push(String::new());
+    lines.push("Any key to close".to_string());
+
+    let width = HISTOGRAM_BARS + digits + (shown - 1).to_string().len() + 2;
+    let x = (state.xy.0 as usize).saturating_sub(width + 4) as u16 / 2;
+    Overlay::boxed(x, 1, &lines, width)
+}
+
+/// Everything known about one cell, beside where it was opened. Built every frame so it
+/// follows the simulation
+fn inspector_overlay(state: &State, x: usize, y: usize, at: (u16, u16)) -> Overlay {
+    let game = &state.game;
+    let life = game.opts().life;
+    let current = game.get_cell(x, y).unwrap_or(0);
+    let mut lines = vec![
+        format!("cell {}, {}", x, y),
+        if current == 0 {
+            "dead".to_string()
+        } else {
+            format!("life {} of {}", current.min(life), life)
+        },
+        format!(
+            "{} of {} neighbors {}",
+            game.neighbors_at(x, y).unwrap_or(0),
+            game.opts().reachable(),
+            if game.opts().mature_neighbors_only {
+                "at full life"
+            } else {
+                "alive"
+            }
+        ),
+    ];
+    let current = current.min(life);
+    lines.push(match game.next_cell(x, y).unwrap_or(0) {
+        _ if game.is_static(x, y) => "next: frozen".to_string(),
+        0 if current == 0 => "next: stays dead".to_string(),
+        n if current == 0 => format!("next: born at {}", n),
+        0 => "next: dies".to_string(),
+        n if n > current => format!("next: grows to {}", n),
+        n if n < current => format!("next: decays to {}", n),
+        _ => "next: survives".to_string(),
+    });
+    if let Some(n) = game.static_regions().iter().position(|r| r.contains(x, y)) {
+        lines.push(format!("in static region {}", n + 1))
+    }
+    if state
+        .selection
+        .is_some_and(|(a, b)| cells::Rect::between(a, b).contains(x, y))
+    {
+        lines.push("in the selection".to_string())
+    }
+    lines.push(String::new());
+    lines.push("Any click or key to close".to_string());
+
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    // beside the cell, moved back onto the screen near the edges
+    let w = width as u16 + 4;
+    let h = lines.len() as u16 + 2;
+    let x = if at.0 + 2 + w <= state.xy.0 {
+        at.0 + 2
+    } else {
+        at.0.saturating_sub(w + 1)
+    };
+    let y = at.1.min(state.xy.1.saturating_sub(h));
+    Overlay::boxed(x, y, &lines, width)
+}
+
+/// Opens the inspector on the world cell under screen `col`, `row`
+fn inspect(state: &mut State, col: u16, row: u16) {
+    if let Some(cell) = world_at(state, col, row) {
+        state.inspect = Some((cell, (col, row)))
+    }
+}
+
+const PICKER_NAMES: usize = 24;
+const PICKER_THUMB: (usize, usize) = (12, 3);
+
+/// Pattern list with a thumbnail of the highlighted entry beside it.
+/// Entries that failed to read are dimmed
+fn picker_overlay(state: &State, picker: &Picker) -> Overlay {
+    let selected = picker.selected;
+    let thumb = match &picker.preview {
+        Ok(grid) => braille(grid, PICKER_THUMB.0, PICKER_THUMB.1),
+        Err(_) => Vec::new(),
+    };
+
+    let mut lines = Vec::new();
+    let mut source = None;
+    for (n, pattern) in state.library.iter().enumerate() {
+        if source != Some(&pattern.source) {
+            source = Some(&pattern.source);
+            let name: String = pattern.source.chars().take(PICKER_NAMES - 1).collect();
+            lines.push((format!("{}:", name), None));
+        }
+        let name: String = pattern.name.chars().take(PICKER_NAMES - 2).collect();
+        let marker = if n == selected { "> " } else { "  " };
+        lines.push((format!("{}{}", marker, name), Some(n)));
+    }
+    // thumbnail starts beside the highlighted entry
+    let row = lines
+        .iter()
+        .position(|(_, n)| *n == Some(selected))
+        .unwrap_or(0);
+    // borders, the two footer lines, and a line of the grid peeking out, like the browser.
+    // The thumbnail shows whole when there's room, the highlighted entry always does
+    let rows = (state.xy.1 as usize).saturating_sub(1 + 2 + 2 + 1).max(1);
+    let first = (row + thumb.len().max(1)).saturating_sub(rows).min(row);
+    let shown = || lines.iter().enumerate().skip(first).take(rows);
+
+    // +1 for the top border
+    let dim = shown()
+        .filter(|(_, (_, n))| n.is_some_and(|n| !state.library[n].selectable()))
+        .map(|(i, _)| i - first + 1)
+        .collect();
+    let mut lines: Vec<String> = shown()
+        .map(|(i, (line, _))| {
+            let preview = i
+                .checked_sub(row)
+                .and_then(|t| thumb.get(t))
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            format!("{:names$} {}", line, preview, names = PICKER_NAMES)
+        })
+        .collect();
+    let width = PICKER_NAMES + 1 + PICKER_THUMB.0;
+    lines.push(String::new());
+    lines.push(match &picker.preview {
+        Err(e) => e.chars().take(width).collect(),
+        Ok(_) => "Enter to pick, Esc to close".to_string(),
+    });
+
+    let x = (state.xy.0 as usize).saturating_sub(width + 4) as u16 / 2;
+    Overlay {
+        dim,
+        ..Overlay::boxed(x, 1, &lines, width)
+    }
+}
+const BROWSER_WIDTH: usize = 48;
+
+/// The directory's entries around the highlighted one, pattern files bold
+fn browser_overlay(state: &State, browser: &Browser) -> Overlay {
+    let width = BROWSER_WIDTH.min((state.xy.0 as usize).saturating_sub(4));
+    let fit = |text: String| -> String {
+        let count = text.chars().count();
+        // the end of a long path is the part worth seeing
+        if count > width {
+            text.chars().skip(count - width).collect()
+        } else {
+            text
+        }
+    };
+    let mut lines = vec![
+        fit(browser.dir.display().to_string()),
+        format!(
+            "{}{}",
+            match browser.purpose {
+                Purpose::Load => "load: ",
+                Purpose::Save => "save as: ",
+            },
+            browser.filter
+        ),
+    ];
+    let header = lines.len();
+
+    // border, header, the two footer lines, and a line of the grid peeking out
+    let rows = (state.xy.1 as usize).saturating_sub(header + 6).max(1);
+    let visible = browser.visible();
+    let first = (browser.selected + 1).saturating_sub(rows);
+    let mut bold = Vec::new();
+    for (n, entry) in visible.iter().enumerate().skip(first).take(rows) {
+        let marker = if n == browser.selected { "> " } else { "  " };
+        let slash = if entry.dir { "/" } else { "" };
+        if entry.pattern {
+            // +1 for the top border
+            bold.push(lines.len() + 1)
+        }
+        lines.push(
+            format!("{}{}{}", marker, entry.name, slash)
+                .chars()
+                .take(width)
+                .collect(),
+        )
+    }
+    if visible.is_empty() {
+        lines.push("  nothing here".to_string())
+    }
+
+    lines.push(String::new());
+    lines.push(if let Some(e) = &browser.error {
+        e.chars().take(width).collect()
+    } else if browser.truncated {
+        format!("showing first {}", MAX_BROWSER_ENTRIES)
+    } else {
+        match browser.purpose {
+            Purpose::Load => "Enter to open, Backspace up, Esc to close",
+            Purpose::Save => "type a name, Enter to save, Esc to close",
+        }
+        .chars()
+        .take(width)
+        .collect()
+    });
+
+    let x = (state.xy.0 as usize).saturating_sub(width + 4) as u16 / 2;
+    Overlay {
+        bold,
+        ..Overlay::boxed(x, 1, &lines, width)
+    }
+}
+// Overlay }}}
+
+// draw {{{
+/// Text and color for the cell at world `x`, `y`, each picked on its own so digits can
+/// be heatmapped too. Dead cells are a space with no color
+fn glyph(state: &State, cell: u8, x: usize, y: usize) -> (String, Option<Color>) {
+    if cell == 0 {
+        return (' '.to_string(), None);
+    }
+    let text = cell_text(state, cell, x, y);
+    let digit = text.chars().all(|c| c.is_ascii_hexdigit());
+    (text, cell_color(state, cell, x, y, digit))
+}
+
+/// The colors cells are drawn in, none while the link is slow
+fn color_depth(state: &State) -> ColorDepth {
+    if state.link.slow {
+        ColorDepth::Monochrome
+    } else {
+        state.opts.color
+    }
+}
+
+/// Versus players are solid blocks when they have colors to tell them apart, a shade for the
+/// second when there are none. Everything else is `plain_glyph`
+fn cell_text(state: &State, cell: u8, x: usize, y: usize) -> String {
+    match state.versus.as_ref().map(|v| v.owner(x, y)) {
+        Some(1 | 2) if color_depth(state) != ColorDepth::Monochrome => '█'.to_string(),
+        Some(2) => '▒'.to_string(),
+        _ => plain_glyph(state, cell),
+    }
+}
+
+/// Versus players get their own colors, everything else is heatmapped by how much life is
+/// left, with F9's newborns fading in from their side's color. A `digit` is lifted to
+/// `DIGIT_LUMINANCE` so it stays readable at the dark end
+fn cell_color(state: &State, cell: u8, x: usize, y: usize, digit: bool) -> Option<Color> {
+    let rgb = match state.versus.as_ref().map(|v| (v, v.owner(x, y))) {
+        Some((versus, p @ (1 | 2))) => player_color(versus, p).0,
+        _ => {
+            let heat = color::heat(cell as f32 / state.game.opts().life.max(1) as f32);
+            match state.birth_trail.as_ref().and_then(|t| t.at(x, y)) {
+                Some((side, age)) => {
+                    color::mix(side_color(side), heat, age as f32 / BIRTH_FADE as f32)
+                }
+                None => heat,
+            }
+        }
+    };
+    let rgb = if digit {
+        color::lift(rgb, color::DIGIT_LUMINANCE)
+    } else {
+        rgb
+    };
+    color_depth(state).color(rgb)
+}
+
+fn plain_glyph(state: &State, cell: u8) -> String {
+    if cell == 0 {
+        ' '.to_string()
+    } else if state.opts.numeric_abs {
+        absolute_digit(cell, state.game.opts().life).to_string()
+    } else if state.opts.numeric {
+        if state.game.opts().life > 9 {
+            (((cell as f32 / state.game.opts().life as f32) * 9.0).round() as u8)
+                .min(9)
+                .to_string()
+        } else {
+            cell.to_string()
+        }
+    } else {
+        '█'.to_string()
+    }
+}
+
+/// The last hex digit of `cell` while life fits in one, otherwise the last decimal digit
+fn absolute_digit(cell: u8, life: u8) -> char {
+    let radix = if life <= 16 { 16 } else { 10 };
+    char::from_digit((cell % radix) as u32, radix as u32).unwrap_or('?')
+}
+
+/// Draws the rulers under a screen area
+fn draw_rulers(out: &mut impl Write, state: &State, rect: Rect) {
+    let area = cell_area(state);
+    if state.rulers == Rulers::Off || area.w == 0 || area.h == 0 {
+        return;
+    }
+    let slice = |text: &str, from: u16, to: u16| -> String {
+        text.chars()
+            .skip((rect.x.max(from) - from) as usize)
+            .take((rect.x + rect.w).min(to).saturating_sub(rect.x.max(from)) as usize)
+            .collect()
+    };
+    queue!(out, style::SetAttribute(style::Attribute::Dim)).expect("ruler fail");
+
+    if rect.y == 0 {
+        // a number starts at every column on a multiple of 10, if there's room
+        let mut top = vec![' '; state.xy.0 as usize];
+        let mut free = area.x as usize;
+        for sx in area.x as usize..(area.x + area.w) as usize {
+            let Some((x, _)) = world_at(state, sx as u16, area.y) else {
+                continue;
+            };
+            if sx >= free && on_line(x, state.zoom) {
+                let label = (x.div_ceil(10) * 10).to_string();
+                for (n, c) in label.chars().enumerate() {
+                    if let Some(slot) = top.get_mut(sx + n) {
+                        *slot = c
+                    }
+                }
+                free = sx + label.len() + 1;
+            }
+        }
+        let top: String = top.into_iter().collect();
+        queue!(
+            out,
+            cursor::MoveTo(rect.x, 0),
+            style::Print(slice(&top, 0, state.xy.0))
+        )
+        .expect("ruler fail");
+    }
+
+    if rect.x < area.x {
+        for sy in rect.y.max(area.y)..rect.y + rect.h {
+            // split, the left half's rows
+            let label = match world_at(state, area.x, sy) {
+                Some((_, y)) => format!("{:>4} ", y % 10000),
+                None => " ".repeat(RULER_WIDTH as usize),
+            };
+            queue!(
+                out,
+                cursor::MoveTo(rect.x, sy),
+                style::Print(slice(&label, 0, area.x))
+            )
+            .expect("ruler fail");
+        }
+    }
+    queue!(out, style::SetAttribute(style::Attribute::Reset)).expect("ruler fail");
+}
+
+/// Tint behind the cell at world `x`, `y`, for the selection and static regions
+fn background(state: &State, selection: Option<cells::Rect>, x: usize, y: usize) -> Option<Color> {
+    if selection.is_some_and(|r| r.contains(x, y)) {
+        color_depth(state).color(SELECTION_TINT)
+    } else if state.game.is_static(x, y) {
+        color_depth(state).color(STATIC_TINT)
+    } else if state.rulers == Rulers::Lines && (on_line(x, state.zoom) || on_line(y, state.zoom)) {
+        color_depth(state).color(GRID_TINT)
+    } else {
+        None
+    }
+}
+
+/// Draws the cells under a screen area. Zoomed out, each screen cell shows the
+/// oldest cell of its block
+fn draw_cells(out: &mut impl Write, state: &State, rect: Rect) {
+    let selection = selected(state);
+    let grid = state.game.grid();
+    let zoom = state.zoom;
+    let (w, h) = state.game.size();
+    let area = cell_area(state);
+    let seam = seam_width(state);
+    let (x0, y0) = (
+        rect.x.max(area.x.saturating_sub(seam)),
+        rect.y.max(area.y.saturating_sub(seam)),
+    );
+    let (x1, y1) = (
+        (rect.x + rect.w)
+            .min(area.x + area.w + seam)
+            .min(state.xy.0),
+        (rect.y + rect.h)
+            .min(area.y + area.h + seam)
+            .min(state.xy.1),
+    );
+
+    for sy in y0..y1 {
+        // only sent when they change, most neighbors share colors
+        let (mut current, mut current_bg, mut dim) = (None, None, false);
+        let mut moved = false;
+        for sx in x0..x1 {
+            let Some((x, y)) = world_at(state, sx, sy).filter(|(x, y)| *x < w && *y < h) else {
+                moved = false;
+                continue;
+            };
+            // every run of cells starts with an absolute move, so nothing depends on
+            // where the previous row left the cursor and no row is special. compose
+            // puts the cursor back with RestorePosition afterwards
+            if !moved {
+                queue!(out, cursor::MoveTo(sx, sy)).expect("Cursor move fail");
+                moved = true
+            }
+            let strip = seam > 0 && on_seam(state, sx, sy);
+            if strip != dim {
+                let attribute = if strip {
+                    style::Attribute::Dim
+                } else {
+                    style::Attribute::NormalIntensity
+                };
+                queue!(out, style::SetAttribute(attribute)).expect("color fail");
+                dim = strip
+            }
+            let cell = grid[y..(y + zoom).min(h)]
+                .iter()
+                .flat_map(|row| &row[x..(x + zoom).min(w)])
+                .copied()
+                .max()
+                .unwrap_or(0);
+            let (text, color) = glyph(state, cell, x, y);
+            let bg = if strip {
+                color_depth(state).color(SEAM_TINT)
+            } else {
+                background(state, selection, x, y)
+            };
+            if bg != current_bg {
+                queue!(out, style::SetBackgroundColor(bg.unwrap_or(Color::Reset)))
+                    .expect("color fail");
+                current_bg = bg
+            }
+            if color != current {
+                queue!(
+                    out,
+                    style::SetForegroundColor(color.unwrap_or(Color::Reset))
+                )
+                .expect("color fail");
+                current = color
+            }
+            queue!(out, style::Print(text)).expect("print cell fail")
+        }
+        if dim {
+            queue!(out, style::SetAttribute(style::Attribute::NormalIntensity)).expect("color fail")
+        }
+        if current.is_some() || current_bg.is_some() {
+            queue!(out, style::ResetColor).expect("color fail")
+        }
+    }
+}
+
+/// Cut to `size`, a screen too small for an overlay gets what fits
+fn draw_overlay(out: &mut impl Write, overlay: &Overlay, size: (u16, u16)) {
+    let room = size.0.saturating_sub(overlay.rect.x) as usize;
+    for (n, line) in overlay.lines.iter().enumerate() {
+        if overlay.rect.y + n as u16 >= size.1 || room == 0 {
+            break;
+        }
+        let line: String = line.chars().take(room).collect();
+        queue!(
+            out,
+            cursor::MoveTo(overlay.rect.x, overlay.rect.y + n as u16)
+        )
+        .expect("Cursor move fail");
+        if let Some((_, colors)) = overlay.colors.iter().find(|(l, _)| *l == n) {
+            let mut current = None;
+            for (c, color) in line
+                .chars()
+                .zip(colors.iter().chain(std::iter::repeat(&None)))
+            {
+                if *color != current {
+                    queue!(
+                        out,
+                        style::SetForegroundColor(color.unwrap_or(Color::Reset))
+                    )
+                    .expect("color fail");
+                    current = *color
+                }
+                queue!(out, style::Print(c)).expect("print overlay fail")
+            }
+            if current.is_some() {
+                queue!(out, style::ResetColor).expect("color fail")
+            }
+            continue;
+        }
+        let attribute = if overlay.dim.contains(&n) {
+            Some(style::Attribute::Dim)
+        } else if overlay.bold.contains(&n) {
+            Some(style::Attribute::Bold)
+        } else {
+            None
+        };
+        if let Some(attribute) = attribute {
+            queue!(
+                out,
+                style::SetAttribute(attribute),
+                style::Print(&line),
+                style::SetAttribute(style::Attribute::Reset)
+            )
+        } else {
+            queue!(out, style::Print(line))
+        }
+        .expect("print overlay fail");
+    }
+}
+
+/// The terminal's size as of the last resize event, which `xy` only catches up to once it settles
+fn terminal_size(state: &State) -> (u16, u16) {
+    state.pending_resize.map_or(state.xy, |(size, _)| size)
+}
+
+/// A full repaint of only the rows that differ from when they were last sent this way,
+/// plus the ones overlays covered, for a slow link
+fn draw_changed_rows(out: &mut impl Write, state: &mut State) {
+    let mut hashes = Vec::with_capacity(state.xy.1 as usize);
+    let mut row = Vec::new();
+    for y in 0..state.xy.1 {
+        row.clear();
+        let rect = Rect {
+            x: 0,
+            y,
+            w: state.xy.0,
+            h: 1,
+        };
+        draw_rulers(&mut row, state, rect);
+        draw_cells(&mut row, state, rect);
+        let mut hasher = DefaultHasher::new();
+        row.hash(&mut hasher);
+        let hash = hasher.finish();
+        let covered = state
+            .shown
+            .iter()
+            .any(|o| (o.rect.y..o.rect.y + o.rect.h).contains(&y));
+        if covered || state.link.rows.get(y as usize) != Some(&hash) {
+            out.write_all(&row).expect("Terminal write fail")
+        }
+        hashes.push(hash)
+    }
+    state.link.rows = hashes
+}
+
+/// Queues a frame, only touching the area of changed overlays unless `state.update` is set.
+/// Returns false if nothing needed drawing. Doesn't flush
+fn compose(out: &mut impl Write, state: &mut State) -> bool {
+    let size = state.xy;
+    let current = overlays(state);
+    // laid out for a size the terminal no longer is. Nothing is drawn until the resize
+    // settles, and `apply_resize` repaints everything then
+    if terminal_size(state) != size {
+        return false;
+    }
+
+    if state.update {
+        let screen = Rect {
+            x: 0,
+            y: 0,
+            w: state.xy.0,
+            h: state.xy.1,
+        };
+        queue!(out, cursor::SavePosition).expect("Cursor move fail");
+        if state.link.slow {
+            draw_changed_rows(out, state)
+        } else {
+            state.link.rows.clear();
+            draw_rulers(out, state, screen);
+            draw_cells(out, state, screen);
+        }
+        current.iter().for_each(|o| draw_overlay(out, o, size));
+    } else if current != state.shown {
+        state.link.rows.clear();
+        queue!(out, cursor::SavePosition).expect("Cursor move fail");
+        for gone in state.shown.iter().filter(|o| !current.contains(o)) {
+            draw_rulers(out, state, gone.rect);
+            draw_cells(out, state, gone.rect)
+        }
+        current.iter().for_each(|o| draw_overlay(out, o, size));
+    } else if state.ring {
+        queue!(out, style::Print('\x07')).expect("bell fail");
+        state.ring = false;
+        return true;
+    } else {
+        return false;
+    }
+    // with the frame, so it isn't split from the cells it's about
+    if state.ring {
+        queue!(out, style::Print('\x07')).expect("bell fail");
+        state.ring = false
+    }
+
+    queue!(out, cursor::RestorePosition).expect("Cursor move fail");
+
+    state.shown = current;
+    state.update = false;
+    true
+}
+// draw }}}
+
+// apply_resize {{{
+/// The only place `xy` changes, so the world always has room for the screen it's drawn on
+fn apply_resize(state: &mut State, x: u16, y: u16) {
+    state.xy = (x, y);
+    // the terminal may have reflowed whatever was there
+    state.painted = None;
+    state.link.rows.clear();
+    let (vw, vh) = view_size(state);
+    let (mut w, mut h) = (vw.max(state.min_world.0), vh.max(state.min_world.1));
+    // an expanded world only ever grows
+    if state.opts.auto_expand.is_some() {
+        let size = state.game.size();
+        w = size.0.max(w);
+        h = size.1.max(h);
+    }
+    state.game.resize(w, h);
+    clamp_origin(state);
+    state.update = true;
+}
+
+/// A `Resize` for the size the terminal reports, if a SIGWINCH set `flag` since the last
+/// call. Some terminals send the signal well before crossterm gets around to the event
+#[cfg(unix)]
+fn take_winch(
+    flag: &AtomicBool,
+    size: impl FnOnce() -> std::io::Result<(u16, u16)>,
+) -> Option<Event> {
+    if !flag.swap(false, Ordering::Relaxed) {
+        return None;
+    }
+    size().ok().map(|(x, y)| Event::Resize(x, y))
+}
+
+/// Applies the pending resize once the terminal has held still for `RESIZE_DEBOUNCE`
+fn settle_resize(state: &mut State, now: Instant) {
+    if let Some(((x, y), t)) = state.pending_resize {
+        if now.saturating_duration_since(t) >= RESIZE_DEBOUNCE {
+            state.pending_resize = None;
+            apply_resize(state, x, y)
+        }
+    }
+}
+// apply_resize }}}
+
+// picker {{{
+#[derive(Clone, Debug)]
+struct Picker {
+    /// highlighted entry
+    selected: usize,
+    /// its cells, or why they couldn't be read. Loaded once per move so files aren't reread
+    /// every frame
+    preview: Result<Grid, String>,
+}
+
+impl Picker {
+    fn new(library: &[Pattern], selected: usize) -> Self {
+        Self {
+            selected,
+            preview: library
+                .get(selected)
+                .map(|p| p.load())
+                .unwrap_or_else(|| Err("no patterns".to_string())),
+        }
+    }
+}
+
+fn picker_key(state: &mut State, code: KeyCode) {
+    let Some(picker) = &state.picker else { return };
+    let selected = picker.selected;
+    let last = state.library.len().saturating_sub(1);
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.picker = Some(Picker::new(&state.library, selected.saturating_sub(1)))
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.picker = Some(Picker::new(&state.library, (selected + 1).min(last)))
+        }
+        KeyCode::Enter => match &picker.preview {
+            Ok(grid) => {
+                state.stamp = Some(grid.clone());
+                let name = state.library[selected].name.clone();
+                state.picker = None;
+                set_message(
+                    state,
+                    format!(
+                        "picked {}, triple click to place, shift to cut it out",
+                        name
+                    ),
+                )
+            }
+            // stays open so another entry can be picked
+            Err(e) => {
+                let e = e.clone();
+                set_message(state, e)
+            }
+        },
+        KeyCode::Esc => state.picker = None,
+        _ => (),
+    }
+}
+// picker }}}
+
+// commands {{{
+/// Rebuilds the picker's library from the built-ins and `--patterns-dir`
+fn rescan(state: &mut State) -> Result<(), String> {
+    state.library = patterns::builtins();
+    state.picker = None;
+    if let Some(dir) = &state.opts.patterns_dir {
+        let found = patterns::scan_dir(dir, state.opts.patterns_recursive)
+            .map_err(|e| format!("could not read {}: {}", dir.display(), e))?;
+        state.library.extend(found);
+    }
+    Ok(())
+}
+
+/// Clears the world and loads a .rle, .cells, or .lif file into it like `--load`
+fn load_file(state: &mut State, path: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let ext = formats::extension(path)
+        .ok_or_else(|| format!("{} isn't a .rle, .cells, or .lif file", path.display()))?;
+    let pattern = formats::parse(&text, &ext, u8::MAX)
+        .map_err(|e| format!("could not load {}: {}", path.display(), e))?;
+
+    state.game.reset();
+    if ext == "rle" {
+        state.game.set_metadata(formats::rle_metadata(&text))
+    }
+    state.generations = 0;
+    load_pattern(state, &pattern);
+    if let Some((_, periods)) = &mut state.screensaver {
+        periods.clear()
+    }
+    set_message(state, format!("loaded {}", path.display()));
+    Ok(())
+}
+
+/// Fills the world with `SOUP_DENSITY` random cells and starts it running, as a new run
+fn soup(state: &mut State) {
+    state.game.reset();
+    // reset forgets, so noted after. Callers make sure it isn't strict
+    let _ = state
+        .game
+        .note_nondeterminism(Nondeterminism::Entropy("soup"));
+    state.game.randomize(SOUP_DENSITY, &mut rand::thread_rng());
+    state.generations = 0;
+    state.max_population = state.game.population();
+    if let Some((_, periods)) = &mut state.screensaver {
+        periods.clear()
+    }
+    state.auto_advance = true;
+    state.update = true
+}
+
+/// Ctrl+M or M, a random rule that keeps the neighborhood and life, on fresh soup
+fn reroll(state: &mut State) {
+    if state.game.strict_determinism() {
+        return set_message(
+            state,
+            "--strict-determinism refuses soup from OS randomness".to_string(),
+        );
+    }
+    let rule = CellOpts::random(
+        state.game.opts(),
+        state.opts.allow_b0,
+        &mut rand::thread_rng(),
+    );
+    state.game.set_opts(rule);
+    soup(state);
+    set_message(state, format!("rolled {}", rule))
+}
+
+/// Writes the live cells, cropped to where they are, in the format the extension says
+fn save_file(state: &mut State, path: &Path) -> Result<(), String> {
+    let ext = formats::extension(path)
+        .ok_or_else(|| format!("{} needs a .rle, .cells, or .lif extension", path.display()))?;
+    let grid = state.game.grid();
+    let live = state.game.to_coords();
+    let x0 = live.iter().map(|c| c.0).min().unwrap_or(0);
+    let x1 = live.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+    let y0 = live.iter().map(|c| c.1).min().unwrap_or(0);
+    let y1 = live.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+    let cropped: Grid = grid[y0..y1].iter().map(|r| r[x0..x1].to_vec()).collect();
+
+    let text = formats::write(
+        &cropped,
+        &ext,
+        &state.game.opts().rulestring(),
+        state.game.metadata(),
+    );
+    std::fs::write(path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    set_message(
+        state,
+        format!("saved {} cells to {}", live.len(), path.display()),
+    );
+    Ok(())
+}
+
+/// Writes the whole world as a raw dump, see `formats::to_raw`
+fn dump_raw(state: &mut State, path: &Path) -> Result<(), String> {
+    std::fs::write(path, formats::to_raw(state.game.grid()))
+        .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    let (w, h) = state.game.size();
+    set_message(
+        state,
+        format!("dumped {}×{} cells to {}", w, h, path.display()),
+    );
+    Ok(())
+}
+
+/// Opens the browser in the working directory
+fn browse(state: &mut State, purpose: Purpose) {
+    let dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    state.browser = Some(Browser::open(&dir, purpose))
+}
+
+fn browser_key(state: &mut State, code: KeyCode) {
+    let Some(browser) = &mut state.browser else {
+        return;
+    };
+    match code {
+        KeyCode::Up => browser.move_by(-1),
+        KeyCode::Down => browser.move_by(1),
+        KeyCode::PageUp => browser.move_by(-10),
+        KeyCode::PageDown => browser.move_by(10),
+        KeyCode::Char(c) => browser.type_char(c),
+        KeyCode::Backspace => browser.backspace(),
+        KeyCode::Enter => {
+            let purpose = browser.purpose;
+            if let Choice::File(path) = browser.enter() {
+                state.browser = None;
+                let result = match purpose {
+                    Purpose::Load => load_file(state, &path),
+                    Purpose::Save => save_file(state, &path),
+                };
+                if let Err(e) = result {
+                    set_message(state, e)
+                }
+            }
+        }
+        KeyCode::Esc => state.browser = None,
+        _ => (),
+    }
+}
+
+/// Runs a line typed after `:`
+fn run_command(state: &mut State, line: &str) -> Result<(), String> {
+    let mut words = line.split_whitespace();
+    let command = words.next();
+    // everything after the command, spaces and all
+    let rest = command
+        .map(|c| line.trim_start()[c.len()..].trim())
+        .unwrap_or("");
+    match command {
+        None => Ok(()),
+        Some("load") if rest.is_empty() => {
+            browse(state, Purpose::Load);
+            Ok(())
+        }
+        Some("load") => load_file(state, &stats::expand_home(Path::new(rest))),
+        Some("save") if rest.is_empty() => {
+            browse(state, Purpose::Save);
+            Ok(())
+        }
+        Some("save") => save_file(state, &stats::expand_home(Path::new(rest))),
+        Some("dumpraw") if rest.is_empty() => Err("dumpraw needs a path".to_string()),
+        Some("dumpraw") => dump_raw(state, &stats::expand_home(Path::new(rest))),
+        Some("rescan") => {
+            rescan(state)?;
+            let count = state
+                .library
+                .iter()
+                .filter(|p| p.source != "built-in")
+                .count();
+            set_message(state, format!("found {} patterns", count));
+            Ok(())
+        }
+        Some("hist") => {
+            state.histogram = Some((state.game.neighbor_histogram(), state.generations));
+            Ok(())
+        }
+        Some("stamp") => {
+            let usage = "usage: stamp NAME X Y [overwrite|union|intersect|subtract|xor]";
+            // read from the end, names can have spaces
+            let mut args: Vec<&str> = words.collect();
+            let op = match args.last() {
+                Some(word) if word.parse::<usize>().is_err() => {
+                    let op = word.parse::<Combine>()?;
+                    args.pop();
+                    op
+                }
+                _ => Combine::default(),
+            };
+            let [name @ .., x, y] = &args[..] else {
+                return Err(usage.to_string());
+            };
+            let (false, Ok(x), Ok(y)) = (name.is_empty(), x.parse(), y.parse()) else {
+                return Err(usage.to_string());
+            };
+            let name = name.join(" ");
+            let pattern = state
+                .library
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| format!("no pattern named {}", name))?;
+            let grid = pattern.load()?;
+            state.game.combine(&grid, x, y, op);
+            state.update = true;
+            Ok(())
+        }
+        Some("thaw") => {
+            let count = state.game.static_regions().len();
+            state.game.clear_static_regions();
+            set_message(state, format!("thawed {} regions", count));
+            Ok(())
+        }
+        Some("rule") => {
+            let rule: CellOpts = rest
+                .parse()
+                .map_err(|e: cells::RuleParseError| e.to_string())?;
+            state.game.set_opts(rule);
+            state.update = true;
+            set_message(state, format!("rule {}", rule));
+            Ok(())
+        }
+        Some("fill") => {
+            let density = rest
+                .parse::<f32>()
+                .ok()
+                .filter(|d| (0.0..=1.0).contains(d))
+                .ok_or("usage: fill DENSITY, from 0 to 1")?;
+            state
+                .game
+                .note_nondeterminism(Nondeterminism::Entropy("fill"))
+                .map_err(|e| format!("--strict-determinism refuses {}", e))?;
+            state.game.randomize(density, &mut rand::thread_rng());
+            state.max_population = state.max_population.max(state.game.population());
+            state.update = true;
+            Ok(())
+        }
+        Some("tick") => {
+            let speed = rest
+                .parse::<f32>()
+                .ok()
+                .filter(|s| *s > 0.0)
+                .ok_or("usage: tick GENERATIONS_PER_SECOND")?;
+            state.tick_interval = tick_interval(speed, &state.opts);
+            Ok(())
+        }
+        Some("run") => {
+            state.auto_advance = true;
+            Ok(())
+        }
+        Some("pause") => {
+            state.auto_advance = false;
+            Ok(())
+        }
+        Some("step") => {
+            let n = match rest {
+                "" => 1,
+                n => n
+                    .parse::<u64>()
+                    .map_err(|_| "usage: step [N]".to_string())?,
+            };
+            for _ in 0..n {
+                step(state)
+            }
+            Ok(())
+        }
+        Some(other) => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+/// Splits a `--startup` macro into commands. They're separated by `;`, and the `:` in
+/// front of each is optional
+pub fn parse_startup(text: &str) -> Vec<String> {
+    text.split(';')
+        .map(|c| c.trim().trim_start_matches(':').trim())
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Runs the `--startup` commands in order, stopping at the first that fails
+fn run_startup(state: &mut State) -> Result<(), String> {
+    for command in state.opts.startup.clone() {
+        run_command(state, &command).map_err(|e| format!("--startup '{}': {}", command, e))?
+    }
+    Ok(())
+}
+
+fn command_key(state: &mut State, code: KeyCode) {
+    let Some(command) = &mut state.command else {
+        return;
+    };
+    match code {
+        KeyCode::Char(c) => command.push(c),
+        // backspace on an empty line closes it
+        KeyCode::Backspace if command.is_empty() => state.command = None,
+        KeyCode::Backspace => {
+            command.pop();
+        }
+        KeyCode::Enter => {
+            let line = state.command.take().unwrap_or_default();
+            if let Err(e) = run_command(state, &line) {
+                set_message(state, e)
+            }
+        }
+        KeyCode::Esc => state.command = None,
+        _ => (),
+    }
+}
+// commands }}}
+
+// brush {{{
+fn paint_value(state: &State) -> u8 {
+    state.paint.clamp(1, state.game.opts().life.max(1))
+}
+
+/// What right dragging does to a cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EraseMode {
+    /// straight to dead
+    Hard,
+    /// 1 less life per mouse event, with shift held
+    Soft,
+}
+
+/// Changes every cell within the brush radius of x, y with `f`, only redrawing if one changed
+fn brush(state: &mut State, x: usize, y: usize, f: impl Fn(u8) -> u8) {
+    let r = state.brush;
+    for cy in y.saturating_sub(r)..=y.saturating_add(r) {
+        for cx in x.saturating_sub(r)..=x.saturating_add(r) {
+            if cx.abs_diff(x).pow(2) + cy.abs_diff(y).pow(2) > r * r {
+                continue;
+            }
+            if let Some(val) = state.game.get_cell(cx, cy).filter(|c| f(*c) != *c) {
+                state.game.set_cell(cx, cy, f(val));
+                state.update = true
+            }
+        }
+    }
+}
+
+fn paint(state: &mut State, x: usize, y: usize, val: u8) {
+    brush(state, x, y, |_| val)
+}
+
+fn erase(state: &mut State, x: usize, y: usize) {
+    match state.erase_mode {
+        EraseMode::Hard => paint(state, x, y, 0),
+        EraseMode::Soft => brush(state, x, y, |c| c.saturating_sub(1)),
+    }
+}
+
+/// Adds or takes 1 life around x, y, once per cell the stroke moves onto. A new stroke
+/// starts with `down`
+fn airbrush(state: &mut State, x: usize, y: usize, down: bool, add: bool) {
+    if !down && state.stroke == Some((x, y)) {
+        return;
+    }
+    state.stroke = Some((x, y));
+    if add {
+        let life = state.game.opts().life;
+        brush(state, x, y, |c| c.saturating_add(1).min(life))
+    } else {
+        brush(state, x, y, |c| c.saturating_sub(1))
+    }
+}
+
+/// Places the picked pattern centered on x, y, cutting it out instead with shift. Alt is
+/// left to the additive brush
+fn stamp_at(state: &mut State, x: usize, y: usize, modifiers: KeyModifiers) {
+    let op = if modifiers.contains(KeyModifiers::SHIFT) {
+        Combine::Subtract
+    } else {
+        Combine::Overwrite
+    };
+    if let Some(stamp) = &state.stamp {
+        let (w, h) = (stamp.first().map(|r| r.len()).unwrap_or(0), stamp.len());
+        state
+            .game
+            .combine(stamp, x.saturating_sub(w / 2), y.saturating_sub(h / 2), op);
+        state.update = true
+    }
+}
+
+/// Plain scrolling over the cells pans up and down, ctrl left and right. Alt changes speed,
+/// alt and ctrl the brush radius, and shift the paint value
+fn wheel(state: &mut State, mevt: MouseEvent) {
+    let up = mevt.kind == MouseEventKind::ScrollUp;
+    let (alt, ctrl) = (
+        mevt.modifiers.contains(KeyModifiers::ALT),
+        mevt.modifiers.contains(KeyModifiers::CONTROL),
+    );
+    let step = if up { -1 } else { 1 };
+    if alt && ctrl {
+        state.brush = if up {
+            (state.brush + 1).min(MAX_BRUSH)
+        } else {
+            state.brush.saturating_sub(1)
+        };
+        set_message(state, format!("brush radius {}", state.brush))
+    } else if mevt.modifiers.contains(KeyModifiers::SHIFT) {
+        let (val, life) = (paint_value(state), state.game.opts().life.max(1));
+        state.paint = match (up, val) {
+            (true, v) if v >= life => 1,
+            (true, v) => v + 1,
+            (false, 1) => life,
+            (false, v) => v - 1,
+        };
+        set_message(state, format!("paint value {}/{}", state.paint, life))
+    } else if alt {
+        change_speed(state, up)
+    } else if world_at(state, mevt.column, mevt.row).is_none() {
+        // over the rulers or the split's divider
+    } else if ctrl {
+        pan(state, step, 0)
+    } else {
+        pan(state, 0, step)
+    }
+}
+// brush }}}
+
+// keyboard cursor {{{
+/// Drawn at the keyboard cursor
+const CURSOR: &str = "+";
+
+/// Turns on mouse reporting unless `--no-mouse`, falling back to the keyboard cursor when the
+/// terminal refuses. Tests hand it a backend that fails
+fn capture_mouse(guard: &mut TerminalGuard<impl Backend>, state: &mut State) {
+    if !state.opts.mouse {
+        keyboard_cursor(state, false)
+    } else if guard.enable(Mode::Mouse).is_err() {
+        keyboard_cursor(state, true)
+    }
+}
+
+/// Edits with the keyboard for terminals that can't report the mouse. `failed` when capturing
+/// it errored, rather than being turned off
+fn keyboard_cursor(state: &mut State, failed: bool) {
+    state.mouse = false;
+    // any key quits a screensaver, there's nothing to edit
+    if state.opts.screensaver {
+        return;
+    }
+    let at = (state.xy.0 / 2, state.xy.1 / 2);
+    state.cursor = Some(at);
+    state.pointer = Some(at);
+    let controls = "arrows move the cursor, space paints, backspace erases";
+    let message = if failed {
+        format!("no mouse support, {}", controls)
+    } else {
+        controls.to_string()
+    };
+    set_message(state, message)
+}
+
+/// Moves the keyboard cursor, panning once it's at the edge of the screen
+fn move_cursor(state: &mut State, dx: isize, dy: isize) {
+    let Some((x, y)) = state.cursor else {
+        return;
+    };
+    match (
+        (x as usize).checked_add_signed(dx),
+        (y as usize).checked_add_signed(dy),
+    ) {
+        (Some(x), Some(y)) if x < state.xy.0 as usize && y < state.xy.1 as usize => {
+            state.cursor = Some((x as u16, y as u16));
+            state.pointer = state.cursor;
+            state.update = true
+        }
+        _ => pan(state, dx, dy),
+    }
+}
+
+/// A click at the keyboard cursor, so keys paint exactly like the mouse does
+fn cursor_click(state: &State, button: MouseButton) -> Option<Event> {
+    let (column, row) = state.cursor?;
+    Some(Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(button),
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }))
+}
+// keyboard cursor }}}
+
+// selection {{{
+/// Left drag spans the selection, letting go ends selecting
+fn select_mouse(state: &mut State, mevt: MouseEvent) {
+    let Some(at) = world_at(state, mevt.column, mevt.row) else {
+        return;
+    };
+    match (mevt.kind, state.selection) {
+        (MouseEventKind::Down(MouseButton::Left), _) => state.selection = Some((at, at)),
+        (MouseEventKind::Drag(MouseButton::Left), Some((start, _))) => {
+            state.selection = Some((start, at))
+        }
+        (MouseEventKind::Up(MouseButton::Left), Some(_)) => state.selecting = false,
+        _ => return,
+    }
+    state.update = true
+}
+
+fn selected(state: &State) -> Option<cells::Rect> {
+    state.selection.map(|(a, b)| cells::Rect::between(a, b))
+}
+
+/// Makes the selection a static region, which `advance` never changes
+fn freeze_selection(state: &mut State) {
+    match selected(state) {
+        Some(region) => {
+            state.game.add_static_region(region);
+            state.selection = None;
+            state.selecting = false;
+            set_message(state, format!("froze {}×{}", region.w, region.h))
+        }
+        None => set_message(state, "select an area with v first".to_string()),
+    }
+    state.update = true
+}
+// selection }}}
+
+// config {{{
+/// What the config file can change, as it is now
+fn live_config(state: &State) -> Live {
+    Live {
+        color: state.opts.color_choice,
+        numeric: state.opts.numeric,
+        speed: state.opts.initial_speed,
+        bell: state.opts.bell,
+        rule: *state.game.opts(),
+    }
+}
+
+fn apply_config(state: &mut State, live: Live) {
+    if live.color != state.opts.color_choice {
+        state.opts.color_choice = live.color;
+        state.opts.color = ColorDepth::detect(live.color, |k| std::env::var(k).ok());
+        state.opts.monochrome = state.opts.color == ColorDepth::Monochrome;
+    }
+    state.opts.numeric = live.numeric;
+    if live.speed != state.opts.initial_speed {
+        state.opts.initial_speed = live.speed;
+        state.tick_interval = tick_interval(live.speed, &state.opts)
+    }
+    if live.bell != state.opts.bell {
+        state.opts.bell = live.bell;
+        state.bell = (live.bell != BellOn::Off)
+            .then(|| (BellPolicy::new(live.bell), PeriodDetector::new(MAX_PERIOD)))
+    }
+    if live.rule != *state.game.opts() {
+        state.game.set_opts(live.rule)
+    }
+    state.update = true
+}
+
+/// Every `CONFIG_POLL`, applies whatever changed in the config file since it was last read.
+/// A file that doesn't parse leaves everything as it was
+fn poll_config(state: &mut State, now: Instant) {
+    let Some((watcher, before, checked)) = &mut state.config else {
+        return;
+    };
+    if now.duration_since(*checked) < CONFIG_POLL {
+        return;
+    }
+    *checked = now;
+    if !watcher.changed() {
+        return;
+    }
+    let file = match config::load(&watcher.path) {
+        Ok(file) => file,
+        Err(e) => return set_message(state, format!("config not reloaded, {}", e)),
+    };
+    let changes = file.changed_from(before);
+    *before = file;
+    let (live, skipped) = merge(&live_config(state), &changes, state.opts.hot_reload_rules);
+    apply_config(state, live);
+    let message = if skipped.is_empty() {
+        "reloaded the config".to_string()
+    } else {
+        format!(
+            "reloaded the config, {} needs a restart or --hot-reload-rules",
+            skipped.join(" and ")
+        )
+    };
+    set_message(state, message)
+}
+// config }}}
+
+// slots {{{
+fn save_slot(state: &mut State, n: usize) {
+    let snapshot = Snapshot {
+        generation: state.generations,
+        cells: state.game.grid().to_vec(),
+        reproducible: state.game.nondeterminism().is_none(),
+    };
+    let message = match state.slots.save(n, snapshot) {
+        Ok(()) => format!("saved slot {}", n),
+        Err(e) => format!("slot {} kept in memory, couldn't write it: {}", n, e),
+    };
+    set_message(state, message)
+}
+
+/// Replaces the cells with slot `n`'s from the top left, growing the world if it doesn't fit
+fn load_slot(state: &mut State, n: usize) {
+    let snapshot = match state.slots.get(n) {
+        Ok(Some(snapshot)) => snapshot.clone(),
+        Ok(None) => return set_message(state, format!("slot {} is empty", n)),
+        Err(e) => return set_message(state, format!("couldn't read the slots: {}", e)),
+    };
+    if !snapshot.reproducible && state.game.strict_determinism() {
+        let message = format!(
+            "--strict-determinism refuses slot {}, it isn't reproducible",
+            n
+        );
+        return set_message(state, message);
+    }
+    let grid = Grid::from(snapshot.cells);
+    state.min_world = (grid.first().map(|r| r.len()).unwrap_or(0), grid.len());
+    apply_resize(state, state.xy.0, state.xy.1);
+    state.game.clear();
+    state.game.stamp(&grid, 0, 0);
+    state.game.reset_nondeterminism();
+    if !snapshot.reproducible {
+        let _ = state.game.note_nondeterminism(Nondeterminism::Inherited);
+    }
+    state.generations = snapshot.generation;
+    state.max_population = state.max_population.max(state.game.population());
+    if let Some((_, periods)) = &mut state.screensaver {
+        periods.clear()
+    }
+    set_message(state, format!("loaded slot {}", n))
+}
+// slots }}}
+
+// process_event {{{
+fn ctrl_c(kevt: &event::KeyEvent) -> bool {
+    kevt.code == KeyCode::Char('c') && kevt.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn process_event(state: &mut State, event: Event) {
+    state.input_frame = true;
+    if let Event::Mouse(mevt) = &event {
+        state.pointer = Some((mevt.column, mevt.row))
+    }
+    match event {
+        Event::Key(_) | Event::Mouse(_) if state.opts.screensaver => state.quit = true,
+        Event::Key(kevt) if state.demo.is_some() && !ctrl_c(&kevt) => demo_key(state, kevt.code),
+        Event::Key(kevt) if state.command.is_some() && !ctrl_c(&kevt) => {
+            command_key(state, kevt.code)
+        }
+        Event::Key(kevt) if state.picker.is_some() && !ctrl_c(&kevt) => {
+            picker_key(state, kevt.code)
+        }
+        Event::Key(kevt) if state.browser.is_some() && !ctrl_c(&kevt) => {
+            browser_key(state, kevt.code)
+        }
+        Event::Key(kevt) if state.histogram.is_some() && !ctrl_c(&kevt) => state.histogram = None,
+        // any input but moving the mouse closes the inspector
+        Event::Key(kevt) if state.inspect.is_some() && !ctrl_c(&kevt) => state.inspect = None,
+        Event::Mouse(mevt) if state.inspect.is_some() && mevt.kind != MouseEventKind::Moved => {
+            state.inspect = None
+        }
+        // player 2 erases with ctrl in versus
+        Event::Mouse(mevt)
+            if mevt.kind == MouseEventKind::Down(MouseButton::Right)
+                && mevt.modifiers.contains(KeyModifiers::CONTROL)
+                && state.versus.is_none() =>
+        {
+            inspect(state, mevt.column, mevt.row)
+        }
+        Event::Key(kevt) => match kevt.code {
+            KeyCode::Char('p') => state.picker = Some(Picker::new(&state.library, 0)),
+            KeyCode::Char('I') | KeyCode::Char('i') => {
+                if let Some((col, row)) = state.pointer {
+                    inspect(state, col, row)
+                }
+            }
+            KeyCode::Char(':') => state.command = Some(String::new()),
+            KeyCode::Enter if state.versus.as_ref().is_some_and(|v| v.seeding()) => {
+                start_round(state, true)
+            }
+            KeyCode::Char('r' | 'R') if state.versus.is_some() => new_round(state, false),
+            KeyCode::Char('m' | 'M') if state.versus.is_some() => new_round(state, true),
+            // a round runs by itself
+            KeyCode::Char('n' | 'a') | KeyCode::F(1..=4) if state.versus.is_some() => (),
+            KeyCode::Left if state.cursor.is_some() => move_cursor(state, -1, 0),
+            KeyCode::Right if state.cursor.is_some() => move_cursor(state, 1, 0),
+            KeyCode::Up if state.cursor.is_some() => move_cursor(state, 0, -1),
+            KeyCode::Down if state.cursor.is_some() => move_cursor(state, 0, 1),
+            KeyCode::Char(' ') | KeyCode::Backspace if state.cursor.is_some() => {
+                let button = if kevt.code == KeyCode::Char(' ') {
+                    MouseButton::Left
+                } else {
+                    MouseButton::Right
+                };
+                if let Some(click) = cursor_click(state, button) {
+                    process_event(state, click)
+                }
+            }
+            KeyCode::Left => pan(state, -1, 0),
+            KeyCode::Right => pan(state, 1, 0),
+            KeyCode::Up => pan(state, 0, -1),
+            KeyCode::Down => pan(state, 0, 1),
+            // two player rounds go by the painted cells alone
+            KeyCode::Char('n') if kevt.modifiers.contains(KeyModifiers::CONTROL) => {
+                if state.versus.is_some() {
+                    return;
+                }
+                if let Err(e) = state
+                    .game
+                    .note_nondeterminism(Nondeterminism::Entropy("noise"))
+                {
+                    return set_message(state, format!("--strict-determinism refuses {}", e));
+                }
+                let changed = state
+                    .game
+                    .apply_noise(NOISE_DENSITY, &mut rand::thread_rng());
+                state.max_population = state.max_population.max(state.game.population());
+                state.update = true;
+                set_message(state, format!("flipped {} cells", changed))
+            }
+            KeyCode::Char('n') => step(state),
+            // most terminals send ctrl+m as enter
+            KeyCode::Char('m') if kevt.modifiers.contains(KeyModifiers::CONTROL) => reroll(state),
+            KeyCode::Char('M') => reroll(state),
+            KeyCode::Char('v') => {
+                state.selecting = !state.selecting;
+                state.selection = None;
+                state.update = true
+            }
+            KeyCode::Char('F') => freeze_selection(state),
+            KeyCode::Char('V') => toggle_split(state),
+            KeyCode::BackTab => switch_half(state),
+            KeyCode::Tab if !kevt.modifiers.is_empty() => switch_half(state),
+            KeyCode::F(5) => toggle_value_plot(state),
+            KeyCode::F(6) => cycle_rulers(state),
+            KeyCode::F(9) => toggle_birth_trail(state),
+            KeyCode::F(11) => toggle_seam(state),
+            KeyCode::F(n @ 1..=4) if kevt.modifiers.contains(KeyModifiers::CONTROL) => {
+                save_slot(state, n as usize)
+            }
+            KeyCode::F(n @ 1..=4) => load_slot(state, n as usize),
+            KeyCode::Esc if state.selecting || state.selection.is_some() => {
+                state.selecting = false;
+                state.selection = None;
+                state.update = true
+            }
+            KeyCode::Char('+' | '=') => change_speed(state, true),
+            KeyCode::Char('-') => change_speed(state, false),
+            KeyCode::Char('a') => {
+                state.auto_advance = !state.auto_advance;
+            }
+            KeyCode::Char('b') => {
+                state.additive = !state.additive;
+                let mode = if state.additive {
+                    "additive brush, dragging adds or takes 1 life per cell"
+                } else {
+                    "painting brush"
+                };
+                set_message(state, mode.to_string())
+            }
+            KeyCode::Esc => state.quit = true,
+            KeyCode::Char('c') if kevt.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.quit = true
+            }
+            _ => (),
+        },
+        Event::Mouse(mevt)
+            if matches!(
+                mevt.kind,
+                MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+            ) =>
+        {
+            wheel(state, mevt)
+        }
+        Event::Mouse(mevt) if state.selecting => select_mouse(state, mevt),
+        // painting is locked once a round starts
+        Event::Mouse(_) if state.versus.as_ref().is_some_and(|v| !v.seeding()) => (),
+        Event::Mouse(mevt) => {
+            let Some((x, y)) = world_at(state, mevt.column, mevt.row) else {
+                return;
+            };
+            let val = paint_value(state);
+            // player 2 paints with the middle button or ctrl
+            let (kind, player) = match mevt.kind {
+                MouseEventKind::Down(MouseButton::Middle) if state.versus.is_some() => {
+                    (MouseEventKind::Down(MouseButton::Left), 2)
+                }
+                MouseEventKind::Drag(MouseButton::Middle) if state.versus.is_some() => {
+                    (MouseEventKind::Drag(MouseButton::Left), 2)
+                }
+                kind if mevt.modifiers.contains(KeyModifiers::CONTROL) => (kind, 2),
+                kind => (kind, 1),
+            };
+            let additive = state.additive || mevt.modifiers.contains(KeyModifiers::ALT);
+            match kind {
+                MouseEventKind::Down(MouseButton::Left) if additive => {
+                    airbrush(state, x, y, true, true);
+                    // only the stamp, a double click fill would undo the gradual adding
+                    if state
+                        .clicks
+                        .click(MouseButton::Left, mevt.column, mevt.row, Instant::now())
+                        == 3
+                    {
+                        stamp_at(state, x, y, mevt.modifiers)
+                    }
+                }
+                MouseEventKind::Drag(MouseButton::Left) if additive => {
+                    airbrush(state, x, y, false, true)
+                }
+                MouseEventKind::Down(MouseButton::Right) if additive => {
+                    airbrush(state, x, y, true, false)
+                }
+                MouseEventKind::Drag(MouseButton::Right) if additive => {
+                    airbrush(state, x, y, false, false)
+                }
+                MouseEventKind::Down(MouseButton::Left) => {
+                    paint(state, x, y, val);
+                    match state.clicks.click(
+                        MouseButton::Left,
+                        mevt.column,
+                        mevt.row,
+                        Instant::now(),
+                    ) {
+                        2 => {
+                            for (cx, cy) in (x.saturating_sub(1)..=x + 1).flat_map(|cx| {
+                                (y.saturating_sub(1)..=y + 1).map(move |cy| (cx, cy))
+                            }) {
+                                state.game.set_cell(cx, cy, val)
+                            }
+                        }
+                        3 => stamp_at(state, x, y, mevt.modifiers),
+                        _ => (),
+                    }
+                    state.update = true;
+                }
+                MouseEventKind::Drag(MouseButton::Left) => paint(state, x, y, val),
+                MouseEventKind::Drag(MouseButton::Right)
+                | MouseEventKind::Down(MouseButton::Right) => {
+                    state.erase_mode = if mevt.modifiers.contains(KeyModifiers::SHIFT) {
+                        EraseMode::Soft
+                    } else {
+                        EraseMode::Hard
+                    };
+                    erase(state, x, y)
+                }
+                _ => (),
+            }
+            if let Some(versus) = &mut state.versus {
+                versus.claim(&state.game, player)
+            }
+        }
+        Event::Resize(x, y) => state.pending_resize = Some(((x, y), Instant::now())),
+        _ => (),
+    }
+}
+// process_event }}}
+
+// title {{{
+/// The window title changes at most this often, tmux and friends redraw on every one
+const TITLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What `--title-format` and `--status-format` fill in
+fn template_values(state: &State) -> Values {
+    let population = state.game.population();
+    let meta = |key| state.game.metadata().get(key).cloned().unwrap_or_default();
+    Values {
+        gen: state.generations,
+        pop: population,
+        rule: state.game.opts().rulestring(),
+        state: if population == 0 {
+            "extinct"
+        } else if state.auto_advance {
+            "running"
+        } else {
+            "paused"
+        },
+        speed: if state.tick_interval < Duration::from_millis(1) {
+            "max".to_string()
+        } else {
+            format!("{:.1}", 1.0 / state.tick_interval.as_secs_f64())
+        },
+        // the TUI's randomness all comes from the OS
+        seed: None,
+        brush: if state.additive { "add" } else { "set" },
+        frameskip: state.frame_skip_count,
+        births: format!("{:.1}", state.sim_stats.births()),
+        deaths: format!("{:.1}", state.sim_stats.deaths()),
+        survivors: format!("{:.1}", state.sim_stats.survivors()),
+        steptime: format!("{:.3}", state.sim_stats.elapsed().as_secs_f64() * 1000.0),
+        name: meta("N"),
+        author: meta("O"),
+    }
+}
+
+/// Without `--title-format`, the pattern's name and author if it said who made it
+fn pattern_title(metadata: &HashMap<String, String>) -> Option<String> {
+    let name = metadata.get("N")?;
+    Some(match metadata.get("O") {
+        Some(author) => format!("{} by {}", name, author),
+        None => name.clone(),
+    })
+}
+
+/// The `--title-format` title when it changed and the last one has been up for
+/// `TITLE_INTERVAL`, recorded as sent
+fn due_title(state: &mut State, now: Instant) -> Option<String> {
+    let title = match &state.opts.title_format {
+        Some(format) => template::render(format, &template_values(state)),
+        // blanked once a new run forgets the pattern
+        None => match pattern_title(state.game.metadata()) {
+            Some(title) => title,
+            None if state.title.is_some() => String::new(),
+            None => return None,
+        },
+    };
+    match &state.title {
+        Some((shown, _)) if *shown == title => None,
+        Some((_, at)) if now.duration_since(*at) < TITLE_INTERVAL => None,
+        _ => {
+            state.title = Some((title.clone(), now));
+            Some(title)
+        }
+    }
+}
+// title }}}
+
+// frame {{{
+/// Whether to leave out the frame due at `now` to let the cells catch up, when it's been
+/// over two ticks since the last one. Never more than `MAX_FRAME_SKIP` in a row, never
+/// paused or at max speed, where there's no tick to fall behind, and never one showing
+/// what a key or the mouse did
+fn skip_frame(state: &mut State, now: Instant) -> bool {
+    let running = state.auto_advance && state.tick_interval >= Duration::from_millis(1);
+    let behind = running
+        && !state.input_frame
+        && state
+            .frame_at
+            .is_some_and(|at| now.saturating_duration_since(at) > 2 * state.tick_interval);
+    state.frame_at = running.then_some(now);
+    if behind && state.frame_skips < MAX_FRAME_SKIP {
+        state.frame_skips += 1;
+        state.frame_skip_count += 1;
+        true
+    } else {
+        state.frame_skips = 0;
+        false
+    }
+}
+
+/// Composes and flushes a frame, recording its timings.
+/// Built in `buffer` when there is one, otherwise queued straight into `out`.
+/// A buffered full repaint that would put back exactly what's on screen isn't sent,
+/// which is most of them when nothing moves. Nothing on screen ticks like a clock, so
+/// it's only a real change that gets through. Nothing is sent while the cells are running
+/// behind, see `skip_frame`, or while the link drains
+fn frame(
+    out: &mut CountingWriter<impl Write>,
+    buffer: Option<&mut FrameBuffer>,
+    state: &mut State,
+    stats: Option<&mut FrameStats>,
+) {
+    let (start, bytes) = (Instant::now(), out.bytes);
+    // what changed stays flagged for the next one
+    if skip_frame(state, start) || state.link.holding(start) {
+        return;
+    }
+    state.input_frame = false;
+    let full = state.update;
+    let drawn = match buffer {
+        Some(buffer) => {
+            let drawn = compose(&mut buffer.bytes, state);
+            let built = Instant::now();
+            let digest = full.then(|| {
+                let mut hasher = DefaultHasher::new();
+                buffer.bytes.hash(&mut hasher);
+                hasher.finish()
+            });
+            if drawn && digest.is_some() && digest == state.painted {
+                buffer.bytes.clear();
+                state.draw_stats.skipped += 1;
+                return;
+            }
+            if drawn {
+                // a bell alone leaves the screen as it was
+                if full || buffer.bytes != [7] {
+                    state.painted = digest
+                }
+                buffer.send(out).expect("Terminal flush fail");
+            }
+            drawn.then_some(built)
+        }
+        None => {
+            let drawn = compose(out, state);
+            let built = Instant::now();
+            if drawn {
+                out.flush().expect("Terminal flush fail");
+            }
+            drawn.then_some(built)
+        }
+    };
+    if let Some(built) = drawn {
+        if state
+            .link
+            .record(out.bytes - bytes, built.elapsed(), Instant::now())
+        {
+            // every color changes
+            state.update = true
+        }
+        state.draw_stats.record(start.elapsed());
+        if let Some(stats) = stats {
+            stats.record(built - start, built.elapsed(), out.bytes - bytes)
+        }
+    }
+}
+// frame }}}
+
+// run {{{
+/// What the world starts with besides a demo
+pub enum Start {
+    /// with what the file said about itself, see `formats::rle_metadata`
+    Pattern(Grid, HashMap<String, String>),
+    Image(Picture, ImageOpts),
+    /// random cells, already running
+    Soup,
+}
+
+/// Fails only when a `--startup` command does, after putting the terminal back
+pub fn run(
+    game: Game,
+    opts: TuiOpts,
+    demo: Option<Demo>,
+    start: Option<Start>,
+) -> Result<RunSummary, String> {
+    // dropped after stdout, so a panic still flushes frames before leaving the alternate screen
+    let mut guard = TerminalGuard::new(Crossterm(std::io::stdout()));
+    // buffered so frames only reach the terminal on flush, keeping build and flush times apart
+    let mut stdout = CountingWriter::new(BufWriter::with_capacity(1 << 16, std::io::stdout()));
+    let mut stats = opts.stats.then(FrameStats::default);
+    let mut buffer = (!opts.legacy_draw).then(FrameBuffer::default);
+
+    // Initialize
+    let size = terminal::size().unwrap_or_else(|e| {
+        eprintln!(
+            "Could not query terminal size, assuming {}x{}: {}",
+            FALLBACK_SIZE.0, FALLBACK_SIZE.1, e
+        );
+        FALLBACK_SIZE
+    });
+    guard
+        .enable(Mode::Raw)
+        .expect("Terminal could not enter raw");
+
+    let mut state = State::new(game, opts, size, demo);
+    #[cfg(unix)]
+    let winch = {
+        let flag = Arc::new(AtomicBool::new(false));
+        match signal_hook::flag::register(signal_hook::consts::SIGWINCH, Arc::clone(&flag)) {
+            Ok(id) => Some((id, flag)),
+            // stderr would land mid screen in raw mode
+            Err(e) => {
+                let message = format!("could not watch for SIGWINCH, resizes may lag: {}", e);
+                set_message(&mut state, message);
+                None
+            }
+        }
+    };
+    // the world is only sized to the terminal once there's a State,
+    // later resizes grow it without rescaling the image
+    match start {
+        Some(Start::Pattern(pattern, metadata)) => {
+            state.game.set_metadata(metadata);
+            load_pattern(&mut state, &pattern)
+        }
+        Some(Start::Image(picture, opts)) => load_image(&mut state, &picture, &opts),
+        Some(Start::Soup) => {
+            let rule = *state.game.opts();
+            soup(&mut state);
+            set_message(&mut state, format!("random rule {}", rule))
+        }
+        None => (),
+    }
+
+    for mode in [
+        Mode::AltScreen,
+        Mode::NoLineWrap,
+        Mode::HiddenCursor,
+        Mode::Title,
+    ] {
+        guard.enable(mode).expect("Terminal init fail")
+    }
+    queue!(stdout, cursor::MoveTo(state.xy.0 / 2, state.xy.1 / 2)).expect("Terminal init fail");
+    capture_mouse(&mut guard, &mut state);
+    // the guard restores the terminal on the way out
+    run_startup(&mut state)?;
+
+    state.update = true;
+    frame(&mut stdout, buffer.as_mut(), &mut state, stats.as_mut());
+
+    // Main loop
+    while !state.quit {
+        let mut timeout = schedule(&mut state, Instant::now());
+        if let Some((_, t)) = state.pending_resize {
+            timeout = timeout.min(RESIZE_DEBOUNCE.saturating_sub(t.elapsed()))
+        }
+        // so a title held back by the interval still goes out while nothing happens
+        if state.opts.title_format.is_some() {
+            timeout = timeout.min(TITLE_INTERVAL)
+        }
+
+        if let Some(evt) = try_read(timeout.as_secs_f32()) {
+            process_event(&mut state, evt)
+        }
+        #[cfg(unix)]
+        if let Some(resize) = winch
+            .as_ref()
+            .and_then(|(_, flag)| take_winch(flag, terminal::size))
+        {
+            process_event(&mut state, resize)
+        }
+        start_round(&mut state, false);
+
+        catch_up(&mut state, Instant::now());
+
+        settle_resize(&mut state, Instant::now());
+        poll_config(&mut state, Instant::now());
+
+        if state
+            .message
+            .as_ref()
+            .is_some_and(|(_, t)| t.elapsed() > MESSAGE_TIME)
+        {
+            state.message = None;
+        }
+
+        frame(&mut stdout, buffer.as_mut(), &mut state, stats.as_mut());
+        if let Some(title) = due_title(&mut state, Instant::now()) {
+            queue!(stdout, terminal::SetTitle(title)).expect("Terminal title fail");
+            stdout.flush().expect("Terminal flush fail");
+        }
+    }
+
+    // Cleanup
+    #[cfg(unix)]
+    if let Some((id, _)) = winch {
+        signal_hook::low_level::unregister(id);
+    }
+    stdout.flush().expect("Terminal flush fail");
+    for (mode, e) in guard.restore() {
+        eprintln!("Could not restore {}: {}", mode, e)
+    }
+    if let Err(e) = state.slots.flush() {
+        eprintln!("Could not save the slots: {}", e)
+    }
+
+    // stderr to keep stdout clean for pipes
+    if state.opts.show_perf_stats {
+        eprintln!("DRAW: {}", state.draw_stats());
+        eprintln!("SIM: {}", state.sim_stats())
+    }
+    if let Some(stats) = stats {
+        eprint!("{}", stats.summary())
+    }
+
+    Ok(RunSummary {
+        rule: *state.game.opts(),
+        generations: state.generations,
+        max_population: state.max_population,
+        population: state.game.population(),
+        area: state.game.size().0 * state.game.size().1,
+        stable: state.game.stable(),
+        neighbors: state.game.neighbor_histogram(),
+        guard: None,
+    })
 }
 // run }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::conway;
+    use crate::demo;
+
+    fn opts() -> TuiOpts {
+        TuiOpts {
+            numeric: false,
+            numeric_abs: false,
+            monochrome: false,
+            color: ColorDepth::TrueColor,
+            color_choice: ColorChoice::Truecolor,
+            double_click: Duration::from_millis(350),
+            edge_warn: false,
+            auto_expand: None,
+            expand_margin: 32,
+            stats: false,
+            show_perf_stats: false,
+            patterns_dir: None,
+            patterns_recursive: false,
+            versus: None,
+            fit: Fit::World,
+            screensaver: false,
+            catch_up: CatchUp::Max(5),
+            initial_speed: 10.0,
+            min_speed: 0.05,
+            max_speed: 120.0,
+            legacy_draw: false,
+            seam_preview: false,
+            slots_file: None,
+            bell: BellOn::Off,
+            config: None,
+            hot_reload_rules: false,
+            title_format: None,
+            status_format: None,
+            startup: Vec::new(),
+            mouse: true,
+            allow_b0: false,
+            target_bandwidth: None,
+            value_history: 256,
+        }
+    }
+
+    /// The first demo rule on a `w`x`h` screen with the default test options
+    fn state(w: u16, h: u16) -> State {
+        State::new(Game::new(conway()), opts(), (w, h), None)
+    }
+
+    fn key(code: KeyCode) -> Event {
+        key_with(code, KeyModifiers::NONE)
+    }
+
+    fn key_with(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(event::KeyEvent::new(code, modifiers))
+    }
+
+    fn fkey(n: u8) -> Event {
+        key(KeyCode::F(n))
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+        mouse_with(kind, column, row, KeyModifiers::NONE)
+    }
+
+    fn mouse_with(kind: MouseEventKind, column: u16, row: u16, modifiers: KeyModifiers) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers,
+        })
+    }
+
+    /// Screen positions of every `CSI row;col H` in the output, 0 indexed
+    fn cursor_moves(out: &[u8]) -> Vec<(u16, u16)> {
+        let text = String::from_utf8_lossy(out);
+        text.split("\x1b[")
+            .skip(1)
+            .filter_map(|seq| {
+                let end = seq.find(|c: char| !c.is_ascii_digit() && c != ';')?;
+                let (row, col) = seq[..end].split_once(';')?;
+                if !seq[end..].starts_with('H') {
+                    return None;
+                }
+                Some((col.parse::<u16>().ok()? - 1, row.parse::<u16>().ok()? - 1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlay_dismissal_repaints_only_its_area() {
+        let mut state = state(80, 24);
+        let shown = Overlay::boxed(20, 8, &["centered".to_string(), "overlay".to_string()], 36);
+        state.shown = vec![shown.clone()];
+
+        let mut out = Vec::new();
+        assert!(compose(&mut out, &mut state));
+
+        let moves = cursor_moves(&out);
+        assert_eq!(moves.len(), shown.rect.h as usize);
+        for (x, y) in moves {
+            assert!(x >= shown.rect.x && x < shown.rect.x + shown.rect.w);
+            assert!(y >= shown.rect.y && y < shown.rect.y + shown.rect.h);
+        }
+        assert!(state.shown.is_empty());
+
+        // nothing left to do
+        out.clear();
+        assert!(!compose(&mut out, &mut state));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn degenerate_sizes() {
+        for xy in [(0, 0), (1, 1), (0, 24), (80, 0), (2, 1)] {
+            for mode in 0..4 {
+                let opts = TuiOpts {
+                    versus: (mode == 1).then_some(10),
+                    screensaver: mode == 2,
+                    ..opts()
+                };
+                let demo = (mode == 3).then(|| Demo::new(demo::steps()));
+                let mut state = State::new(Game::new(conway()), opts, xy, demo);
+                start_scene(&mut state);
+                let mut out = CountingWriter::new(Vec::new());
+                load_pattern(&mut state, &patterns::builtins()[7].load().unwrap());
+                for evt in [
+                    mouse(MouseEventKind::Down(MouseButton::Left), 0, 0),
+                    mouse(MouseEventKind::Drag(MouseButton::Left), 500, 500),
+                    key(KeyCode::Char('n')),
+                    key(KeyCode::Right),
+                    key(KeyCode::Down),
+                    key(KeyCode::Char('p')),
+                    key(KeyCode::Down),
+                    key(KeyCode::Esc),
+                    key(KeyCode::Char(':')),
+                    key(KeyCode::Char('x')),
+                    key(KeyCode::Enter),
+                    Event::Resize(0, 0),
+                    Event::Resize(1, 1),
+                ] {
+                    process_event(&mut state, evt);
+                    step(&mut state);
+                    state.update = true;
+                    frame(&mut out, None, &mut state, None);
+                }
+                apply_resize(&mut state, 0, 0);
+                run_command(&mut state, "hist").unwrap();
+                state.update = true;
+                frame(&mut out, None, &mut state, None);
+            }
+        }
+    }
+
+    #[test]
+    fn demo_exit() {
+        let highlife: CellOpts = "B36/S23".parse().unwrap();
+        let demo = Some(Demo::new(demo::steps()));
+        let mut state = State::new(Game::new(highlife), opts(), (40, 20), demo);
+        assert_eq!(state.game.opts(), &demo::steps()[0].rule);
+
+        // leaving the tour goes back to the rule it started from
+        process_event(&mut state, key(KeyCode::Esc));
+        assert!(state.demo.is_none());
+        assert_eq!(state.game.opts(), &highlife);
+        assert_eq!(state.game.population(), 0);
+    }
+
+    #[test]
+    fn heatmap_depth() {
+        let draw = |color| {
+            let mut game = Game::new(CellOpts {
+                life: 9,
+                ..conway()
+            });
+            game.resize(20, 10);
+            for x in 0..9 {
+                game.set_cell(x, 0, x as u8 + 1)
+            }
+            let opts = TuiOpts { color, ..opts() };
+            let mut state = State::new(game, opts, (20, 10), None);
+            let mut out = Vec::new();
+            state.update = true;
+            compose(&mut out, &mut state);
+            String::from_utf8(out).unwrap()
+        };
+        let truecolor = draw(ColorDepth::TrueColor);
+        assert_eq!(truecolor.matches("\x1b[38;2;").count(), 9);
+        let ansi256 = draw(ColorDepth::Ansi256);
+        assert!(!ansi256.contains("\x1b[38;2;"));
+        assert!(ansi256.contains("\x1b[38;5;"));
+        assert!(!draw(ColorDepth::Monochrome).contains("\x1b[38;"));
+    }
+
+    #[test]
+    fn freeze_selection() {
+        let mut state = state(20, 10);
+
+        process_event(&mut state, key(KeyCode::Char('F')));
+        assert_eq!(
+            state.message.as_ref().unwrap().0,
+            "select an area with v first"
+        );
+
+        process_event(&mut state, key(KeyCode::Char('v')));
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 5, 4),
+        );
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Drag(MouseButton::Left), 2, 2),
+        );
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Up(MouseButton::Left), 2, 2),
+        );
+        // selecting never paints
+        assert_eq!(state.game.population(), 0);
+        assert!(!state.selecting);
+
+        let mut out = Vec::new();
+        state.update = true;
+        compose(&mut out, &mut state);
+        let tint = |(r, g, b)| format!("\x1b[48;2;{};{};{}m", r, g, b);
+        assert!(String::from_utf8_lossy(&out).contains(&tint(SELECTION_TINT)));
+
+        process_event(&mut state, key(KeyCode::Char('F')));
+        assert_eq!(state.message.as_ref().unwrap().0, "froze 4×3");
+        assert_eq!(
+            state.game.static_regions(),
+            [cells::Rect::between((2, 2), (5, 4))]
+        );
+        out.clear();
+        compose(&mut out, &mut state);
+        let text = String::from_utf8_lossy(&out).into_owned();
+        assert!(text.contains(&tint(STATIC_TINT)) && !text.contains(&tint(SELECTION_TINT)));
+
+        // Esc drops a selection before it quits
+        process_event(&mut state, key(KeyCode::Char('v')));
+        process_event(&mut state, key(KeyCode::Esc));
+        assert!(!state.selecting && !state.quit);
+
+        assert_eq!(run_command(&mut state, "thaw"), Ok(()));
+        assert!(state.game.static_regions().is_empty());
+    }
+
+    #[test]
+    fn slots() {
+        let message = |state: &State| state.message.as_ref().unwrap().0.clone();
+        let mut game = Game::new(conway());
+        game.resize(30, 20);
+        let mut state = State::new(game, opts(), (30, 20), None);
+        let glider = patterns::builtins().remove(0).load().unwrap();
+        state.game.stamp(&glider, 25, 15);
+        state.generations = 12;
+
+        process_event(&mut state, key_with(KeyCode::F(2), KeyModifiers::CONTROL));
+        assert_eq!(message(&state), "saved slot 2");
+        let saved = state.game.to_coords();
+        step(&mut state);
+        assert_ne!(state.game.to_coords(), saved);
+
+        // into a smaller terminal, the world keeps the snapshot's size
+        apply_resize(&mut state, 10, 10);
+        process_event(&mut state, fkey(2));
+        assert_eq!(message(&state), "loaded slot 2");
+        assert_eq!(state.game.to_coords(), saved);
+        assert_eq!(state.game.size(), (30, 20));
+        assert_eq!(state.generations, 12);
+
+        process_event(&mut state, fkey(3));
+        assert_eq!(message(&state), "slot 3 is empty");
+        assert_eq!(state.game.to_coords(), saved);
+    }
+
+    #[test]
+    fn load_and_save() {
+        let dir = std::env::temp_dir().join(format!("ti_files_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let message = |state: &State| state.message.as_ref().unwrap().0.clone();
+        let mut game = Game::new(conway());
+        game.resize(30, 20);
+        let mut state = State::new(game, opts(), (30, 20), None);
+        let glider = patterns::builtins().remove(0).load().unwrap();
+        state.game.stamp(&glider, 20, 3);
+
+        let path = dir.join("glider.rle");
+        run_command(&mut state, &format!("save {}", path.display())).unwrap();
+        assert_eq!(
+            message(&state),
+            format!("saved 5 cells to {}", path.display())
+        );
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("x = 3, y = 3, rule = B3/S23"));
+        assert!(run_command(&mut state, "save nowhere.txt").is_err());
+
+        let raw = dir.join("world.bin");
+        run_command(&mut state, &format!("dumpraw {}", raw.display())).unwrap();
+        let dumped = formats::parse_raw(&std::fs::read(&raw).unwrap()).unwrap();
+        assert_eq!(&dumped, state.game.grid());
+        assert!(run_command(&mut state, "dumpraw").is_err());
+
+        // through the browser, typing to filter
+        state.game.clear();
+        state.browser = Some(Browser::open(&dir, Purpose::Load));
+        let overlay = browser_overlay(&state, state.browser.as_ref().unwrap());
+        assert!(overlay.lines[overlay.bold[0]].contains("glider.rle"));
+        for c in "gli".chars() {
+            process_event(&mut state, key(KeyCode::Char(c)))
+        }
+        process_event(&mut state, key(KeyCode::Enter));
+        assert!(state.browser.is_none());
+        assert_eq!(state.game.population(), 5);
+        assert_eq!(message(&state), format!("loaded {}", path.display()));
+
+        // a failed load leaves the world alone
+        assert!(run_command(
+            &mut state,
+            &format!("load {}", dir.join("gone.rle").display())
+        )
+        .unwrap_err()
+        .starts_with("could not read"));
+        assert_eq!(state.game.population(), 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bell() {
+        let mut game = Game::new(conway());
+        game.resize(20, 10);
+        let opts = TuiOpts {
+            bell: BellOn::OnExtinction,
+            ..opts()
+        };
+        let mut state = State::new(game, opts, (20, 10), None);
+        state.game.set_cell(4, 4, 1);
+        let mut out = Vec::new();
+        compose(&mut out, &mut state);
+        assert!(!out.contains(&7));
+
+        step(&mut state);
+        out.clear();
+        assert!(compose(&mut out, &mut state));
+        assert_eq!(out.iter().filter(|b| **b == 7).count(), 1);
+        // rung once, then silent
+        step(&mut state);
+        out.clear();
+        compose(&mut out, &mut state);
+        assert!(!out.contains(&7));
+
+        // a bell alone is still a frame
+        state.ring = true;
+        out.clear();
+        assert!(compose(&mut out, &mut state));
+        assert_eq!(out, [7]);
+    }
+
+    #[test]
+    fn inspector() {
+        let mut game = Game::new(conway());
+        game.resize(40, 20);
+        let mut state = State::new(game, opts(), (40, 20), None);
+        let cell = world_at(&state, 10, 10).unwrap();
+        // a blinker through the cell
+        for dx in [-1, 0, 1] {
+            state
+                .game
+                .set_cell((cell.0 as isize + dx) as usize, cell.1, 1);
+        }
+
+        process_event(&mut state, key(KeyCode::Char('I')));
+        assert!(state.inspect.is_none());
+        process_event(&mut state, mouse(MouseEventKind::Moved, 10, 10));
+        process_event(&mut state, key(KeyCode::Char('I')));
+        assert_eq!(state.inspect.map(|i| i.0), Some(cell));
+        let lines = inspector_overlay(&state, cell.0, cell.1, (10, 10)).lines;
+        assert!(lines.iter().any(|l| l.contains("life 1 of 1")));
+        assert!(lines.iter().any(|l| l.contains("2 of 8 neighbors alive")));
+        assert!(lines.iter().any(|l| l.contains("next: survives")));
+        // follows the simulation
+        let above = inspector_overlay(&state, cell.0, cell.1 - 1, (10, 9)).lines;
+        assert!(above.iter().any(|l| l.contains("next: born at 1")));
+        step(&mut state);
+        let above = inspector_overlay(&state, cell.0, cell.1 - 1, (10, 9)).lines;
+        assert!(above.iter().any(|l| l.contains("next: dies")));
+
+        // moving doesn't close it, anything else does without acting
+        process_event(&mut state, mouse(MouseEventKind::Moved, 10, 10));
+        assert!(state.inspect.is_some());
+        let population = state.game.population();
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 10, 10),
+        );
+        assert!(state.inspect.is_none());
+        assert_eq!(state.game.population(), population);
+
+        process_event(
+            &mut state,
+            mouse_with(
+                MouseEventKind::Down(MouseButton::Right),
+                10,
+                10,
+                KeyModifiers::CONTROL,
+            ),
+        );
+        assert_eq!(state.inspect.map(|i| i.0), Some(cell));
+        process_event(&mut state, key(KeyCode::Char('c')));
+        assert!(state.inspect.is_none());
+    }
+
+    #[test]
+    fn strict_determinism() {
+        let mut game = Game::new(conway());
+        game.resize(40, 20);
+        let mut state = State::new(game, opts(), (40, 20), None);
+        let marked = |state: &State| {
+            overlays(state)
+                .iter()
+                .any(|o| o.lines[0] == NONDETERMINISTIC && o.rect.x + o.rect.w == 40)
+        };
+        assert!(!marked(&state));
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('n'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(
+            state.game.nondeterminism(),
+            Some(Nondeterminism::Entropy("noise"))
+        );
+        assert!(marked(&state));
+        save_slot(&mut state, 1);
+        assert_eq!(
+            state.slots.get(1).unwrap().map(|s| s.reproducible),
+            Some(false)
+        );
+
+        state.game.reset_nondeterminism();
+        state.game.set_strict_determinism(true);
+        let before = state.game.grid().clone();
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('n'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(state.game.grid(), &before);
+        assert!(state.message.as_ref().unwrap().0.contains("refuses noise"));
+        load_slot(&mut state, 1);
+        assert!(state.message.as_ref().unwrap().0.contains("refuses slot 1"));
+        assert!(!marked(&state));
+
+        state.game.set_strict_determinism(false);
+        load_slot(&mut state, 1);
+        assert_eq!(state.game.nondeterminism(), Some(Nondeterminism::Inherited));
+    }
+
+    #[test]
+    fn title_and_status() {
+        let templated = TuiOpts {
+            title_format: Some("{rule} {gen} {state}".parse().unwrap()),
+            status_format: Some("pop {pop} at {speed}/s".parse().unwrap()),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), templated, (30, 10), None);
+        state.game.stamp(&Grid::from(vec![vec![1; 3]]), 4, 4);
+        let t0 = Instant::now();
+        let rule = state.game.opts().rulestring();
+
+        assert_eq!(
+            due_title(&mut state, t0),
+            Some(format!("{} 0 paused", rule))
+        );
+        assert_eq!(due_title(&mut state, t0), None);
+        // a change waits out the interval
+        state.generations = 1;
+        assert_eq!(due_title(&mut state, t0 + TITLE_INTERVAL / 2), None);
+        assert_eq!(
+            due_title(&mut state, t0 + TITLE_INTERVAL),
+            Some(format!("{} 1 paused", rule))
+        );
+
+        let bottom = |state: &State| {
+            overlays(state)
+                .into_iter()
+                .find(|o| o.rect.y == 9)
+                .map(|o| o.lines[0].clone())
+        };
+        assert_eq!(bottom(&state).unwrap(), "pop 3 at 10.0/s");
+        set_message(&mut state, "hello".to_string());
+        assert_eq!(bottom(&state).unwrap(), "hello");
+    }
+
+    #[test]
+    fn pattern_metadata() {
+        let dir = std::env::temp_dir().join(format!("ti_metadata_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (load, save) = (dir.join("in.rle"), dir.join("out.rle"));
+        std::fs::write(
+            &load,
+            "#N Glider\n#O Richard K. Guy\n#C one\n#C two\nx = 3, y = 3\nbo$2bo$3o!\n",
+        )
+        .unwrap();
+
+        let mut state = state(30, 10);
+        let t0 = Instant::now();
+        assert_eq!(due_title(&mut state, t0), None);
+        load_file(&mut state, &load).unwrap();
+        assert_eq!(
+            due_title(&mut state, t0),
+            Some("Glider by Richard K. Guy".to_string())
+        );
+
+        save_file(&mut state, &save).unwrap();
+        let saved = std::fs::read_to_string(&save).unwrap();
+        assert_eq!(formats::rle_metadata(&saved), *state.game.metadata());
+        assert!(saved.contains("#C one\n#C two\n"), "{}", saved);
+
+        // a new run isn't the pattern any more
+        soup(&mut state);
+        assert!(state.game.metadata().is_empty());
+        assert_eq!(
+            due_title(&mut state, t0 + TITLE_INTERVAL),
+            Some(String::new())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mouse_unsupported() {
+        struct Refuses;
+        impl Write for Refuses {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("no mouse here"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut state = state(20, 10);
+        let mut works = TerminalGuard::new(Crossterm(Vec::new()));
+        capture_mouse(&mut works, &mut state);
+        assert!(state.mouse && state.cursor.is_none());
+
+        let mut refuses = TerminalGuard::new(Crossterm(Refuses));
+        capture_mouse(&mut refuses, &mut state);
+        assert!(!state.mouse);
+        assert_eq!(state.cursor, Some((10, 5)));
+        assert!(state
+            .message
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("no mouse support"));
+
+        process_event(&mut state, key(KeyCode::Right));
+        process_event(&mut state, key(KeyCode::Char(' ')));
+        let (x, y) = world_at(&state, 11, 5).unwrap();
+        assert_ne!(state.game.get_cell(x, y), Some(0));
+        assert!(overlays(&state).iter().any(|o| o.lines == [CURSOR]));
+        process_event(&mut state, key(KeyCode::Backspace));
+        assert_eq!(state.game.population(), 0);
+
+        // turned off by hand it says so without blaming the terminal
+        let no_mouse = TuiOpts {
+            mouse: false,
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), no_mouse, (20, 10), None);
+        capture_mouse(&mut refuses, &mut state);
+        assert!(state.cursor.is_some());
+        assert!(state.message.as_ref().unwrap().0.starts_with("arrows"));
+    }
+
+    #[test]
+    fn split_view() {
+        let mut state = state(21, 10);
+        state.min_world = (200, 100);
+        apply_resize(&mut state, 21, 10);
+        state.origin = (90, 45);
+
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('V'), KeyModifiers::SHIFT),
+        );
+        // two 10 wide halves around a divider at 10, both centered on 100, 50
+        assert_eq!(world_at(&state, 0, 0), Some((95, 45)));
+        assert_eq!(world_at(&state, 10, 0), None);
+        assert_eq!(world_at(&state, 11, 0), Some((95, 45)));
+        let divider = overlays(&state);
+        assert_eq!(divider[0].rect.x, 10);
+        assert_eq!(divider[0].lines[0], "◀");
+
+        // panning moves the focused half only, then the other after shift+tab
+        process_event(&mut state, key(KeyCode::Right));
+        let panned = world_at(&state, 0, 0).unwrap();
+        assert!(panned.0 > 95);
+        assert_eq!(world_at(&state, 11, 0), Some((95, 45)));
+        process_event(&mut state, key_with(KeyCode::BackTab, KeyModifiers::SHIFT));
+        assert_eq!(overlays(&state)[0].lines[0], "▶");
+        process_event(&mut state, key(KeyCode::Down));
+        assert_eq!(world_at(&state, 0, 0), Some(panned));
+        let right = world_at(&state, 11, 0).unwrap();
+        assert!(right.1 > 45);
+
+        // painting lands in the world under whichever half was clicked
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 11, 0),
+        );
+        assert_ne!(state.game.get_cell(right.0, right.1), Some(0));
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 10, 3),
+        );
+        assert_eq!(state.game.population(), 1);
+
+        // back to one view around the focused half's center
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('V'), KeyModifiers::SHIFT),
+        );
+        assert!(state.split.is_none());
+        assert_eq!(world_at(&state, 10, 5), Some((right.0 + 5, right.1 + 5)));
+    }
+
+    #[test]
+    fn reroll() {
+        let (mut state, mut strict) = (state(40, 20), state(40, 20));
+        state.generations = 30;
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('M'), KeyModifiers::SHIFT),
+        );
+        let rule = *state.game.opts();
+        assert!(!rule.is_boring());
+        assert_eq!((rule.corners, rule.life), (conway().corners, conway().life));
+        assert!(state.game.population() > 0 && state.auto_advance);
+        assert_eq!(state.generations, 0);
+        assert_eq!(
+            state.message.as_ref().unwrap().0,
+            format!("rolled {}", rule)
+        );
+        assert!(state.game.nondeterminism().is_some());
+
+        strict.game.set_strict_determinism(true);
+        process_event(
+            &mut strict,
+            key_with(KeyCode::Char('M'), KeyModifiers::SHIFT),
+        );
+        assert_eq!(strict.game.opts(), &conway());
+        assert_eq!(strict.game.population(), 0);
+    }
+
+    #[test]
+    fn config_reload() {
+        let path = std::env::temp_dir().join(format!("ti_tui_config_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"speed": 10}"#).unwrap();
+        let with_config = TuiOpts {
+            config: Some((path.clone(), config::load(&path).unwrap())),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), with_config, (20, 10), None);
+        let t0 = Instant::now();
+        let later = |s| t0 + Duration::from_secs(s);
+        let message = |state: &State| state.message.as_ref().map(|m| m.0.clone());
+
+        // sped up by hand, which a reload of other keys keeps
+        change_speed(&mut state, true);
+        let sped = state.tick_interval;
+        std::fs::write(
+            &path,
+            r#"{"speed": 10, "numeric": true, "bell": "on-extinction", "rule": "B36/S23"}"#,
+        )
+        .unwrap();
+        poll_config(&mut state, t0);
+        assert!(!state.opts.numeric);
+        poll_config(&mut state, later(3));
+        assert!(state.opts.numeric);
+        assert_eq!(state.opts.bell, BellOn::OnExtinction);
+        assert!(state.bell.is_some());
+        assert_eq!(state.tick_interval, sped);
+        assert_eq!(state.game.opts(), &conway());
+        assert_eq!(
+            message(&state).unwrap(),
+            "reloaded the config, rule needs a restart or --hot-reload-rules"
+        );
+
+        // a broken file changes nothing
+        std::fs::write(&path, r#"{"numeric": false, "speed": "#).unwrap();
+        poll_config(&mut state, later(6));
+        assert!(state.opts.numeric);
+        assert!(message(&state).unwrap().starts_with("config not reloaded"));
+
+        state.opts.hot_reload_rules = true;
+        std::fs::write(&path, r#"{"speed": 2, "rule": "B3/S2", "numeric": true}"#).unwrap();
+        poll_config(&mut state, later(9));
+        assert_eq!(state.game.opts().rulestring(), "B3/S2");
+        assert_eq!(state.tick_interval, Duration::from_millis(500));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn seam_preview() {
+        let mut flat = state(20, 12);
+        let mut game = Game::new(conway());
+        game.set_wrap(true);
+        let seam = TuiOpts {
+            seam_preview: true,
+            ..opts()
+        };
+        let mut state = State::new(game, seam, (20, 12), None);
+        assert_eq!(view_size(&state), (14, 6));
+        assert_eq!(state.game.size(), (14, 6));
+
+        // the strips hold the far side
+        assert_eq!(world_at(&state, 3, 3), Some((0, 0)));
+        assert_eq!(world_at(&state, 2, 2), Some((13, 5)));
+        assert_eq!(world_at(&state, 17, 9), Some((0, 0)));
+        assert_eq!(world_at(&state, 19, 11), Some((2, 2)));
+        assert!(on_seam(&state, 2, 5) && !on_seam(&state, 3, 5));
+
+        // and paint it
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 2, 2),
+        );
+        assert_eq!(state.game.to_coords(), [(13, 5)]);
+        let mut out = Vec::new();
+        state.update = true;
+        compose(&mut out, &mut state);
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("\x1b[48;2;48;24;48m") && text.contains("\x1b[2m"));
+
+        process_event(&mut state, fkey(11));
+        assert_eq!(view_size(&state), (20, 12));
+        assert_eq!(world_at(&state, 2, 2), Some((2, 2)));
+
+        process_event(&mut flat, fkey(11));
+        assert!(!flat.seam_preview);
+        assert_eq!(
+            flat.message.as_ref().unwrap().0,
+            "the edges don't wrap, start with --wrap"
+        );
+    }
+
+    #[test]
+    fn resize_then_draw() {
+        let mut game = Game::new(conway());
+        game.resize(40, 12);
+        game.randomize(0.5, &mut rand::thread_rng());
+        let mut state = State::new(game, opts(), (40, 12), None);
+        let mut out = Vec::new();
+        for (n, (x, y)) in [(10, 4), (200, 60), (0, 0), (1, 1), (7, 3), (80, 24)]
+            .into_iter()
+            .enumerate()
+        {
+            if n % 2 == 1 {
+                cycle_rulers(&mut state)
+            }
+            // held until the terminal stops changing size
+            process_event(&mut state, Event::Resize(x, y));
+            let t = Instant::now();
+            state.update = true;
+            assert!(!compose(&mut out, &mut state));
+            settle_resize(&mut state, t);
+            assert!(state.pending_resize.is_some());
+
+            settle_resize(&mut state, t + RESIZE_DEBOUNCE);
+            assert_eq!(state.xy, (x, y));
+            let (vw, vh) = view_size(&state);
+            let (w, h) = state.game.size();
+            assert!(
+                w >= vw && h >= vh,
+                "{}x{} world for a {}x{} view",
+                w,
+                h,
+                vw,
+                vh
+            );
+            assert!(compose(&mut out, &mut state));
+        }
+    }
+
+    #[test]
+    fn rulers() {
+        let mut state = state(40, 12);
+        // bigger than the screen, so it can pan
+        state.min_world = (100, 50);
+        apply_resize(&mut state, 40, 12);
+        let screen = |state: &mut State| {
+            let mut out = Vec::new();
+            state.update = true;
+            compose(&mut out, state);
+            String::from_utf8_lossy(&out).into_owned()
+        };
+
+        assert_eq!(view_size(&state), (40, 12));
+        process_event(&mut state, fkey(6));
+        assert_eq!(state.rulers, Rulers::Numbers);
+        assert_eq!(view_size(&state), (35, 11));
+
+        // the rulers aren't the world, and the world starts past them
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 2, 0),
+        );
+        assert_eq!(state.game.population(), 0);
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 5, 1),
+        );
+        assert_eq!(state.game.to_coords(), [(0, 0)]);
+
+        // numbers follow the world as it pans
+        pan(&mut state, 1, 1);
+        assert_eq!(world_at(&state, 5, 1), Some((4, 4)));
+        let text = screen(&mut state);
+        let top = format!("{}10{}20", " ".repeat(11), " ".repeat(8));
+        assert!(text.contains(&top), "{:?}", text);
+        assert!(text.contains("   4 ") && text.contains("  14 "));
+        assert!(!text.contains("\x1b[48;2;24;24;24m"));
+
+        process_event(&mut state, fkey(6));
+        assert!(screen(&mut state).contains("\x1b[48;2;24;24;24m"));
+        process_event(&mut state, fkey(6));
+        assert_eq!(state.rulers, Rulers::Off);
+        assert_eq!(world_at(&state, 0, 0), Some((4, 4)));
+        assert!(!screen(&mut state).contains("  14 "));
+    }
+
+    #[test]
+    fn frame_stats() {
+        let mut out = CountingWriter::new(Vec::new());
+        let mut state = state(20, 10);
+        let mut stats = FrameStats::default();
+
+        state.update = true;
+        frame(&mut out, None, &mut state, Some(&mut stats));
+        // nothing changed so no frame
+        frame(&mut out, None, &mut state, Some(&mut stats));
+        assert_eq!(stats.build.len(), 1);
+        assert_eq!(stats.bytes, [out.inner.len() as u64]);
+        assert_eq!(state.draw_stats().count, 1);
+
+        // the same bytes through a FrameBuffer, which keeps its memory
+        let mut buffered = CountingWriter::new(Vec::new());
+        let mut buffer = FrameBuffer::default();
+        state.update = true;
+        frame(
+            &mut buffered,
+            Some(&mut buffer),
+            &mut state,
+            Some(&mut stats),
+        );
+        frame(
+            &mut buffered,
+            Some(&mut buffer),
+            &mut state,
+            Some(&mut stats),
+        );
+        assert_eq!(buffered.inner, out.inner);
+        assert_eq!(stats.bytes[1], out.inner.len() as u64);
+        assert!(buffer.bytes.is_empty() && buffer.bytes.capacity() >= out.inner.len());
+
+        // repainting the same screen writes nothing, a change or a resize does
+        let sent = buffered.inner.len();
+        state.update = true;
+        frame(&mut buffered, Some(&mut buffer), &mut state, None);
+        assert_eq!(buffered.inner.len(), sent);
+        assert_eq!(state.draw_stats().skipped, 1);
+        assert!(buffer.bytes.is_empty());
+        state.game.resize(20, 10);
+        state.game.set_cell(3, 3, 1);
+        state.update = true;
+        frame(&mut buffered, Some(&mut buffer), &mut state, None);
+        assert!(buffered.inner.len() > sent);
+        let sent = buffered.inner.len();
+        apply_resize(&mut state, 20, 10);
+        state.update = true;
+        frame(&mut buffered, Some(&mut buffer), &mut state, None);
+        assert!(buffered.inner.len() > sent);
+        assert_eq!(state.draw_stats().skipped, 1);
+
+        buffer.bytes.resize(MAX_FRAME_BUFFER + 1, 0);
+        buffer.send(&mut Vec::new()).unwrap();
+        buffer.bytes.push(0);
+        buffer.send(&mut Vec::new()).unwrap();
+        assert!(buffer.bytes.capacity() <= MAX_FRAME_BUFFER / 4);
+    }
+
+    /// Prints bytes and build time of a dense 200×50 truecolor frame down both paths
+    #[test]
+    #[ignore]
+    fn frame_cost() {
+        let mut game = Game::new(CellOpts {
+            life: 9,
+            ..conway()
+        });
+        game.resize(200, 50);
+        game.randomize(0.5, &mut rand::thread_rng());
+        game.advance_n(5);
+        let mut state = State::new(game, opts(), (200, 50), None);
+        const FRAMES: u32 = 500;
+
+        for legacy in [true, false] {
+            let mut out = CountingWriter::new(BufWriter::with_capacity(1 << 16, std::io::sink()));
+            let mut buffer = (!legacy).then(FrameBuffer::default);
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                // as if every frame were different, so none are skipped
+                state.painted = None;
+                state.update = true;
+                frame(&mut out, buffer.as_mut(), &mut state, None)
+            }
+            eprintln!(
+                "{}: {} bytes, {:?} per frame",
+                if legacy { "legacy" } else { "buffered" },
+                out.bytes / FRAMES as u64,
+                start.elapsed() / FRAMES
+            );
+        }
+    }
+
+    #[test]
+    fn frameskip() {
+        let running = TuiOpts {
+            status_format: Some("{frameskip}".parse().unwrap()),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), running, (30, 10), None);
+        state.auto_advance = true;
+        let tick = state.tick_interval;
+        let t0 = Instant::now();
+
+        assert!(!skip_frame(&mut state, t0));
+        assert!(!skip_frame(&mut state, t0 + tick));
+        // falling behind skips, but only so many in a row
+        let mut at = t0 + tick;
+        let skipped: Vec<bool> = (0..MAX_FRAME_SKIP + 2)
+            .map(|_| {
+                at += 3 * tick;
+                skip_frame(&mut state, at)
+            })
+            .collect();
+        assert!(skipped[..MAX_FRAME_SKIP as usize].iter().all(|s| *s));
+        assert!(!skipped[MAX_FRAME_SKIP as usize]);
+        assert!(skipped[MAX_FRAME_SKIP as usize + 1]);
+        assert_eq!(state.frame_skip_count, MAX_FRAME_SKIP as u32 + 1);
+        // the first frame after a pause is never behind
+        state.auto_advance = false;
+        assert!(!skip_frame(&mut state, at + 10 * tick));
+        state.auto_advance = true;
+        assert!(!skip_frame(&mut state, at + 20 * tick));
+        // what a key did always goes out
+        process_event(&mut state, key(KeyCode::Null));
+        assert!(!skip_frame(&mut state, at + 30 * tick));
+
+        let bottom: Vec<Overlay> = overlays(&state)
+            .into_iter()
+            .filter(|o| o.rect.y == 9)
+            .collect();
+        assert_eq!(bottom.len(), 2);
+        let total = (MAX_FRAME_SKIP as u32 + 1).to_string();
+        assert_eq!(bottom[1].lines[0], total);
+        // shown without the template too
+        assert_eq!(bottom[0].lines[0], format!("{} frames skipped", total));
+        assert_eq!(bottom[0].rect.x + bottom[0].rect.w, 30);
+    }
+
+    #[test]
+    fn sim_stats() {
+        let with_status = TuiOpts {
+            status_format: Some("+{births} -{deaths} ={survivors}".parse().unwrap()),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), with_status, (30, 10), None);
+        assert_eq!(state.sim_stats().elapsed(), Duration::ZERO);
+        // a blinker, 2 born, 2 dead, and the middle surviving every step
+        state.game.stamp(&Grid::from(vec![vec![1; 3]]), 4, 4);
+        for _ in 0..SIM_WINDOW + 6 {
+            step(&mut state)
+        }
+        assert_eq!(state.sim_stats().steps.len(), SIM_WINDOW);
+        assert_eq!(state.sim_stats().steps[0].generation, 7);
+        assert_eq!(
+            (state.sim_stats().births(), state.sim_stats().survivors()),
+            (2.0, 1.0)
+        );
+        assert!(state.sim_stats().to_string().starts_with("last 64 steps"));
+        let status = overlays(&state)
+            .into_iter()
+            .find(|o| o.rect.y == 9)
+            .unwrap();
+        assert_eq!(status.lines[0], "+2.0 -2.0 =1.0");
+    }
+
+    #[test]
+    fn value_plot() {
+        let mut state = state(40, 16);
+        process_event(&mut state, fkey(5));
+        assert!(!state.value_plot);
+
+        let rule = CellOpts {
+            life: 3,
+            ..*state.game.opts()
+        };
+        let small = TuiOpts {
+            value_history: 4,
+            ..opts()
+        };
+        let mut state = State::new(Game::new(rule), small, (40, 16), None);
+        state.game.stamp(&Grid::from(vec![vec![3; 3]]), 4, 4);
+        // nothing is kept before the plot was ever opened
+        step(&mut state);
+        assert!(state.value_history.entries.is_empty());
+        process_event(&mut state, fkey(5));
+        process_event(&mut state, fkey(5));
+        for _ in 0..5 {
+            step(&mut state)
+        }
+        let generations: Vec<u64> = state.value_history.entries.iter().map(|e| e.0).collect();
+        assert_eq!(generations, [3, 4, 5, 6]);
+        assert!(state.value_history.entries.iter().all(|e| e.1.len() == 3));
+        // a new run starts the history over
+        state.game.reset();
+        state.game.stamp(&Grid::from(vec![vec![3; 3]]), 4, 4);
+        step(&mut state);
+        assert_eq!(state.value_history.entries.len(), 1);
+        step(&mut state);
+
+        process_event(&mut state, fkey(5));
+        let plot = overlays(&state).into_iter().find(|o| !o.colors.is_empty());
+        let plot = plot.unwrap();
+        assert!(plot.lines[1].starts_with("│ Cell values, generations 1 to 2"));
+        // one column per generation, the bigger population reaching the top
+        let population: Vec<usize> = (state.value_history.entries.iter())
+            .map(|e| e.1.iter().sum())
+            .collect();
+        assert!(population[0] < population[1]);
+        let top = format!("│ {}  █ ", population[1]);
+        assert!(plot.lines[2].starts_with(&top), "{:?}", plot.lines);
+        let (_, bottom) = plot.colors.last().unwrap();
+        assert!(bottom[4].is_some());
+
+        let mut out = Vec::new();
+        draw_overlay(&mut out, &plot, state.xy);
+        assert!(String::from_utf8_lossy(&out).contains("38;2;"));
+        process_event(&mut state, fkey(5));
+        assert!(!overlays(&state).iter().any(|o| !o.colors.is_empty()));
+    }
+
+    #[test]
+    fn link_pacing() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+        let mut link = Link::new(None);
+        for _ in 0..SLOW_AFTER - 1 {
+            assert!(!link.record(10_000, ms(100), t0));
+        }
+        assert!(!link.slow && link.hold.is_none());
+        // backed up for long enough, frames wait for the link to drain
+        assert!(link.record(10_000, ms(100), t0));
+        assert!(link.slow);
+        assert_eq!(link.measured, Some(100_000.0));
+        assert!(link.holding(t0 + ms(99)));
+        assert!(!link.holding(t0 + ms(100)));
+        // one quick frame isn't a recovery
+        assert!(!link.record(100, ms(1), t0));
+        assert!(link.slow);
+        for _ in 0..RECOVER_AFTER - 1 {
+            link.record(100, ms(1), t0);
+        }
+        assert!(!link.slow);
+        assert_eq!(link.hold, None);
+
+        // a target paces every frame, and more than it takes in SLOW_FRAME is slow
+        let mut link = Link::new(Some(1000));
+        link.record(10, ms(0), t0);
+        assert!(!link.slow);
+        assert!(link.holding(t0 + ms(9)) && !link.holding(t0 + ms(10)));
+        for _ in 0..SLOW_AFTER {
+            link.record(1000, ms(0), t0);
+        }
+        assert!(link.slow);
+
+        // the wait for input ends with the hold, and once it's past an idle loop still sleeps
+        let mut state = State::new(
+            Game::new(conway()),
+            TuiOpts {
+                target_bandwidth: Some(1000),
+                ..opts()
+            },
+            (20, 10),
+            None,
+        );
+        state.auto_advance = false;
+        state.link.record(10, ms(0), t0);
+        assert_eq!(schedule(&mut state, t0 + ms(4)), ms(6));
+        assert_eq!(schedule(&mut state, t0 + ms(10)), MAX_POLL);
+        assert_eq!(schedule(&mut state, t0 + ms(500)), MAX_POLL);
+    }
+
+    #[test]
+    fn slow_link_rows() {
+        let mut state = state(40, 12);
+        state.game.stamp(&Grid::from(vec![vec![1; 3]]), 4, 4);
+        state.link.slow = true;
+        let mut buffer = FrameBuffer::default();
+        let mut out = CountingWriter::new(Vec::new());
+        state.update = true;
+        frame(&mut out, Some(&mut buffer), &mut state, None);
+        let first = out.inner.len();
+        let rows = |bytes: &[u8]| {
+            let mut rows: Vec<u16> = cursor_moves(bytes).iter().map(|m| m.1).collect();
+            rows.sort_unstable();
+            rows.dedup();
+            rows
+        };
+        assert_eq!(rows(&out.inner), (0..12).collect::<Vec<u16>>());
+        let text = String::from_utf8_lossy(&out.inner);
+        assert!(text.contains(SLOW_LINK));
+        assert!(!text.contains("38;"), "colors while slow");
+
+        // only the changed row, and the one under the marker
+        state.game.set_cell(20, 8, 1);
+        state.update = true;
+        frame(&mut out, Some(&mut buffer), &mut state, None);
+        let y = (0..12)
+            .find(|sy| (0..40).any(|sx| world_at(&state, sx, *sy) == Some((20, 8))))
+            .unwrap();
+        assert_eq!(rows(&out.inner[first..]), [y, 11]);
+    }
+
+    #[test]
+    fn draw_stats() {
+        let ms = Duration::from_millis;
+        let mut stats = DrawStats::default();
+        assert_eq!(stats.mean(), Duration::ZERO);
+        for t in [4, 2, 6] {
+            stats.record(ms(t))
+        }
+        assert_eq!(
+            stats,
+            DrawStats {
+                count: 3,
+                sum: ms(12),
+                min: ms(2),
+                max: ms(6),
+                last: ms(6),
+                skipped: 0,
+            }
+        );
+        assert_eq!(stats.mean(), ms(4));
+    }
+
+    #[test]
+    fn braille_thumbnails() {
+        let glider = patterns::builtins().remove(0).load().unwrap();
+        assert_eq!(braille(&glider, 12, 3), ["⠬⠆"]);
+
+        let gun = patterns::builtins()
+            .into_iter()
+            .find(|p| p.name == "Gosper glider gun")
+            .unwrap()
+            .load()
+            .unwrap();
+        let thumb = braille(&gun, 12, 3);
+        assert!(thumb.len() <= 3);
+        assert!(thumb.iter().all(|l| l.chars().count() <= 12));
+        assert!(thumb.iter().any(|l| l.chars().any(|c| c != '⠀')));
+    }
+
+    #[test]
+    fn patterns_dir() {
+        let dir = std::env::temp_dir().join(format!("ti_picker_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.rle"), "bo$2bo$3o!").unwrap();
+
+        let opts = TuiOpts {
+            patterns_dir: Some(dir.clone()),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), opts, (80, 40), None);
+        let builtins = patterns::builtins().len();
+        assert_eq!(state.library.len(), builtins + 1);
+
+        // broken entries are dimmed, and picking one keeps the picker open
+        state.picker = Some(Picker::new(&state.library, builtins));
+        let overlay = picker_overlay(&state, state.picker.as_ref().unwrap());
+        assert_eq!(overlay.dim.len(), 1);
+        assert!(overlay.lines[overlay.dim[0]].contains("broken.rle"));
+        // a short terminal shows the entries around the highlighted one
+        state.xy = (80, 12);
+        let overlay = picker_overlay(&state, state.picker.as_ref().unwrap());
+        assert!(overlay.lines.len() < 12, "{:?}", overlay.lines);
+        assert!(overlay.lines[overlay.dim[0]].contains("> broken.rle"));
+        state.picker = Some(Picker::new(&state.library, 0));
+        let overlay = picker_overlay(&state, state.picker.as_ref().unwrap());
+        assert!(overlay.lines[2].contains("> "), "{:?}", overlay.lines);
+        state.picker = Some(Picker::new(&state.library, builtins));
+        state.xy = (80, 40);
+        picker_key(&mut state, KeyCode::Enter);
+        assert!(state.picker.is_some());
+        assert!(state.stamp.is_none());
+        assert!(state.message.is_some());
+
+        std::fs::write(dir.join("glider.cells"), ".O\n..O\nOOO").unwrap();
+        assert_eq!(run_command(&mut state, "rescan"), Ok(()));
+        assert_eq!(state.library.len(), builtins + 2);
+        assert!(state.picker.is_none());
+        assert!(run_command(&mut state, "bogus").is_err());
+        assert_eq!(run_command(&mut state, "hist"), Ok(()));
+        assert_eq!(state.histogram.map(|h| h.0[0]), Some(80 * 40));
+
+        state.picker = Some(Picker::new(&state.library, builtins + 1));
+        picker_key(&mut state, KeyCode::Enter);
+        assert!(state.picker.is_none());
+        assert_eq!(state.stamp.as_ref().map(|s| s.len()), Some(3));
+
+        // ctrl+c still quits over the picker
+        state.picker = Some(Picker::new(&state.library, 0));
+        process_event(
+            &mut state,
+            key_with(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        );
+        assert!(state.quit);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fit() {
+        // a 200 wide row of blocks
+        let fleet = Grid::from(vec![(0..200).map(|x| u8::from(x % 3 != 2)).collect(); 2]);
+        let population = 2 * 134;
+        let state = |fit| {
+            let opts = TuiOpts { fit, ..opts() };
+            let mut state = State::new(Game::new(conway()), opts, (80, 24), None);
+            load_pattern(&mut state, &fleet);
+            state
+        };
+
+        let clip = state(Fit::Clip);
+        assert_eq!(clip.game.size(), (80, 24));
+        assert!(clip.game.population() < population);
+
+        let mut world = state(Fit::World);
+        assert_eq!(world.game.size(), (200 + 2 * LOAD_MARGIN, 24));
+        assert_eq!(world.game.population(), population);
+        assert_eq!(world.origin, (100 + LOAD_MARGIN - 40, 0));
+        // a smaller terminal doesn't shrink the world, panning stays inside it
+        apply_resize(&mut world, 40, 12);
+        assert_eq!(world.game.population(), population);
+        pan(&mut world, 100, 0);
+        assert_eq!(world.origin.0, 200 + 2 * LOAD_MARGIN - 40);
+
+        let scale = state(Fit::Scale);
+        assert_eq!(scale.zoom, 3);
+        assert_eq!(scale.game.size(), (240, 72));
+        assert_eq!(scale.game.population(), population);
+        assert_eq!(scale.origin, (0, 0));
+        // every screen cell of the pattern's row is drawn from a 3×3 block
+        let mut out = Vec::new();
+        draw_cells(
+            &mut out,
+            &scale,
+            Rect {
+                x: 0,
+                y: 11,
+                w: 80,
+                h: 1,
+            },
+        );
+        let text = String::from_utf8_lossy(&out);
+        assert_eq!(text.matches('█').count(), 68);
+    }
+
+    #[test]
+    fn screensaver() {
+        let opts = TuiOpts {
+            screensaver: true,
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), opts, (40, 20), None);
+        assert!(state.auto_advance);
+        // an empty grid is filled right away
+        step(&mut state);
+        assert!(state.game.population() > 0);
+        set_message(&mut state, "hidden".to_string());
+        assert!(state.message.is_none());
+
+        process_event(&mut state, key(KeyCode::Char('x')));
+        assert!(state.quit);
+    }
+
+    #[test]
+    fn speed() {
+        let opts = TuiOpts {
+            initial_speed: 4.0,
+            min_speed: 0.5,
+            max_speed: 20.0,
+            ..opts()
+        };
+        let ms = Duration::from_millis;
+        assert_eq!(tick_interval(10.0, &opts), ms(100));
+        assert_eq!(tick_interval(1e6, &opts), ms(50));
+        assert_eq!(tick_interval(0.0, &opts), ms(2000));
+        // however slow the floor, slowing down stops at a day
+        let glacial = TuiOpts {
+            min_speed: 1e-40,
+            ..opts.clone()
+        };
+        assert_eq!(tick_bounds(&glacial).1, SLOWEST_TICK);
+        let mut state = state(40, 20);
+        state.opts = glacial;
+        for _ in 0..200 {
+            change_speed(&mut state, false)
+        }
+        assert_eq!(state.tick_interval, SLOWEST_TICK);
+        state.scheduler.start(Instant::now(), state.tick_interval);
+        // no wait once it's faster than a poll can tell
+        let fast = TuiOpts {
+            max_speed: 1e7,
+            ..opts.clone()
+        };
+        assert_eq!(tick_interval(1e9, &fast).as_millis(), 0);
+        let mut state = State::new(Game::new(conway()), fast, (40, 20), None);
+        for _ in 0..40 {
+            process_event(&mut state, key(KeyCode::Char('+')))
+        }
+        assert_eq!(state.tick_interval, Duration::from_nanos(100));
+        assert_eq!(state.message.as_ref().unwrap().0, "as fast as possible");
+
+        state = State::new(Game::new(conway()), opts, (40, 20), None);
+        assert_eq!(state.tick_interval, ms(250));
+        process_event(&mut state, key(KeyCode::Char('+')));
+        assert_eq!(state.tick_interval, ms(125));
+        assert_eq!(state.message.as_ref().unwrap().0, "8.0 generations/s");
+        for _ in 0..20 {
+            process_event(&mut state, key(KeyCode::Char('-')))
+        }
+        assert_eq!(state.tick_interval, ms(2000));
+        assert_eq!(state.message.as_ref().unwrap().0, "0.5 generations/s");
+        for _ in 0..20 {
+            process_event(&mut state, key(KeyCode::Char('+')))
+        }
+        assert_eq!(state.tick_interval, ms(50));
+    }
+
+    #[test]
+    fn wheel() {
+        let mut game = Game::new(CellOpts {
+            life: 9,
+            ..conway()
+        });
+        game.resize(40, 20);
+        let mut state = State::new(game, opts(), (40, 20), None);
+        let (up, down) = (MouseEventKind::ScrollUp, MouseEventKind::ScrollDown);
+
+        process_event(&mut state, mouse_with(up, 10, 10, KeyModifiers::ALT));
+        assert_eq!(state.tick_interval, Duration::from_millis(50));
+        process_event(&mut state, mouse_with(down, 10, 10, KeyModifiers::ALT));
+        assert_eq!(state.tick_interval, Duration::from_millis(100));
+
+        for _ in 0..2 {
+            process_event(
+                &mut state,
+                mouse_with(up, 10, 10, KeyModifiers::CONTROL | KeyModifiers::ALT),
+            )
+        }
+        assert_eq!(state.brush, 2);
+        assert_eq!(state.message.as_ref().unwrap().0, "brush radius 2");
+
+        // life 9, wrapping both ways
+        process_event(&mut state, mouse_with(up, 10, 10, KeyModifiers::SHIFT));
+        assert_eq!(state.paint, 1);
+        process_event(&mut state, mouse_with(down, 10, 10, KeyModifiers::SHIFT));
+        process_event(&mut state, mouse_with(down, 10, 10, KeyModifiers::SHIFT));
+        assert_eq!(state.paint, 8);
+        assert_eq!(state.message.as_ref().unwrap().0, "paint value 8/9");
+        // none of that painted
+        assert_eq!(state.game.population(), 0);
+
+        process_event(
+            &mut state,
+            mouse(MouseEventKind::Down(MouseButton::Left), 10, 10),
+        );
+        assert_eq!(state.game.population(), 13);
+        assert_eq!(state.game.get_cell(12, 10), Some(8));
+        assert_eq!(state.game.get_cell(12, 11), Some(0));
+
+        // shift right dragging takes one life per event
+        let right = MouseEventKind::Drag(MouseButton::Right);
+        for _ in 0..3 {
+            process_event(&mut state, mouse_with(right, 10, 10, KeyModifiers::SHIFT))
+        }
+        assert_eq!(state.erase_mode, EraseMode::Soft);
+        assert_eq!(state.game.get_cell(12, 10), Some(5));
+        assert_eq!(state.game.population(), 13);
+        process_event(&mut state, mouse(right, 10, 10));
+        assert_eq!(state.erase_mode, EraseMode::Hard);
+        assert_eq!(state.game.population(), 0);
+    }
+
+    #[test]
+    fn scheduler() {
+        let tick = Duration::from_millis(100);
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut scheduler = Scheduler::new(CatchUp::Max(3), t0);
+        scheduler.start(t0, tick);
+
+        assert_eq!(scheduler.timeout(at(30)), Duration::from_millis(70));
+        assert_eq!(scheduler.due(at(99), tick), 0);
+        assert_eq!(scheduler.due(at(100), tick), 1);
+        // a late frame doesn't push the cadence back
+        assert_eq!(scheduler.due(at(230), tick), 1);
+        assert_eq!(scheduler.timeout(at(230)), Duration::from_millis(70));
+        // two ticks late runs both
+        assert_eq!(scheduler.due(at(420), tick), 2);
+        assert_eq!(scheduler.due(at(500), tick), 1);
+
+        // a suspend runs the max then starts fresh
+        assert_eq!(scheduler.due(at(60_000), tick), 3);
+        assert_eq!(scheduler.due(at(60_050), tick), 0);
+        assert_eq!(scheduler.due(at(60_100), tick), 1);
+
+        let mut scheduler = Scheduler::new(CatchUp::Reanchor, t0);
+        scheduler.start(t0, tick);
+        assert_eq!(scheduler.due(at(60_000), tick), 1);
+        assert_eq!(scheduler.due(at(60_099), tick), 0);
+        assert_eq!(scheduler.due(at(60_100), tick), 1);
+
+        // waits are capped so input and messages stay live
+        scheduler.start(at(60_100), Duration::from_secs(10));
+        assert_eq!(scheduler.timeout(at(60_100)), MAX_POLL);
+
+        // an interval past what the clock holds waits a day instead of panicking
+        scheduler.start(t0, Duration::MAX);
+        assert_eq!(scheduler.due(at(100), Duration::MAX), 0);
+        assert_eq!(scheduler.next_tick, t0 + SLOWEST_TICK);
+        scheduler.next_tick = t0;
+        assert_eq!(scheduler.due(at(100), Duration::MAX), 1);
+    }
+
+    #[test]
+    fn suspended() {
+        let mut game = Game::new(conway());
+        game.resize(20, 10);
+        let mut state = State::new(game, opts(), (20, 10), None);
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+
+        assert_eq!(schedule(&mut state, t0), MAX_POLL);
+        assert_eq!(catch_up(&mut state, at(1000)), 0);
+
+        state.auto_advance = true;
+        assert_eq!(schedule(&mut state, t0), Duration::from_millis(100));
+        assert_eq!(catch_up(&mut state, at(100)), 1);
+        // stopped for a second, only 5 of the 10 missed generations are run
+        assert_eq!(catch_up(&mut state, at(1200)), 5);
+        assert_eq!(state.generations, 6);
+        assert_eq!(
+            state.message.as_ref().unwrap().0,
+            "ran 5 generations to catch up, skipped 6"
+        );
+        assert_eq!(catch_up(&mut state, at(1250)), 0);
+
+        // a new speed starts counting over
+        state.tick_interval = Duration::from_millis(50);
+        assert_eq!(schedule(&mut state, at(1260)), Duration::from_millis(50));
+        assert_eq!(catch_up(&mut state, at(1310)), 1);
+    }
+
+    #[test]
+    fn catch_up_counts() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let blinker = Grid::from(vec![vec![1; 3]]);
+
+        // nothing past the counts watching, so the generations between skip `step`
+        let mut state = state(20, 10);
+        state.game.stamp(&blinker, 4, 4);
+        state.auto_advance = true;
+        schedule(&mut state, t0);
+        assert!(!watches_generations(&state));
+        assert_eq!(catch_up(&mut state, at(500)), 5);
+        assert_eq!(state.generations, 5);
+        assert_eq!(state.sim_stats().steps.len(), 5);
+        assert_eq!(state.sim_stats().births(), 2.0);
+        assert_eq!(state.max_population, 3);
+
+        // the value history has to see each one
+        let rule = CellOpts {
+            life: 3,
+            ..*state.game.opts()
+        };
+        let mut state = State::new(Game::new(rule), opts(), (20, 10), None);
+        state.game.stamp(&blinker, 4, 4);
+        state.value_history.started = true;
+        state.auto_advance = true;
+        schedule(&mut state, t0);
+        assert!(watches_generations(&state));
+        assert_eq!(catch_up(&mut state, at(500)), 5);
+        let generations: Vec<u64> = state.value_history.entries.iter().map(|e| e.0).collect();
+        assert_eq!(generations, [1, 2, 3, 4, 5]);
+        assert_eq!(state.sim_stats().steps.len(), 5);
+    }
+
+    #[test]
+    fn catch_up_args() {
+        assert_eq!("max:5".parse(), Ok(CatchUp::Max(5)));
+        assert_eq!("reanchor".parse(), Ok(CatchUp::Reanchor));
+        assert!("max:x".parse::<CatchUp>().is_err());
+        assert!("sometimes".parse::<CatchUp>().is_err());
+    }
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline(&[0, 4, 8], 8, 10), "▁▄█");
+        // bucketed by max
+        assert_eq!(sparkline(&[0, 8, 0, 0, 4, 0], 8, 3), "█▁▄");
+        assert_eq!(sparkline(&[0, 0], 0, 3), "▁▁");
+    }
+
+    #[test]
+    fn percentiles() {
+        let data: Vec<u32> = (1..=100).collect();
+        assert_eq!(percentile(&data, 0.0), 1);
+        assert_eq!(percentile(&data, 0.5), 50);
+        assert_eq!(percentile(&data, 0.9), 90);
+        assert_eq!(percentile(&data, 0.99), 99);
+        assert_eq!(percentile(&data, 1.0), 100);
+        assert_eq!(percentile(&[7], 0.5), 7);
+        assert_eq!(percentile::<u32>(&[], 0.5), 0);
+    }
+
+    #[test]
+    fn click_streak() {
+        let mut clicks = ClickTracker::new(Duration::from_millis(350));
+        let t = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t), 1);
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t + ms(100)), 2);
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t + ms(200)), 3);
+        // streak wraps after a triple
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t + ms(300)), 1);
+    }
+
+    #[test]
+    fn click_breaks() {
+        let mut clicks = ClickTracker::new(Duration::from_millis(350));
+        let t = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t), 1);
+        // too slow
+        assert_eq!(clicks.click(MouseButton::Left, 4, 4, t + ms(400)), 1);
+        // moved
+        assert_eq!(clicks.click(MouseButton::Left, 5, 4, t + ms(500)), 1);
+        // other buttons keep their own streaks
+        assert_eq!(clicks.click(MouseButton::Right, 5, 4, t + ms(550)), 1);
+        assert_eq!(clicks.click(MouseButton::Left, 5, 4, t + ms(600)), 2);
+    }
+
+    #[test]
+    fn stamp_ops() {
+        let mut state = state(40, 20);
+        assert_eq!(run_command(&mut state, "stamp glider 2 3"), Ok(()));
+        assert_eq!(state.game.population(), 5);
+        assert_eq!(run_command(&mut state, "stamp Glider 2 3 xor"), Ok(()));
+        assert_eq!(state.game.population(), 0);
+        assert_eq!(
+            run_command(&mut state, "stamp gosper glider gun 0 0"),
+            Ok(())
+        );
+        assert_eq!(state.game.population(), 36);
+        assert!(run_command(&mut state, "stamp glider 2 3 sideways").is_err());
+        assert!(run_command(&mut state, "stamp glider 2").is_err());
+        assert!(run_command(&mut state, "stamp 2 3").is_err());
+        assert!(run_command(&mut state, "stamp nothing 2 3").is_err());
+
+        // shift on the third click punches the pattern out instead
+        state.game.edit(|view| view.fill(1));
+        let total = state.game.population();
+        state.stamp = Some(Grid::from(vec![vec![1; 3]; 3]));
+        let click = mouse_with(
+            MouseEventKind::Down(MouseButton::Left),
+            10,
+            5,
+            KeyModifiers::SHIFT,
+        );
+        for _ in 0..3 {
+            process_event(&mut state, click.clone())
+        }
+        assert_eq!(state.game.population(), total - 9);
+        let (x, y) = world_at(&state, 10, 5).unwrap();
+        assert_eq!(state.game.get_cell(x, y), Some(0));
+
+        // while alt airbrushes, and its third click places the pattern like any other
+        state.game.edit(|view| view.fill(0));
+        let click = mouse_with(
+            MouseEventKind::Down(MouseButton::Left),
+            10,
+            5,
+            KeyModifiers::ALT,
+        );
+        for _ in 0..3 {
+            process_event(&mut state, click.clone())
+        }
+        for (dx, dy) in (0..3).flat_map(|dx| (0..3).map(move |dy| (dx, dy))) {
+            assert_eq!(state.game.get_cell(x + dx - 1, y + dy - 1), Some(1));
+        }
+    }
+
+    #[test]
+    fn absolute_digits() {
+        assert_eq!(absolute_digit(9, 16), '9');
+        assert_eq!(absolute_digit(12, 16), 'c');
+        assert_eq!(absolute_digit(16, 16), '0');
+        assert_eq!(absolute_digit(17, 100), '7');
+        assert_eq!(absolute_digit(255, 255), '5');
+
+        let mut rule = conway();
+        rule.life = 12;
+        let opts = TuiOpts {
+            numeric: true,
+            numeric_abs: true,
+            ..opts()
+        };
+        let state = State::new(Game::new(rule), opts, (40, 20), None);
+        assert_eq!(plain_glyph(&state, 11), "b");
+        assert_eq!(plain_glyph(&state, 0), " ");
+    }
+
+    #[test]
+    fn additive_brush() {
+        let mut rule = conway();
+        rule.life = 16;
+        let templated = TuiOpts {
+            status_format: Some("{brush}".parse().unwrap()),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(rule), templated, (40, 20), None);
+        let toggle = key(KeyCode::Char('b'));
+        let (left, right) = (MouseButton::Left, MouseButton::Right);
+        let (x, y) = world_at(&state, 10, 5).unwrap();
+
+        process_event(&mut state, toggle.clone());
+        assert!(state.additive);
+        state.message = None;
+        assert_eq!(overlays(&state).last().unwrap().lines[0], "add");
+
+        // resting on a cell adds once, coming back to it adds again
+        process_event(&mut state, mouse(MouseEventKind::Down(left), 10, 5));
+        for column in [10, 10, 10, 11, 10] {
+            process_event(&mut state, mouse(MouseEventKind::Drag(left), column, 5))
+        }
+        assert_eq!(state.game.get_cell(x, y), Some(2));
+        assert_eq!(state.game.get_cell(x + 1, y), Some(1));
+        process_event(&mut state, mouse(MouseEventKind::Down(right), 10, 5));
+        process_event(&mut state, mouse(MouseEventKind::Drag(right), 10, 5));
+        assert_eq!(state.game.get_cell(x, y), Some(1));
+
+        // capped at life
+        for _ in 0..20 {
+            process_event(&mut state, mouse(MouseEventKind::Down(left), 11, 5));
+            state.clicks = ClickTracker::new(Duration::from_millis(350));
+        }
+        assert_eq!(state.game.get_cell(x + 1, y), Some(16));
+
+        // alt does it for one stroke without the mode
+        process_event(&mut state, toggle.clone());
+        state.message = None;
+        assert_eq!(overlays(&state).last().unwrap().lines[0], "set");
+        process_event(
+            &mut state,
+            mouse_with(MouseEventKind::Down(left), 12, 5, KeyModifiers::ALT),
+        );
+        assert_eq!(state.game.get_cell(x + 2, y), Some(1));
+        process_event(&mut state, mouse(MouseEventKind::Down(left), 13, 5));
+        assert_eq!(state.game.get_cell(x + 3, y), Some(16));
+    }
+
+    #[test]
+    fn wheel_pans() {
+        let mut state = state(40, 20);
+        state.min_world = (100, 50);
+        apply_resize(&mut state, 40, 20);
+        state.origin = (0, 0);
+        let speed = state.tick_interval;
+        let (up, down) = (MouseEventKind::ScrollUp, MouseEventKind::ScrollDown);
+
+        // nothing above or left of the world to show
+        process_event(&mut state, mouse(up, 10, 10));
+        process_event(&mut state, mouse_with(up, 10, 10, KeyModifiers::CONTROL));
+        assert_eq!(state.origin, (0, 0));
+        process_event(&mut state, mouse(down, 10, 10));
+        assert_eq!(state.origin, (0, PAN_STEP));
+        process_event(&mut state, mouse_with(down, 10, 10, KeyModifiers::CONTROL));
+        assert_eq!(state.origin, (PAN_STEP, PAN_STEP));
+
+        // and nothing past the bottom right
+        let (vw, vh) = view_size(&state);
+        for _ in 0..100 {
+            process_event(&mut state, mouse(down, 10, 10));
+            process_event(&mut state, mouse_with(down, 10, 10, KeyModifiers::CONTROL));
+        }
+        assert_eq!(state.origin, (100 - vw, 50 - vh));
+        process_event(&mut state, mouse(up, 10, 10));
+        assert_eq!(state.origin, (100 - vw, 50 - vh - PAN_STEP));
+        assert_eq!(state.tick_interval, speed);
+    }
+
+    #[test]
+    fn resize_between_frames() {
+        let mut game = Game::new(conway());
+        game.resize(60, 20);
+        game.randomize(0.5, &mut rand::thread_rng());
+        let mut state = State::new(game, opts(), (60, 20), None);
+        cycle_rulers(&mut state);
+        let mut buffer = FrameBuffer::default();
+        for buffered in [false, true] {
+            let mut out = CountingWriter::new(Vec::new());
+            let mut draw = |state: &mut State, out: &mut CountingWriter<Vec<u8>>| {
+                frame(out, buffered.then_some(&mut buffer), state, None)
+            };
+            process_event(&mut state, Event::Resize(60, 20));
+            settle_resize(&mut state, Instant::now() + RESIZE_DEBOUNCE);
+            draw(&mut state, &mut out);
+            let sent = out.inner.len();
+            assert!(sent > 0);
+
+            // anything drawn while the size is moving would be laid out for the old one
+            let t = Instant::now();
+            for (x, y) in [(30, 8), (90, 30), (14, 5)] {
+                process_event(&mut state, Event::Resize(x, y));
+                set_message(
+                    &mut state,
+                    "a message wider than the final screen".to_string(),
+                );
+                state.update = true;
+                draw(&mut state, &mut out);
+                settle_resize(&mut state, t);
+                draw(&mut state, &mut out);
+            }
+            assert_eq!(out.inner.len(), sent);
+            // drawn once it settles, and an event repeating the size it's at holds nothing back
+            settle_resize(&mut state, Instant::now() + RESIZE_DEBOUNCE);
+            process_event(&mut state, Event::Resize(14, 5));
+            draw(&mut state, &mut out);
+            assert!(out.inner.len() > sent);
+
+            let moved = cursor_moves(&out.inner[sent..]);
+            assert!(!moved.is_empty());
+            for (x, y) in moved {
+                assert!(x < 14 && y < 5, "moved to {}, {} on 14x5", x, y)
+            }
+        }
+    }
+
+    #[test]
+    fn numeric_colors() {
+        let state = |life, numeric| {
+            let rule = CellOpts { life, ..conway() };
+            let opts = TuiOpts {
+                color: ColorDepth::TrueColor,
+                numeric,
+                ..opts()
+            };
+            State::new(Game::new(rule), opts, (20, 10), None)
+        };
+        let rgb = |color| match color {
+            Some(Color::Rgb { r, g, b }) => (r, g, b),
+            other => panic!("{:?}", other),
+        };
+        for life in [1, 5, 9, 20] {
+            let (digits, blocks) = (state(life, true), state(life, false));
+            assert_eq!(glyph(&digits, 0, 0, 0), (" ".to_string(), None));
+            assert_eq!(glyph(&blocks, 0, 0, 0), (" ".to_string(), None));
+
+            let (text, color) = glyph(&digits, 1, 0, 0);
+            assert_eq!(text, if life > 9 { "0" } else { "1" });
+            assert!(color::luminance(rgb(color)) >= color::DIGIT_LUMINANCE - 0.01);
+            let (text, color) = glyph(&digits, life, 0, 0);
+            assert_eq!(text, life.min(9).to_string());
+            // the bright end reads fine already, so the digit matches the block
+            assert_eq!(color, glyph(&blocks, life, 0, 0).1);
+            assert_eq!(rgb(color), color::heat(1.0));
+        }
+        // only the digits are lifted
+        let (text, color) = glyph(&state(20, false), 1, 0, 0);
+        assert_eq!(text, "█");
+        assert_eq!(rgb(color), color::heat(1.0 / 20.0));
+
+        // 19 and 20 are both a 9, but not the same color
+        let mut state = state(20, true);
+        state.game.resize(20, 10);
+        state.game.set_cell(2, 2, 19);
+        let (mut out, mut buffer) = (CountingWriter::new(Vec::new()), FrameBuffer::default());
+        state.update = true;
+        frame(&mut out, Some(&mut buffer), &mut state, None);
+        let sent = out.inner.len();
+        state.game.set_cell(2, 2, 20);
+        state.update = true;
+        frame(&mut out, Some(&mut buffer), &mut state, None);
+        assert!(out.inner.len() > sent);
+        assert_eq!(state.draw_stats().skipped, 0);
+    }
+
+    #[test]
+    fn startup_macro() {
+        assert_eq!(
+            parse_startup(":rule B36/S23; :fill 0.3;; step 3 ;pause; "),
+            ["rule B36/S23", "fill 0.3", "step 3", "pause"]
+        );
+        assert!(parse_startup(" ; ").is_empty());
+
+        let opts = TuiOpts {
+            startup: parse_startup(":rule B36/S23; :fill 0.3; :tick 40; step 2; run"),
+            ..opts()
+        };
+        let mut state = State::new(Game::new(conway()), opts, (20, 10), None);
+        state.auto_advance = false;
+        assert_eq!(run_startup(&mut state), Ok(()));
+        assert_eq!(state.game.opts().rulestring(), "B36/S23");
+        assert_eq!(state.generations, 2);
+        assert_eq!(state.game.generation(), 2);
+        assert_eq!(state.tick_interval, Duration::from_millis(25));
+        assert!(state.auto_advance && state.max_population > 0);
+
+        // stops at the one that fails, and names it
+        state.opts.startup = parse_startup("pause; fill 2; step");
+        let err = run_startup(&mut state).unwrap_err();
+        assert!(err.contains("'fill 2'"), "{}", err);
+        assert!(!state.auto_advance);
+        assert_eq!(state.generations, 2);
+        for bad in ["rule S23", "tick 0", "step x", "frobnicate"] {
+            assert!(run_command(&mut state, bad).is_err(), "{}", bad)
+        }
+        state.game.set_strict_determinism(true);
+        assert!(run_command(&mut state, "fill 0.5").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn winch() {
+        let flag = Arc::new(AtomicBool::new(false));
+        assert_eq!(take_winch(&flag, || unreachable!()), None);
+        let id =
+            signal_hook::flag::register(signal_hook::consts::SIGWINCH, Arc::clone(&flag)).unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGWINCH).unwrap();
+        assert!(signal_hook::low_level::unregister(id));
+        assert_eq!(
+            take_winch(&flag, || Ok((90, 30))),
+            Some(Event::Resize(90, 30))
+        );
+        // once per signal
+        assert_eq!(take_winch(&flag, || Ok((1, 1))), None);
+        // a size that can't be read leaves it to crossterm's event
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(
+            take_winch(&flag, || Err(std::io::Error::other("no tty"))),
+            None
+        );
+
+        // and the synthesized event goes the same way a real one does
+        let mut state = state(20, 10);
+        flag.store(true, Ordering::Relaxed);
+        process_event(&mut state, take_winch(&flag, || Ok((30, 12))).unwrap());
+        assert_eq!(terminal_size(&state), (30, 12));
+        settle_resize(&mut state, Instant::now() + RESIZE_DEBOUNCE);
+        assert_eq!(state.xy, (30, 12));
+    }
+
+    #[test]
+    fn birth_trail() {
+        let mut game = Game::new(conway());
+        game.resize(20, 10);
+        for x in 4..7 {
+            game.set_cell(x, 4, 1)
+        }
+        let mut state = State::new(game, opts(), (20, 10), None);
+        let before = glyph(&state, 1, 5, 3).1;
+        process_event(&mut state, fkey(9));
+        step(&mut state);
+        let trail = state.birth_trail.as_ref().unwrap();
+        assert_eq!(trail.at(5, 3), Some((Side::Down, 0)));
+        assert_eq!(trail.at(5, 5), Some((Side::Up, 0)));
+        assert_eq!(trail.at(5, 4), None);
+        assert_eq!(trail.at(50, 3), None);
+        let rgb = |(x, y)| glyph(&state, state.game.get_cell(x, y).unwrap(), x, y).1;
+        assert_eq!(
+            rgb((5, 3)),
+            color_depth(&state).color(side_color(Side::Down))
+        );
+        assert_ne!(rgb((5, 3)), before);
+
+        // each blinker phase's newborns die the step after, so nothing lasts to fade
+        step(&mut state);
+        let trail = state.birth_trail.as_ref().unwrap();
+        assert_eq!(trail.at(5, 3), None);
+        assert_eq!(trail.at(4, 4), Some((Side::Right, 0)));
+        // a still life ages out
+        state.game.clear();
+        for (x, y) in [(1, 1), (2, 1), (1, 2)] {
+            state.game.set_cell(x, y, 1)
+        }
+        step(&mut state);
+        assert_eq!(
+            state.birth_trail.as_ref().unwrap().at(2, 2),
+            Some((Side::Left, 0))
+        );
+        for age in 1..BIRTH_FADE {
+            step(&mut state);
+            assert_eq!(
+                state.birth_trail.as_ref().unwrap().at(2, 2),
+                Some((Side::Left, age))
+            );
+        }
+        step(&mut state);
+        assert_eq!(state.birth_trail.as_ref().unwrap().at(2, 2), None);
+        assert_eq!(glyph(&state, 1, 2, 2).1, before);
+
+        process_event(&mut state, fkey(9));
+        assert!(state.birth_trail.is_none() && state.game.birth_sides().is_none());
+    }
+}
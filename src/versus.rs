@@ -0,0 +1,272 @@
+//! Two player mode, run with `--versus`.
+//!
+//! Both players paint during a timed seeding phase, then the simulation runs
+//! untouched for a set number of generations and the bigger population wins.
+//! Ownership is tracked beside the game: a newborn cell belongs to whichever
+//! player owns most of its live neighbors, a tie leaves it neutral.
+
+use std::time::{Duration, Instant};
+
+use super::cells::{self, Game, Grid, StepInfo};
+
+/// How long both players get to paint
+pub const SEED_TIME: Duration = Duration::from_secs(20);
+
+/// Owner of a neutral or dead cell
+pub const NOBODY: u8 = 0;
+
+// Phase {{{
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// paused, both players painting until the deadline
+    Seeding {
+        until: Instant,
+    },
+    /// painting locked, generations left to run
+    Running {
+        remaining: u64,
+    },
+    Results,
+}
+// Phase }}}
+
+// Versus {{{
+#[derive(Clone, Debug)]
+pub struct Versus {
+    pub phase: Phase,
+    /// generations a round runs for
+    pub generations: u64,
+    /// player 1 and 2 trade colors on a rematch
+    pub swapped: bool,
+    /// 1 or 2 for each owned live cell, else `NOBODY`
+    owners: Grid,
+    /// each player's population after every generation
+    history: [Vec<usize>; 2],
+}
+
+impl Versus {
+    pub fn new(generations: u64, now: Instant) -> Self {
+        Self {
+            phase: Phase::Seeding {
+                until: now + SEED_TIME,
+            },
+            generations,
+            swapped: false,
+            owners: Grid::new(),
+            history: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// Back to seeding with the same colors
+    pub fn restart(&mut self, now: Instant) {
+        *self = Self {
+            swapped: self.swapped,
+            ..Self::new(self.generations, now)
+        }
+    }
+
+    /// Back to seeding with the colors swapped
+    pub fn rematch(&mut self, now: Instant) {
+        self.restart(now);
+        self.swapped = !self.swapped;
+    }
+
+    pub fn seeding(&self) -> bool {
+        matches!(self.phase, Phase::Seeding { .. })
+    }
+
+    pub fn owner(&self, x: usize, y: usize) -> u8 {
+        self.owners.get_cell(x, y).unwrap_or(NOBODY)
+    }
+
+    /// Gives every unowned live cell to `player` and forgets dead ones. Called after painting
+    pub fn claim(&mut self, game: &Game, player: u8) {
+        let (w, h) = game.size();
+        self.owners.resize(w, h);
+        for (row, owners) in game.grid().iter().zip(self.owners.iter_mut()) {
+            for (cell, owner) in row.iter().zip(owners.iter_mut()) {
+                if *cell == 0 {
+                    *owner = NOBODY
+                } else if *owner == NOBODY {
+                    *owner = player
+                }
+            }
+        }
+    }
+
+    /// Ends seeding early, or on time once `until` passes. True if the round just started
+    pub fn start(&mut self, now: Instant, early: bool) -> bool {
+        match self.phase {
+            Phase::Seeding { until } if early || now >= until => {
+                self.phase = Phase::Running {
+                    remaining: self.generations,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates ownership and scores after the step `info` described. True if the round just
+    /// ended
+    pub fn record(&mut self, info: &StepInfo, game: &Game) -> bool {
+        let Phase::Running { remaining } = self.phase else {
+            return false;
+        };
+        let (w, h) = game.size();
+        self.owners.resize(w, h);
+        // cells can't be drawn on mid round, so nothing changing leaves the owners as well
+        if info.changed > 0 {
+            self.claim_births(game)
+        }
+
+        let scores = self.scores();
+        self.history[0].push(scores[0]);
+        self.history[1].push(scores[1]);
+
+        let remaining = remaining.saturating_sub(1);
+        self.phase = if remaining == 0 {
+            Phase::Results
+        } else {
+            Phase::Running { remaining }
+        };
+        self.phase == Phase::Results
+    }
+
+    /// Forgets the owners of dead cells and gives each newborn to the player owning more of
+    /// its neighbors, nobody on a tie
+    fn claim_births(&mut self, game: &Game) {
+        let (shape, radius) = (game.opts().shape(), game.opts().radius);
+        let before = self.owners.clone();
+        for (y, row) in game.grid().iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let owner = &mut self.owners[y][x];
+                if *cell == 0 {
+                    *owner = NOBODY
+                } else if *owner == NOBODY {
+                    let mut votes = [0; 2];
+                    for (nx, ny) in cells::neighborhood(x, y, shape, radius) {
+                        match before.get_cell(nx, ny) {
+                            Some(1) => votes[0] += 1,
+                            Some(2) => votes[1] += 1,
+                            _ => (),
+                        }
+                    }
+                    *owner = match votes[0].cmp(&votes[1]) {
+                        std::cmp::Ordering::Greater => 1,
+                        std::cmp::Ordering::Less => 2,
+                        std::cmp::Ordering::Equal => NOBODY,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Live cells owned by player 1 and 2
+    pub fn scores(&self) -> [usize; 2] {
+        let count = |p| self.owners.iter().flatten().filter(|o| **o == p).count();
+        [count(1), count(2)]
+    }
+
+    /// 1 or 2, or `None` on a draw
+    pub fn winner(&self) -> Option<u8> {
+        let [one, two] = self.scores();
+        match one.cmp(&two) {
+            std::cmp::Ordering::Greater => Some(1),
+            std::cmp::Ordering::Less => Some(2),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn history(&self, player: u8) -> &[usize] {
+        &self.history[usize::from(player == 2)]
+    }
+}
+// Versus }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::conway;
+
+    #[test]
+    fn phases() {
+        let now = Instant::now();
+        let mut game = Game::new(conway());
+        game.resize(10, 10);
+        let mut versus = Versus::new(2, now);
+
+        assert!(!versus.start(now, false));
+        assert!(versus.seeding());
+        // nothing happens while seeding
+        assert!(!versus.record(&StepInfo::default(), &game));
+
+        assert!(versus.start(now + SEED_TIME, false));
+        assert_eq!(versus.phase, Phase::Running { remaining: 2 });
+        assert!(!versus.record(&StepInfo::default(), &game));
+        assert!(versus.record(&StepInfo::default(), &game));
+        assert_eq!(versus.phase, Phase::Results);
+        assert_eq!(versus.history(1).len(), 2);
+
+        versus.rematch(now);
+        assert!(versus.seeding());
+        assert!(versus.swapped);
+        assert!(versus.history(1).is_empty());
+        versus.restart(now);
+        assert!(versus.swapped);
+    }
+
+    #[test]
+    fn births_follow_the_majority() {
+        let mut game = Game::new(conway());
+        game.resize(10, 10);
+        let mut versus = Versus::new(10, Instant::now());
+
+        // player 1's blinker
+        for x in 1..4 {
+            game.set_cell(x, 1, 1)
+        }
+        versus.claim(&game, 1);
+        // player 2's block, far away
+        for (x, y) in [(6, 6), (7, 6), (6, 7), (7, 7)] {
+            game.set_cell(x, y, 1)
+        }
+        versus.claim(&game, 2);
+        assert_eq!(versus.scores(), [3, 4]);
+
+        versus.start(Instant::now(), true);
+        let info = game.advance();
+        versus.record(&info, &game);
+        // the blinker's new cells are born from player 1's
+        assert_eq!(versus.owner(2, 0), 1);
+        assert_eq!(versus.owner(2, 2), 1);
+        assert_eq!(versus.owner(1, 1), NOBODY);
+        assert_eq!(versus.scores(), [3, 4]);
+        assert_eq!(versus.winner(), Some(2));
+    }
+
+    #[test]
+    fn ties_are_neutral() {
+        // B2/S, where everything dies and a birth can split evenly
+        let mut rule = conway();
+        rule.grow = [false; cells::COUNTS];
+        rule.grow[2] = true;
+        rule.survive = [false; cells::COUNTS];
+        let mut game = Game::new(rule);
+        game.resize(5, 5);
+        let mut versus = Versus::new(10, Instant::now());
+
+        game.set_cell(0, 0, 1);
+        versus.claim(&game, 1);
+        game.set_cell(2, 0, 1);
+        versus.claim(&game, 2);
+
+        versus.start(Instant::now(), true);
+        let info = game.advance();
+        versus.record(&info, &game);
+        assert_eq!(game.get_cell(1, 0), Some(1));
+        assert_eq!(versus.owner(1, 0), NOBODY);
+        assert_eq!(versus.scores(), [0, 0]);
+        assert_eq!(versus.winner(), None);
+    }
+}